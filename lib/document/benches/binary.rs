@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use trax_document::Document;
+
+const SRC: &str = include_str!("../tests/testfiles/todo.trax");
+
+fn bench(c: &mut Criterion) {
+    let parsed_todo = Document::new(SRC).unwrap();
+    let encoded_todo = parsed_todo.encode();
+
+    println!(
+        "todo: {} bytes as text, {} bytes encoded ({:.0}%)",
+        SRC.len(),
+        encoded_todo.len(),
+        encoded_todo.len() as f64 / SRC.len() as f64 * 100.0
+    );
+
+    let mut binary_encode = c.benchmark_group("binary_encode");
+
+    binary_encode.bench_function("todo", |b| b.iter(|| black_box(&parsed_todo).encode()));
+    binary_encode.bench_function("todo_text", |b| {
+        b.iter(|| black_box(&parsed_todo).into_string())
+    });
+
+    binary_encode.finish();
+
+    let mut binary_decode = c.benchmark_group("binary_decode");
+
+    binary_decode.bench_function("todo", |b| {
+        b.iter(|| Document::decode(black_box(&encoded_todo)))
+    });
+    binary_decode.bench_function("todo_text", |b| b.iter(|| Document::new(black_box(SRC))));
+
+    binary_decode.finish();
+
+    let mut binary_round_trip = c.benchmark_group("binary_round_trip");
+
+    binary_round_trip.bench_function("todo", |b| {
+        b.iter(|| Document::decode(&Document::new(black_box(SRC)).unwrap().encode()).is_ok())
+    });
+
+    binary_round_trip.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = bench
+);
+
+criterion_main!(benches);
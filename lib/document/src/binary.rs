@@ -0,0 +1,434 @@
+use std::{borrow::Cow, collections::HashMap, str::Utf8Error};
+
+use thiserror::Error;
+use trax_parser::Tokenizer;
+
+use crate::{
+    Attribute, Comment, Document, DocumentParseError, Element, EntityRef, Fragment, Name, NodeId,
+    Text,
+};
+
+const MAGIC: &[u8; 4] = b"TRAX";
+
+/// The version of the binary format written by [`Document::encode`] and [`encode_message`].
+pub const BINARY_VERSION: u8 = 2;
+
+const KIND_DOCUMENT: u8 = 0;
+const KIND_MESSAGE: u8 = 1;
+
+// The low bits of each node's first integer.
+const TAG_BITS: u32 = 2;
+const TAG_ELEMENT: u64 = 0;
+const TAG_TEXT: u64 = 1;
+const TAG_COMMENT: u64 = 2;
+const TAG_MASK: u64 = (1 << TAG_BITS) - 1;
+
+/// An error encountered when decoding the binary format.
+#[derive(Debug, Error, PartialEq)]
+pub enum DecodeError {
+    /// The input doesn't start with the `TRAX` magic bytes.
+    #[error("not a binary TRAX document")]
+    InvalidMagic,
+
+    /// The input was written by an incompatible version of the format.
+    #[error("unsupported binary version {0}, expected {BINARY_VERSION}")]
+    UnsupportedVersion(u8),
+
+    /// The input holds a document where a message was expected, or vice versa.
+    #[error("unexpected payload kind {0}")]
+    UnexpectedKind(u8),
+
+    /// The input ended in the middle of a value.
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+
+    /// A variable-length integer doesn't fit in 64 bits.
+    #[error("invalid variable-length integer at byte {0}")]
+    InvalidVarint(usize),
+
+    /// A string isn't valid UTF-8.
+    #[error("invalid string: {0}")]
+    InvalidUtf8(#[from] Utf8Error),
+
+    /// A node isn't an element, text or comment.
+    #[error("invalid node kind {0}")]
+    InvalidNodeKind(u64),
+
+    /// A name refers to a string missing from the string table.
+    #[error("string {0} is missing from the string table")]
+    InvalidString(u64),
+
+    /// The root element isn't `<document>`.
+    #[error("Document must have a single root <document> element")]
+    InvalidRootElement,
+
+    /// There are bytes left after the payload.
+    #[error("{0} unexpected bytes after the end of the payload")]
+    TrailingBytes(usize),
+}
+
+impl<'a> Document<'a> {
+    /// Encode the document in the compact binary format.
+    ///
+    /// The format starts with the `TRAX` magic bytes, the format version and the payload kind,
+    /// followed by a table of every element/attribute name, each written once. Nodes are then
+    /// written depth-first: elements as their name indices, a bit flag per attribute marking
+    /// modifiers, their attributes and their children, and text and comments inline. All
+    /// integers are LEB128 variable-length integers.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.element(self, 0);
+        encoder.finish(KIND_DOCUMENT)
+    }
+
+    /// Decode a document written by [`Document::encode`]. Names and text are borrowed from the
+    /// input.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, KIND_DOCUMENT)?;
+
+        let (root, children) = decoder.element()?;
+        if !root.prefix.is_empty() || root.local != "document" {
+            return Err(DecodeError::InvalidRootElement);
+        }
+
+        let mut document = Document {
+            element_store: vec![Some(root)],
+            ..Default::default()
+        };
+        decoder.children(&mut document, children)?;
+        decoder.finish()?;
+
+        Ok(document)
+    }
+}
+
+/// Encode a message frame, i.e. any number of sibling elements and text such as
+/// `<get doc="todo.trax" /><get doc="todo_todos.trax" />`, in the compact binary format.
+pub fn encode_message(source: &str) -> Result<Vec<u8>, DocumentParseError> {
+    let mut frame = Document {
+        element_store: vec![Some(Element::default())],
+        ..Default::default()
+    };
+    frame.parse_children(source, Tokenizer::from_fragment(source, 0..source.len()))?;
+
+    let mut encoder = Encoder::default();
+    encoder.children(&frame, 0);
+    Ok(encoder.finish(KIND_MESSAGE))
+}
+
+/// Decode a message frame written by [`encode_message`].
+pub fn decode_message(bytes: &[u8]) -> Result<Vec<Fragment<'_>>, DecodeError> {
+    let mut decoder = Decoder::new(bytes, KIND_MESSAGE)?;
+    let mut frame = Document {
+        element_store: vec![Some(Element::default())],
+        ..Default::default()
+    };
+
+    let count = decoder.varint()?;
+    decoder.children(&mut frame, count)?;
+    decoder.finish()?;
+
+    let root = frame.element_store[0].as_ref().unwrap();
+    Ok(root
+        .children
+        .iter()
+        .filter_map(|c| frame.fragment(c))
+        .collect())
+}
+
+#[derive(Default)]
+struct Encoder<'s> {
+    strings: HashMap<&'s str, u64>,
+    table: Vec<&'s str>,
+    body: Vec<u8>,
+}
+
+impl<'s> Encoder<'s> {
+    fn string(&mut self, s: &'s str) -> u64 {
+        let next = self.table.len() as u64;
+        *self.strings.entry(s).or_insert_with(|| {
+            self.table.push(s);
+            next
+        })
+    }
+
+    fn element(&mut self, document: &'s Document, id: NodeId) {
+        let element = document.element_store[id].as_ref().unwrap();
+
+        let local = self.string(&element.local);
+        let prefix = self.string(&element.prefix);
+        write_varint(&mut self.body, (local << TAG_BITS) | TAG_ELEMENT);
+        write_varint(&mut self.body, prefix);
+
+        let attributes = &element.attributes;
+        write_varint(&mut self.body, attributes.len() as u64);
+
+        let mut flags = vec![0u8; attributes.len().div_ceil(8)];
+        for (i, attr) in attributes.iter().enumerate() {
+            if attr.value.is_none() {
+                flags[i / 8] |= 1 << (i % 8);
+            }
+        }
+        self.body.extend_from_slice(&flags);
+
+        for attr in attributes {
+            let prefix = self.string(&attr.prefix);
+            let local = self.string(&attr.local);
+            write_varint(&mut self.body, prefix);
+            write_varint(&mut self.body, local);
+            if let Some(value) = &attr.value {
+                write_varint(&mut self.body, value.len() as u64);
+                self.body.extend_from_slice(value.as_bytes());
+            }
+        }
+
+        self.children(document, id);
+    }
+
+    fn children(&mut self, document: &'s Document, id: NodeId) {
+        let children = &document.element_store[id].as_ref().unwrap().children;
+        write_varint(&mut self.body, children.len() as u64);
+
+        for child in children {
+            match child {
+                EntityRef::Element(i) => self.element(document, *i),
+                EntityRef::Text(i) => {
+                    let content = &document.text_store[*i].as_ref().unwrap().content;
+                    self.inline(TAG_TEXT, content);
+                }
+                EntityRef::Comment(i) => {
+                    let content = &document.comment_store[*i].as_ref().unwrap().content;
+                    self.inline(TAG_COMMENT, content);
+                }
+            }
+        }
+    }
+
+    fn inline(&mut self, tag: u64, content: &str) {
+        write_varint(&mut self.body, ((content.len() as u64) << TAG_BITS) | tag);
+        self.body.extend_from_slice(content.as_bytes());
+    }
+
+    fn finish(self, kind: u8) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.body.len() + self.table.len() * 8 + 6);
+        out.extend_from_slice(MAGIC);
+        out.push(BINARY_VERSION);
+        out.push(kind);
+
+        write_varint(&mut out, self.table.len() as u64);
+        for s in self.table {
+            write_varint(&mut out, s.len() as u64);
+            out.extend_from_slice(s.as_bytes());
+        }
+
+        out.extend_from_slice(&self.body);
+        out
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    table: Vec<&'a str>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8], kind: u8) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder {
+            bytes,
+            pos: 0,
+            table: Vec::new(),
+        };
+
+        if decoder.bytes(MAGIC.len()).ok() != Some(MAGIC) {
+            return Err(DecodeError::InvalidMagic);
+        }
+
+        let version = decoder.byte()?;
+        if version != BINARY_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let found = decoder.byte()?;
+        if found != kind {
+            return Err(DecodeError::UnexpectedKind(found));
+        }
+
+        let count = decoder.varint()?;
+        for _ in 0..count {
+            let s = decoder.str()?;
+            decoder.table.push(s);
+        }
+
+        Ok(decoder)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.bytes.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.pos;
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 {
+                break;
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DecodeError::InvalidVarint(start))
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let n = self.varint()?;
+        usize::try_from(n).map_err(|_| DecodeError::UnexpectedEnd)
+    }
+
+    fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.len()?;
+        Ok(std::str::from_utf8(self.bytes(len)?)?)
+    }
+
+    fn name(&mut self, index: u64) -> Result<Name<'a>, DecodeError> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.table.get(i))
+            .map(|s| Name::Borrowed(s))
+            .ok_or(DecodeError::InvalidString(index))
+    }
+
+    // Decodes an element without its children, returning the number of children to decode.
+    fn element_after_tag(&mut self, tag: u64) -> Result<(Element<'a>, u64), DecodeError> {
+        let local = self.name(tag >> TAG_BITS)?;
+        let prefix_index = self.varint()?;
+        let prefix = self.name(prefix_index)?;
+
+        let count = self.len()?;
+        let flags = self.bytes(count.div_ceil(8))?;
+
+        let mut element = Element {
+            prefix,
+            local,
+            ..Default::default()
+        };
+
+        for i in 0..count {
+            let prefix_index = self.varint()?;
+            let local_index = self.varint()?;
+            let value = if flags[i / 8] & (1 << (i % 8)) == 0 {
+                Some(Cow::Borrowed(self.str()?))
+            } else {
+                None
+            };
+
+            element.attributes.push_back(Attribute {
+                prefix: self.name(prefix_index)?,
+                local: self.name(local_index)?,
+                value,
+            });
+        }
+
+        let children = self.varint()?;
+        Ok((element, children))
+    }
+
+    fn inline(&mut self, tag: u64) -> Result<Cow<'a, str>, DecodeError> {
+        let len = usize::try_from(tag >> TAG_BITS).map_err(|_| DecodeError::UnexpectedEnd)?;
+        Ok(Cow::Borrowed(std::str::from_utf8(self.bytes(len)?)?))
+    }
+
+    fn element(&mut self) -> Result<(Element<'a>, u64), DecodeError> {
+        let tag = self.varint()?;
+        if tag & TAG_MASK != TAG_ELEMENT {
+            return Err(DecodeError::InvalidRootElement);
+        }
+        self.element_after_tag(tag)
+    }
+
+    // Decodes `count` children of the root element, and their descendants, without recursion.
+    fn children(&mut self, document: &mut Document<'a>, count: u64) -> Result<(), DecodeError> {
+        let mut stack = vec![(0, count)];
+
+        while let Some((parent, remaining)) = stack.last_mut() {
+            if *remaining == 0 {
+                stack.pop();
+                continue;
+            }
+            *remaining -= 1;
+            let parent = *parent;
+
+            let tag = self.varint()?;
+            let entity_ref = match tag & TAG_MASK {
+                TAG_ELEMENT => {
+                    let (mut element, children) = self.element_after_tag(tag)?;
+                    element.parent = parent;
+                    document.element_store.push(Some(element));
+
+                    let id = document.element_store.len() - 1;
+                    if children > 0 {
+                        stack.push((id, children));
+                    }
+                    EntityRef::Element(id)
+                }
+                TAG_TEXT => {
+                    let content = self.inline(tag)?;
+                    document.text_store.push(Some(Text { parent, content }));
+                    EntityRef::Text(document.text_store.len() - 1)
+                }
+                TAG_COMMENT => {
+                    let content = self.inline(tag)?;
+                    document
+                        .comment_store
+                        .push(Some(Comment { parent, content }));
+                    EntityRef::Comment(document.comment_store.len() - 1)
+                }
+                kind => return Err(DecodeError::InvalidNodeKind(kind)),
+            };
+
+            document.element_store[parent]
+                .as_mut()
+                .unwrap()
+                .children
+                .push_back(entity_ref);
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), DecodeError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            n => Err(DecodeError::TrailingBytes(n)),
+        }
+    }
+}
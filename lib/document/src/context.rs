@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+
+use crate::{
+    split_name, Attribute, Document, EntityRef, EvalError, NodeId, Segment, Type, Url, Value,
+};
+
+/// What a [`Url`] locates in a document. See [`EvalContext::resolve`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target<'d, 'a> {
+    /// An element.
+    Element(NodeId),
+    /// A property of an element.
+    Property(NodeId, &'d Attribute<'a>),
+}
+
+/// Resolves URLs in a [`Document`], and the properties which use them through the `asRef` and
+/// `asEval` prefixes.
+///
+/// Referencing a URL gives the structure or text it locates, while evaluating it gives the
+/// value it evaluates to. Either gives `undefined` when the URL can't be resolved.
+///
+/// ```rust
+/// use trax_document::{Document, EvalContext, Type, Value};
+///
+/// let src = r#"<document>
+///     <add> 1 2 </add>
+///     <Counter asEval:count="document/add" asRef:label="document/add" />
+/// </document>"#;
+/// let doc = Document::new(src).unwrap();
+/// let context = EvalContext::new(&doc);
+///
+/// assert_eq!(context.property(2, "count", Type::Int), Ok(Value::Int(3)));
+/// assert_eq!(
+///     context.property(2, "label", Type::Element).unwrap().to_string(),
+///     "<add>1 2</add>"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct EvalContext<'d, 'a> {
+    document: &'d Document<'a>,
+    name: Option<&'d str>,
+}
+
+impl<'d, 'a> EvalContext<'d, 'a> {
+    /// Create a context for a document. URLs naming a document don't resolve, see
+    /// [`EvalContext::with_name`].
+    pub fn new(document: &'d Document<'a>) -> Self {
+        Self {
+            document,
+            name: None,
+        }
+    }
+
+    /// Set the name of the document, like `todo.trax`, so URLs naming it resolve.
+    pub fn with_name(mut self, name: &'d str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// The document URLs are resolved in.
+    pub fn document(&self) -> &'d Document<'a> {
+        self.document
+    }
+
+    /// Find what a URL locates, starting from the element `from` for relative paths.
+    pub fn resolve(&self, from: NodeId, url: &Url) -> Option<Target<'d, 'a>> {
+        let (current, last) = self.resolve_parent(from, url)?;
+
+        let current = match last {
+            Some(segment) => self.step(current, segment).next()?,
+            None => current,
+        };
+        self.target(current, url)
+    }
+
+    /// Find everything a URL locates. Unlike [`EvalContext::resolve`], a last path segment
+    /// without an index, like `Todo` in `document/Body/Todo`, locates every matching element
+    /// rather than the first one.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, EvalContext, Target, Url};
+    ///
+    /// let doc = Document::new("<document><a /><b /><a n=\"1\" /></document>").unwrap();
+    /// let context = EvalContext::new(&doc);
+    ///
+    /// let url = Url::parse("document/a").unwrap();
+    /// assert_eq!(context.resolve_all(0, &url), [Target::Element(1), Target::Element(3)]);
+    ///
+    /// let url = Url::parse("document/a.n").unwrap();
+    /// assert_eq!(context.resolve_all(0, &url).len(), 1);
+    /// ```
+    pub fn resolve_all(&self, from: NodeId, url: &Url) -> Vec<Target<'d, 'a>> {
+        let Some((current, last)) = self.resolve_parent(from, url) else {
+            return Vec::new();
+        };
+
+        match last {
+            Some(segment @ Segment::Element { index: None, .. }) => self
+                .step(current, segment)
+                .filter_map(|id| self.target(id, url))
+                .collect(),
+            Some(segment) => self
+                .step(current, segment)
+                .next()
+                .and_then(|id| self.target(id, url))
+                .into_iter()
+                .collect(),
+            None => self.target(current, url).into_iter().collect(),
+        }
+    }
+
+    // Resolves every segment of a URL but the last one, which is returned.
+    fn resolve_parent<'u>(
+        &self,
+        from: NodeId,
+        url: &'u Url<'u>,
+    ) -> Option<(NodeId, Option<&'u Segment<'u>>)> {
+        let mut segments = url.segments.iter().peekable();
+
+        let mut current = match (url.document, segments.peek()) {
+            (Some(document), _) => {
+                let name = self.name?;
+                let named = document == name || document.rsplit('/').next() == Some(name);
+                if !named {
+                    return None;
+                }
+                0
+            }
+            (
+                None,
+                Some(Segment::Element {
+                    prefix: "",
+                    local: "document",
+                    index: None | Some(0),
+                    queries,
+                }),
+            ) if queries.is_empty() => {
+                segments.next();
+                0
+            }
+            (None, _) => from,
+        };
+        self.document.element(current)?;
+
+        let mut last = segments.next();
+        for segment in segments {
+            current = self.step(current, last?).next()?;
+            last = Some(segment);
+        }
+
+        Some((current, last))
+    }
+
+    // The elements a segment locates from the current element, ignoring its index unless it's
+    // `.` or `..`.
+    fn step<'s>(
+        &'s self,
+        current: NodeId,
+        segment: &'s Segment,
+    ) -> Box<dyn Iterator<Item = NodeId> + 's> {
+        match segment {
+            Segment::Current => Box::new(std::iter::once(current)),
+            Segment::Parent if current == 0 => Box::new(std::iter::empty()),
+            Segment::Parent => Box::new(
+                self.document
+                    .element(current)
+                    .map(|e| e.parent())
+                    .into_iter(),
+            ),
+            Segment::Element {
+                prefix,
+                local,
+                index,
+                queries,
+            } => Box::new(
+                self.children(current)
+                    .filter(move |id| {
+                        let element = self.document.element(*id).unwrap();
+                        element.prefix() == *prefix && element.local() == *local
+                    })
+                    .filter(move |id| {
+                        queries.iter().all(|q| {
+                            let has_property = |t: NodeId| {
+                                self.document
+                                    .element(t)
+                                    .and_then(|e| e.attribute(q.key))
+                                    .is_some_and(|a| q.value.is_none() || a.value() == q.value)
+                            };
+
+                            match q.child {
+                                Some(child) => self.children_named(*id, child).any(has_property),
+                                None => has_property(*id),
+                            }
+                        })
+                    })
+                    .skip(index.unwrap_or_default()),
+            ),
+        }
+    }
+
+    fn target(&self, id: NodeId, url: &Url) -> Option<Target<'d, 'a>> {
+        match url.property {
+            Some(property) => {
+                let element = self.document.element(id)?;
+                Some(Target::Property(id, element.attribute(property)?))
+            }
+            None => Some(Target::Element(id)),
+        }
+    }
+
+    /// Reference a URL: elements give a copy of themselves, attributes give their value and
+    /// modifiers give themselves.
+    pub fn reference(&self, from: NodeId, url: &str) -> Value<'d> {
+        let target = Url::parse(url)
+            .ok()
+            .and_then(|url| self.resolve(from, &url));
+
+        match target {
+            Some(Target::Element(id)) => self
+                .document
+                .fragment(&EntityRef::Element(id))
+                .map_or(Value::Undefined, Value::Element),
+            Some(Target::Property(_, attribute)) => match attribute.value() {
+                Some(value) => Value::Str(Cow::Borrowed(value)),
+                None => Value::Modifier(attribute.clone()),
+            },
+            None => Value::Undefined,
+        }
+    }
+
+    /// Evaluate a URL: elements give what they evaluate to (see [`Document::evaluate`]),
+    /// attributes give their value as a literal (see [`Value::infer`]) and modifiers give
+    /// `true`.
+    pub fn evaluate(&self, from: NodeId, url: &str) -> Result<Value<'d>, EvalError> {
+        let target = Url::parse(url)
+            .ok()
+            .and_then(|url| self.resolve(from, &url));
+
+        match target {
+            Some(Target::Element(id)) => self.document.evaluate(&EntityRef::Element(id)),
+            Some(Target::Property(_, attribute)) => Ok(match attribute.value() {
+                Some(value) => Value::infer(value),
+                None => Value::Bool(true),
+            }),
+            None => Ok(Value::Undefined),
+        }
+    }
+
+    /// Get the value of an element's property, which may be set directly, by reference with
+    /// `asRef:name="url"` or by evaluation with `asEval:name="url"`.
+    ///
+    /// For modifiers, i.e. `bool` properties, `asRef` gives whether the URL resolves and `asEval`
+    /// must evaluate to a `bool`. Missing modifiers are `false`, while other missing properties
+    /// are `undefined`.
+    pub fn property(
+        &self,
+        element_id: NodeId,
+        name: &str,
+        ty: Type,
+    ) -> Result<Value<'d>, EvalError> {
+        let Some(element) = self.document.element(element_id) else {
+            return Ok(Value::Undefined);
+        };
+
+        if let Some(value) = element.get(name, ty)? {
+            return Ok(value);
+        }
+
+        let (prefix, local) = split_name(name);
+        let prefixed = |by: &str| {
+            element
+                .attributes()
+                .find(|a| prefix.is_empty() && a.prefix() == by && a.local() == local)
+        };
+
+        if let Some(attribute) = prefixed("asRef") {
+            let Some(url) = attribute.value() else {
+                return Ok(Value::Undefined);
+            };
+
+            return Ok(match ty {
+                Type::Bool => Value::Bool(
+                    Url::parse(url)
+                        .ok()
+                        .and_then(|url| self.resolve(element_id, &url))
+                        .is_some(),
+                ),
+                _ => self.reference(element_id, url),
+            });
+        }
+
+        if let Some(attribute) = prefixed("asEval") {
+            let Some(url) = attribute.value() else {
+                return Ok(Value::Undefined);
+            };
+
+            let value = self.evaluate(element_id, url)?;
+            if ty == Type::Bool && value.ty() != Type::Bool {
+                return Err(EvalError::AsEvalModifierTypeMismatch {
+                    modifier: local.to_string(),
+                    url: url.to_string(),
+                    value: Box::new(value.into_owned()),
+                    location: self
+                        .document
+                        .element_span(element_id)
+                        .and_then(|s| s.attribute("asEval", local))
+                        .map(|a| a.full.clone()),
+                });
+            }
+
+            return Ok(value);
+        }
+
+        Ok(match ty {
+            Type::Bool => Value::Bool(false),
+            _ => Value::Undefined,
+        })
+    }
+
+    fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + 'd {
+        self.document
+            .element_store
+            .get(id)
+            .and_then(Option::as_ref)
+            .into_iter()
+            .flat_map(|e| e.children.iter())
+            .filter_map(|c| match c {
+                EntityRef::Element(i) => Some(*i),
+                _ => None,
+            })
+    }
+
+    fn children_named<'n>(&self, id: NodeId, name: &'n str) -> impl Iterator<Item = NodeId> + 'n
+    where
+        'd: 'n,
+    {
+        let (prefix, local) = split_name(name);
+        let document = self.document;
+        self.children(id).filter(move |c| {
+            let child = document.element(*c).unwrap();
+            child.prefix() == prefix && child.local() == local
+        })
+    }
+}
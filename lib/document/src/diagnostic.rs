@@ -0,0 +1,362 @@
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use trax_parser::{TextPos, Tokenizer};
+
+use crate::{
+    gen_full_name, validate_document_start, Document, DocumentParseError, Element, EntityRef, Name,
+    NodeId,
+};
+
+/// Prefixes given a meaning by the spec or the runtime.
+pub(crate) const KNOWN_PREFIXES: &[&str] = &[
+    "action", "asEval", "asRef", "bind", "class", "clear", "let", "onEvent", "pin", "read",
+    "visible",
+];
+
+/// Spec elements and the properties they require.
+const REQUIRED_PROPERTIES: &[(&str, &[&str])] = &[
+    ("insert", &["target"]),
+    ("insertProp", &["target"]),
+    ("redirect", &["connection", "url"]),
+];
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The document is invalid.
+    Error,
+    /// The document is valid, but probably not what was intended.
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// The kind of problem reported by a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// The source isn't valid TRAX markup. Nothing after it is parsed.
+    SyntaxError,
+    /// The source is empty.
+    EmptyDocument,
+    /// The root element isn't `<document>`.
+    InvalidRootElement,
+    /// An end tag doesn't match any open element.
+    MismatchedTag,
+    /// An element is never closed.
+    UnclosedTag,
+    /// An element has the same attribute or modifier more than once.
+    DuplicateAttribute,
+    /// An element or attribute uses a prefix which has no meaning.
+    UnknownPrefix,
+    /// A spec element is missing one of its required properties.
+    MissingProperty,
+    /// A `<document>` element is nested in the document, outside of an `<insert>` message.
+    NestedDocument,
+    /// An element has a property which isn't in its [`Schema`](crate::Schema).
+    UnknownProperty,
+    /// A property's value doesn't match its type in the [`Schema`](crate::Schema).
+    TypeMismatch,
+    /// An element has a child which its [`Schema`](crate::Schema) doesn't allow.
+    InvalidChild,
+    /// An element is neither a `class:` definition nor described by a
+    /// [`Schema`](crate::Schema).
+    UnknownClass,
+    /// A `class:` is defined more than once.
+    DuplicateClass,
+    /// A `let:` field is declared without a valid type, or more than once.
+    InvalidField,
+    /// A class is used inside one of its own instances.
+    RecursiveClass,
+    /// A `for` loop has more items than the runtime allows.
+    TooManyIterations,
+}
+
+impl Display for DiagnosticCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A problem found in a [`Document`]. See [`Document::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The kind of problem.
+    pub code: DiagnosticCode,
+    /// The range of bytes in the source where the problem is, if known.
+    pub span: Option<Range<usize>>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.code)?;
+        if let Some(span) = &self.span {
+            write!(f, " at {}..{}", span.start, span.end)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic.
+    pub fn new<S: Into<String>>(
+        severity: Severity,
+        code: DiagnosticCode,
+        span: Option<Range<usize>>,
+        message: S,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            span,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn syntax_error(source: &str, error: trax_parser::Error) -> Self {
+        let start = offset_of(source, error.pos());
+        let end = source[start..]
+            .chars()
+            .next()
+            .map_or(start, |c| start + c.len_utf8());
+
+        Self::new(
+            Severity::Error,
+            DiagnosticCode::SyntaxError,
+            Some(start..end),
+            error.to_string(),
+        )
+    }
+
+    pub(crate) fn unclosed(element: &Element, start_tag: Option<Range<usize>>) -> Self {
+        Self::new(
+            Severity::Error,
+            DiagnosticCode::UnclosedTag,
+            start_tag,
+            format!(
+                "`{}` is never closed",
+                gen_full_name(&element.prefix, &element.local)
+            ),
+        )
+    }
+}
+
+// Converts a 1-based row and column, in characters, to a byte offset.
+fn offset_of(source: &str, pos: TextPos) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(pos.row.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+
+    source[line_start..]
+        .char_indices()
+        .nth(pos.col.saturating_sub(1) as usize)
+        .map_or(source.len(), |(i, _)| line_start + i)
+}
+
+impl<'a> Document<'a> {
+    /// Parse a document, recovering from problems where possible instead of stopping at the
+    /// first one. Returns everything that could be parsed along with every problem found,
+    /// including those found by [`Document::validate`].
+    ///
+    /// ```rust
+    /// use trax_document::{DiagnosticCode, Document};
+    ///
+    /// let (doc, diagnostics) = Document::parse_with_diagnostics("<document><a></b></document>");
+    ///
+    /// assert_eq!(doc.into_string(), "<document>\n\t<a />\n</document>\n");
+    /// assert_eq!(diagnostics[0].code, DiagnosticCode::MismatchedTag);
+    /// assert_eq!(diagnostics[1].code, DiagnosticCode::UnclosedTag);
+    /// ```
+    pub fn parse_with_diagnostics(source: &'a str) -> (Self, Vec<Diagnostic>) {
+        let mut diagnostics = Vec::new();
+        let mut document = Self {
+            element_store: vec![Some(Element {
+                local: Name::Borrowed("document"),
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+
+        let mut tokenizer = Tokenizer::from(source);
+        let first_token = tokenizer.next();
+
+        // A different root element is parsed as a child of a `<document>` which isn't in the
+        // source.
+        let replayed = match validate_document_start(source, first_token) {
+            Ok(span) => {
+                document.spans.start_element(0, span);
+                None
+            }
+            Err(DocumentParseError::InvalidRootElement(..)) => {
+                let token = first_token.unwrap().unwrap();
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::InvalidRootElement,
+                    Some(token.span().range()),
+                    "Document must have a single root <document> element",
+                ));
+                Some(Ok(token))
+            }
+            Err(DocumentParseError::SyntaxError(e)) => {
+                diagnostics.push(Diagnostic::syntax_error(source, e));
+                return (document, diagnostics);
+            }
+            Err(e) => {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::EmptyDocument,
+                    None,
+                    e.to_string(),
+                ));
+                return (document, diagnostics);
+            }
+        };
+
+        let synthetic_root = replayed.is_some();
+        let open = document
+            .parse_tokens(
+                source,
+                replayed.into_iter().chain(tokenizer),
+                Some(&mut diagnostics),
+            )
+            // Problems are collected rather than returned
+            .unwrap_or_default();
+
+        for id in open {
+            if id == 0 && synthetic_root {
+                continue;
+            }
+
+            let element = document.element_store[id].as_ref().unwrap();
+            diagnostics.push(Diagnostic::unclosed(element, document.spans.start_tag(id)));
+        }
+
+        diagnostics.extend(document.validate());
+        (document, diagnostics)
+    }
+
+    /// Check the document for problems which don't prevent parsing it: duplicate attributes,
+    /// unknown prefixes, spec elements missing required properties, and nested `<document>`
+    /// elements.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(id) = stack.pop() {
+            let Some(Some(element)) = self.element_store.get(id) else {
+                continue;
+            };
+
+            self.validate_element(id, element, &mut diagnostics);
+
+            // children are visited in order
+            stack.extend(element.children.iter().rev().filter_map(|c| match c {
+                EntityRef::Element(i) => Some(*i),
+                _ => None,
+            }));
+        }
+
+        diagnostics
+    }
+
+    fn validate_element(&self, id: NodeId, element: &Element, diagnostics: &mut Vec<Diagnostic>) {
+        let span = self.element_span(id);
+        let start_tag = span.map(|s| s.start_tag.clone());
+        let name = gen_full_name(&element.prefix, &element.local);
+
+        if !element.prefix.is_empty() && !KNOWN_PREFIXES.contains(&element.prefix.as_str()) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                DiagnosticCode::UnknownPrefix,
+                start_tag.clone(),
+                format!("unknown prefix `{}` on element `{name}`", element.prefix),
+            ));
+        }
+
+        if id != 0 && element.prefix.is_empty() && element.local == "document" {
+            let parent = self.element_store[element.parent].as_ref().unwrap();
+            if !(parent.prefix.is_empty() && parent.local == "insert") {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::NestedDocument,
+                    start_tag.clone(),
+                    "`document` can only be the root element, or the contents of an `insert`",
+                ));
+            }
+        }
+
+        let mut seen = HashMap::new();
+        for attr in &element.attributes {
+            let attr_name = gen_full_name(&attr.prefix, &attr.local);
+            let occurrence = seen
+                .entry((attr.prefix.as_str(), attr.local.as_str()))
+                .and_modify(|n| *n += 1)
+                .or_insert(0);
+
+            // point at this occurrence of the attribute rather than the first one
+            let attr_span = span
+                .and_then(|s| {
+                    s.attributes
+                        .iter()
+                        .filter(|a| a.prefix == *attr.prefix && a.local == *attr.local)
+                        .nth(*occurrence)
+                })
+                .map(|a| a.full.clone())
+                .or(start_tag.clone());
+
+            if *occurrence > 0 {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::DuplicateAttribute,
+                    attr_span.clone(),
+                    format!("`{attr_name}` is set more than once on `{name}`"),
+                ));
+            }
+
+            if !attr.prefix.is_empty() && !KNOWN_PREFIXES.contains(&attr.prefix.as_str()) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticCode::UnknownPrefix,
+                    attr_span,
+                    format!("unknown prefix `{}` on `{attr_name}`", attr.prefix),
+                ));
+            }
+        }
+
+        if !element.prefix.is_empty() {
+            return;
+        }
+
+        let required = REQUIRED_PROPERTIES
+            .iter()
+            .find(|(local, _)| element.local == *local)
+            .map_or(&[][..], |(_, required)| required);
+
+        for property in required {
+            if !element
+                .attributes
+                .iter()
+                .any(|a| a.prefix.is_empty() && a.local == *property)
+            {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::MissingProperty,
+                    start_tag.clone(),
+                    format!("`{name}` requires the `{property}` property"),
+                ));
+            }
+        }
+    }
+}
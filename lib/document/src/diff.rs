@@ -0,0 +1,312 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+};
+
+use crate::{gen_full_name, Attribute, Document, EntityRef, Fragment, Name, NodeId};
+
+/// A single edit to a [`Document`], as produced by [`diff`].
+///
+/// Ids refer to entities of the old document. Patches are meant to be applied in order, and
+/// indices refer to the parent's children at the time the patch is applied.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Patch<'a> {
+    /// Insert a subtree as the `index`th child of `parent`.
+    Insert {
+        /// The element receiving the subtree.
+        parent: NodeId,
+        /// The position of the subtree within the parent's children.
+        index: usize,
+        /// The subtree to insert.
+        fragment: Fragment<'a>,
+    },
+    /// Drop an entity and its descendants.
+    Drop {
+        /// The dropped entity.
+        node: EntityRef,
+    },
+    /// Move an entity to the `index`th position of its parent's children.
+    Move {
+        /// The moved entity.
+        node: EntityRef,
+        /// The entity's parent.
+        parent: NodeId,
+        /// The new position of the entity.
+        index: usize,
+    },
+    /// Set the value of an attribute, replacing the attribute with the same name or appending it.
+    SetAttribute {
+        /// The element being changed.
+        element: NodeId,
+        /// The new attribute.
+        attribute: Attribute<'a>,
+    },
+    /// Remove the attribute with the given name.
+    RemoveAttribute {
+        /// The element being changed.
+        element: NodeId,
+        /// The prefix of the removed attribute.
+        prefix: Name<'a>,
+        /// The local name of the removed attribute.
+        local: Name<'a>,
+    },
+    /// Replace the content of a segment of text.
+    SetText {
+        /// The id of the text being changed.
+        text: usize,
+        /// The new content.
+        content: Cow<'a, str>,
+    },
+}
+
+impl Display for Patch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Patch::Insert {
+                parent,
+                index,
+                fragment,
+            } => write!(f, "+ Element at {parent}[{index}]: {fragment}"),
+            Patch::Drop { node } => write!(f, "- {node}"),
+            Patch::Move {
+                node,
+                parent,
+                index,
+            } => write!(f, "> {node} to Element at {parent}[{index}]"),
+            Patch::SetAttribute { element, attribute } => {
+                write!(f, "~ Element at {element}: {attribute}")
+            }
+            Patch::RemoveAttribute {
+                element,
+                prefix,
+                local,
+            } => write!(
+                f,
+                "~ Element at {element}: -{}",
+                gen_full_name(prefix, local)
+            ),
+            Patch::SetText { text, content } => write!(f, "~ Text at {text}: {content:?}"),
+        }
+    }
+}
+
+/// Compute an edit script turning `old` into `new`.
+///
+/// Children are matched within each parent: first to entities that are identical on the surface
+/// (same name and attributes, or same text), then to the first unmatched entity with the same
+/// name. The rest are dropped or inserted. Matched entities are kept, and the longest run of
+/// them which is already in order stays in place, so only the others are moved.
+pub fn diff<'a>(old: &Document, new: &Document<'a>) -> Vec<Patch<'a>> {
+    let mut patches = Vec::new();
+    diff_element(old, new, 0, 0, &mut patches);
+    patches
+}
+
+fn diff_element<'a>(
+    old: &Document,
+    new: &Document<'a>,
+    old_id: NodeId,
+    new_id: NodeId,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let old_elem = old.element_store[old_id].as_ref().unwrap();
+    let new_elem = new.element_store[new_id].as_ref().unwrap();
+
+    for attr in &new_elem.attributes {
+        if !old_elem.attributes.contains(attr) {
+            patches.push(Patch::SetAttribute {
+                element: old_id,
+                attribute: attr.clone(),
+            });
+        }
+    }
+
+    for attr in &old_elem.attributes {
+        if !new_elem
+            .attributes
+            .iter()
+            .any(|a| a.prefix == attr.prefix && a.local == attr.local)
+        {
+            patches.push(Patch::RemoveAttribute {
+                element: old_id,
+                prefix: Name::from(attr.prefix.to_string()),
+                local: Name::from(attr.local.to_string()),
+            });
+        }
+    }
+
+    let old_children = &old_elem.children;
+    let new_children = &new_elem.children;
+
+    // matches[i] is the index of the old child kept in place of the ith new child
+    let mut matches: Vec<Option<usize>> = vec![None; new_children.len()];
+    let mut matched = vec![false; old_children.len()];
+
+    for pass in [Surface::Identical, Surface::SameName] {
+        for (i, new_child) in new_children.iter().enumerate() {
+            if matches[i].is_some() {
+                continue;
+            }
+
+            let candidate = old_children.iter().enumerate().position(|(j, old_child)| {
+                !matched[j] && pass.matches(old, old_child, new, new_child)
+            });
+
+            if let Some(j) = candidate {
+                matched[j] = true;
+                matches[i] = Some(j);
+            }
+        }
+    }
+
+    for (j, old_child) in old_children.iter().enumerate() {
+        if !matched[j] {
+            patches.push(Patch::Drop {
+                node: old_child.clone(),
+            });
+        }
+    }
+
+    // the matched children which already are in order stay in place
+    let sequence: Vec<usize> = matches.iter().flatten().copied().collect();
+    let mut stable = vec![false; old_children.len()];
+    for k in longest_increasing(&sequence) {
+        stable[sequence[k]] = true;
+    }
+
+    // simulate the parent's children, by the index of the new child they become, while placing
+    // every other child in front of the one following it, from last to first
+    let mut new_index = vec![0; old_children.len()];
+    for (i, j) in matches.iter().enumerate() {
+        if let Some(j) = j {
+            new_index[*j] = i;
+        }
+    }
+    let mut current: Vec<usize> = (0..old_children.len())
+        .filter(|j| matched[*j])
+        .map(|j| new_index[j])
+        .collect();
+
+    let mut placed = Vec::new();
+    for (i, new_child) in new_children.iter().enumerate().rev() {
+        let anchor = |current: &Vec<usize>| {
+            current
+                .iter()
+                .position(|c| *c == i + 1)
+                .unwrap_or(current.len())
+        };
+
+        match matches[i] {
+            Some(j) if stable[j] => continue,
+            Some(j) => {
+                let from = current.iter().position(|c| *c == i).unwrap();
+                current.remove(from);
+                let index = anchor(&current);
+                current.insert(index, i);
+                placed.push(Patch::Move {
+                    node: old_children[j].clone(),
+                    parent: old_id,
+                    index,
+                });
+            }
+            None => {
+                let index = anchor(&current);
+                current.insert(index, i);
+                placed.push(Patch::Insert {
+                    parent: old_id,
+                    index,
+                    fragment: new.fragment(new_child).unwrap(),
+                });
+            }
+        }
+    }
+    patches.extend(placed);
+
+    for (i, new_child) in new_children.iter().enumerate() {
+        match (matches[i].map(|j| &old_children[j]), new_child) {
+            (Some(EntityRef::Element(o)), EntityRef::Element(n)) => {
+                diff_element(old, new, *o, *n, patches)
+            }
+            (Some(EntityRef::Text(o)), EntityRef::Text(n)) => {
+                let old_text = &old.text_store[*o].as_ref().unwrap().content;
+                let new_text = &new.text_store[*n].as_ref().unwrap().content;
+                if old_text != new_text {
+                    patches.push(Patch::SetText {
+                        text: *o,
+                        content: new_text.clone(),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// The indices of a longest strictly increasing subsequence of `sequence`, in order.
+fn longest_increasing(sequence: &[usize]) -> Vec<usize> {
+    // tails[n] ends the increasing subsequence of length n + 1 with the smallest last value
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; sequence.len()];
+
+    for (i, value) in sequence.iter().enumerate() {
+        let len = tails.partition_point(|t| sequence[*t] < *value);
+        if len > 0 {
+            previous[i] = Some(tails[len - 1]);
+        }
+        match tails.get_mut(len) {
+            Some(tail) => *tail = i,
+            None => tails.push(i),
+        }
+    }
+
+    let mut subsequence = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        subsequence.push(i);
+        current = previous[i];
+    }
+    subsequence.reverse();
+    subsequence
+}
+
+#[derive(Clone, Copy)]
+enum Surface {
+    Identical,
+    SameName,
+}
+
+impl Surface {
+    fn matches(
+        self,
+        old: &Document,
+        old_ref: &EntityRef,
+        new: &Document,
+        new_ref: &EntityRef,
+    ) -> bool {
+        match (old_ref, new_ref) {
+            (EntityRef::Element(o), EntityRef::Element(n)) => {
+                let o = old.element_store[*o].as_ref().unwrap();
+                let n = new.element_store[*n].as_ref().unwrap();
+                let same_name = o.prefix == n.prefix && o.local == n.local;
+
+                match self {
+                    Surface::Identical => same_name && o.attributes == n.attributes,
+                    Surface::SameName => same_name,
+                }
+            }
+            (EntityRef::Text(o), EntityRef::Text(n)) => match self {
+                Surface::Identical => {
+                    old.text_store[*o].as_ref().unwrap().content
+                        == new.text_store[*n].as_ref().unwrap().content
+                }
+                Surface::SameName => true,
+            },
+            // comments can't be edited in place, so only identical ones are kept
+            (EntityRef::Comment(o), EntityRef::Comment(n)) => {
+                old.comment_store[*o].as_ref().unwrap().content
+                    == new.comment_store[*n].as_ref().unwrap().content
+            }
+            _ => false,
+        }
+    }
+}
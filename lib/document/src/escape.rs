@@ -0,0 +1,122 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+};
+
+use crate::Quote;
+
+// Writes text, escaping `&` and `<` when `escape` is set.
+pub(crate) fn write_text(f: &mut impl Write, text: &str, escape: bool) -> fmt::Result {
+    if !escape {
+        return f.write_str(text);
+    }
+    write_escaped(f, text, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        _ => None,
+    })
+}
+
+// Writes a quoted attribute value, escaping `&`, `<` and the quote when `escape` is set.
+pub(crate) fn write_value(
+    f: &mut impl Write,
+    value: &str,
+    quote: Quote,
+    escape: bool,
+) -> fmt::Result {
+    f.write_char(quote.char())?;
+    if escape {
+        write_escaped(f, value, |c| match c {
+            '&' => Some("&amp;"),
+            '<' => Some("&lt;"),
+            '"' if quote == Quote::Double => Some("&quot;"),
+            '\'' if quote == Quote::Single => Some("&apos;"),
+            _ => None,
+        })?;
+    } else {
+        f.write_str(value)?;
+    }
+    f.write_char(quote.char())
+}
+
+// The number of characters `write_value` writes between the quotes.
+pub(crate) fn value_width(value: &str, quote: Quote, escape: bool) -> usize {
+    let mut counter = Counter(0);
+    let _ = write_value(&mut counter, value, quote, escape);
+    counter.0 - 2
+}
+
+// Decodes the character and entity references `write_text` and `write_value` produce, along
+// with `&gt;` and numeric references like `&#160;`. Unknown references are left as they are.
+pub(crate) fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest
+            .find(';')
+            .and_then(|end| Some((decode_reference(&rest[1..end])?, end)));
+        match reference {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    Cow::Owned(decoded)
+}
+
+fn decode_reference(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn write_escaped(
+    f: &mut impl Write,
+    s: &str,
+    escape: impl Fn(char) -> Option<&'static str>,
+) -> fmt::Result {
+    let mut last = 0;
+    for (i, c) in s.char_indices() {
+        if let Some(escaped) = escape(c) {
+            f.write_str(&s[last..i])?;
+            f.write_str(escaped)?;
+            last = i + c.len_utf8();
+        }
+    }
+    f.write_str(&s[last..])
+}
+
+// Counts the characters written to it.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
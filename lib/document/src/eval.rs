@@ -0,0 +1,367 @@
+use std::{borrow::Cow, ops::Range};
+
+use thiserror::Error;
+
+use crate::{gen_full_name, Document, EntityRef, NodeId, Type, Value, ValueError};
+
+/// An error raised while evaluating an element. See [`Document::evaluate`].
+#[derive(Debug, PartialEq, Error)]
+pub enum EvalError {
+    /// Dividing, or taking the modulo, by zero.
+    #[error("division by zero")]
+    DivideByZero {
+        /// The range of bytes in the source of the element's start tag, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// An arithmetic element has an operand which isn't a number.
+    #[error("expected int or float, found {found} `{value}`")]
+    TypeMismatch {
+        /// The type of the operand.
+        found: Type,
+        /// The operand's value.
+        value: String,
+        /// The range of bytes in the source of the operand, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// An arithmetic element has more operands than it uses.
+    #[error("`{element}` doesn't use #{index}")]
+    UnexpectedOperand {
+        /// The name of the element.
+        element: String,
+        /// The index of the first unused operand.
+        index: usize,
+        /// The range of bytes in the source of the operand, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// The result of integer arithmetic doesn't fit in an `int`.
+    #[error("integer overflow")]
+    Overflow {
+        /// The range of bytes in the source of the element's start tag, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// A modifier with the `asEval` prefix evaluated to something other than a `bool`.
+    #[error("`{modifier}` is a modifier, but `{url}` evaluated to {} `{value}`", value.ty())]
+    AsEvalModifierTypeMismatch {
+        /// The name of the modifier.
+        modifier: String,
+        /// The URL which was evaluated.
+        url: String,
+        /// What the URL evaluated to.
+        value: Box<Value<'static>>,
+        /// The range of bytes in the source of the attribute, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// A property couldn't be converted to the type it was expected to have.
+    #[error(transparent)]
+    InvalidValue(#[from] ValueError),
+}
+
+impl EvalError {
+    /// The range of bytes in the source where the problem is, if known.
+    pub fn location(&self) -> Option<&Range<usize>> {
+        match self {
+            EvalError::DivideByZero { location }
+            | EvalError::TypeMismatch { location, .. }
+            | EvalError::UnexpectedOperand { location, .. }
+            | EvalError::Overflow { location }
+            | EvalError::AsEvalModifierTypeMismatch { location, .. } => location.as_ref(),
+            EvalError::InvalidValue(e) => e.location(),
+        }
+    }
+}
+
+/// The arithmetic elements.
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Abs,
+}
+
+impl Op {
+    fn from_name(prefix: &str, local: &str) -> Option<Self> {
+        if !prefix.is_empty() {
+            return None;
+        }
+
+        Some(match local {
+            "add" => Op::Add,
+            "sub" => Op::Sub,
+            "mul" => Op::Mul,
+            "div" => Op::Div,
+            "mod" => Op::Mod,
+            "abs" => Op::Abs,
+            _ => return None,
+        })
+    }
+
+    // The number of operands used, or `None` if it uses all of them.
+    fn arity(self) -> Option<usize> {
+        match self {
+            Op::Add | Op::Mul => None,
+            Op::Sub | Op::Div | Op::Mod => Some(2),
+            Op::Abs => Some(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn is_zero(self) -> bool {
+        match self {
+            Number::Int(i) => i == 0,
+            Number::Float(f) => f == 0.0,
+        }
+    }
+
+    fn into_value<'v>(self) -> Value<'v> {
+        match self {
+            Number::Int(i) => Value::Int(i),
+            Number::Float(f) => Value::Float(f),
+        }
+    }
+}
+
+// Ints are cast to floats when the other side is a float, never the other way around.
+fn apply(
+    op: Op,
+    a: Number,
+    b: Number,
+    location: &Option<Range<usize>>,
+) -> Result<Number, EvalError> {
+    if matches!(op, Op::Div | Op::Mod) && b.is_zero() {
+        return Err(EvalError::DivideByZero {
+            location: location.clone(),
+        });
+    }
+
+    let (a, b) = match (a, b) {
+        (Number::Int(a), Number::Int(b)) => {
+            let result = match op {
+                Op::Add => a.checked_add(b),
+                Op::Sub => a.checked_sub(b),
+                Op::Mul => a.checked_mul(b),
+                // Dividing ints only gives an int when there's no remainder
+                Op::Div if a.checked_rem(b) != Some(0) => {
+                    return Ok(Number::Float(a as f64 / b as f64))
+                }
+                Op::Div => a.checked_div(b),
+                Op::Mod => a.checked_rem(b),
+                Op::Abs => a.checked_abs(),
+            };
+
+            return result.map(Number::Int).ok_or(EvalError::Overflow {
+                location: location.clone(),
+            });
+        }
+        (Number::Int(a), Number::Float(b)) => (a as f64, b),
+        (Number::Float(a), Number::Int(b)) => (a, b as f64),
+        (Number::Float(a), Number::Float(b)) => (a, b),
+    };
+
+    Ok(Number::Float(match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+        Op::Mod => a % b,
+        Op::Abs => a.abs(),
+    }))
+}
+
+/// A child of an element, as seen by an arithmetic element.
+enum Operand<'d> {
+    Literal {
+        text: &'d str,
+        location: Option<Range<usize>>,
+    },
+    Entity(EntityRef),
+}
+
+impl<'a> Document<'a> {
+    /// Evaluate an entity.
+    ///
+    /// Arithmetic elements (`add`, `sub`, `mul`, `div`, `mod` and `abs`) evaluate their
+    /// children, where each whitespace-separated word in their text is a separate literal.
+    /// Other elements evaluate to `void`. Text evaluates to the literal it contains, see
+    /// [`Value::infer`]. Entities which don't exist evaluate to `undefined`.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, EntityRef, Value};
+    ///
+    /// let doc = Document::new("<document><add> 1 2 <abs> -3 </abs> </add></document>").unwrap();
+    ///
+    /// assert_eq!(doc.evaluate(&EntityRef::Element(1)), Ok(Value::Int(6)));
+    /// assert_eq!(doc.evaluate(&EntityRef::Element(0)), Ok(Value::Void));
+    /// ```
+    pub fn evaluate(&self, entity_ref: &EntityRef) -> Result<Value<'_>, EvalError> {
+        match entity_ref {
+            EntityRef::Element(id) => self.eval_element(*id),
+            EntityRef::Text(id) => Ok(self
+                .text(*id)
+                .map_or(Value::Undefined, |t| Value::infer(t.content()))),
+            EntityRef::Comment(id) => Ok(match self.comment_store.get(*id) {
+                Some(Some(_)) => Value::Void,
+                _ => Value::Undefined,
+            }),
+        }
+    }
+
+    /// Evaluate `#index` of an element, i.e. its 0-indexed child, counting each word of its text
+    /// as a separate child. Comments aren't counted.
+    ///
+    /// Returns `undefined` if there's no such child.
+    pub fn eval_child(&self, element_id: NodeId, index: usize) -> Result<Value<'_>, EvalError> {
+        match self.operands(element_id).nth(index) {
+            Some(operand) => self.eval_operand(operand),
+            None => Ok(Value::Undefined),
+        }
+    }
+
+    fn eval_element(&self, id: NodeId) -> Result<Value<'_>, EvalError> {
+        let Some(element) = self.element(id) else {
+            return Ok(Value::Undefined);
+        };
+        let Some(op) = Op::from_name(element.prefix(), element.local()) else {
+            return Ok(Value::Void);
+        };
+
+        let location = self.spans.start_tag(id);
+        let mut operands = self.operands(id);
+
+        let result = match op.arity() {
+            None => {
+                let (identity, fold) = match op {
+                    Op::Mul => (Number::Int(1), Op::Mul),
+                    _ => (Number::Int(0), Op::Add),
+                };
+
+                let mut result = identity;
+                for operand in operands {
+                    let n = self.eval_number(Some(operand), &location)?;
+                    result = apply(fold, result, n, &location)?;
+                }
+                return Ok(result.into_value());
+            }
+            Some(1) => {
+                let a = self.eval_number(operands.next(), &location)?;
+                apply(op, a, a, &location)?
+            }
+            Some(_) => {
+                let a = self.eval_number(operands.next(), &location)?;
+                let b = self.eval_number(operands.next(), &location)?;
+                apply(op, a, b, &location)?
+            }
+        };
+
+        if let Some(extra) = operands.next() {
+            return Err(EvalError::UnexpectedOperand {
+                element: gen_full_name(element.prefix(), element.local()),
+                index: op.arity().unwrap_or_default(),
+                location: self.operand_location(&extra),
+            });
+        }
+
+        Ok(result.into_value())
+    }
+
+    // A missing operand is `undefined`, so it's reported at the element instead.
+    fn eval_number<'d>(
+        &'d self,
+        operand: Option<Operand<'d>>,
+        element_location: &Option<Range<usize>>,
+    ) -> Result<Number, EvalError> {
+        let location = match &operand {
+            Some(operand) => self.operand_location(operand),
+            None => element_location.clone(),
+        };
+        let value = match operand {
+            Some(operand) => self.eval_operand(operand)?,
+            None => Value::Undefined,
+        };
+
+        match value {
+            Value::Int(i) => Ok(Number::Int(i)),
+            Value::Float(f) => Ok(Number::Float(f)),
+            value => Err(EvalError::TypeMismatch {
+                found: value.ty(),
+                value: value.to_string(),
+                location,
+            }),
+        }
+    }
+
+    fn eval_operand<'d>(&'d self, operand: Operand<'d>) -> Result<Value<'d>, EvalError> {
+        match operand {
+            Operand::Literal { text, .. } => Ok(Value::infer(text)),
+            Operand::Entity(entity_ref) => self.evaluate(&entity_ref),
+        }
+    }
+
+    fn operand_location(&self, operand: &Operand) -> Option<Range<usize>> {
+        match operand {
+            Operand::Literal { location, .. } => location.clone(),
+            Operand::Entity(EntityRef::Element(id)) => self.spans.start_tag(*id),
+            Operand::Entity(entity_ref) => self.span_of(entity_ref),
+        }
+    }
+
+    fn operands(&self, element_id: NodeId) -> impl Iterator<Item = Operand<'_>> {
+        self.element_store
+            .get(element_id)
+            .and_then(Option::as_ref)
+            .into_iter()
+            .flat_map(|e| e.children.iter())
+            .flat_map(move |child| -> Box<dyn Iterator<Item = Operand<'_>>> {
+                match child {
+                    EntityRef::Text(id) => {
+                        let Some(text) = self.text(*id) else {
+                            return Box::new(std::iter::empty());
+                        };
+                        let content = text.content();
+                        let span = self.span_of(child);
+                        // Words are slices of the content, which is a slice of the source unless
+                        // it had to be unescaped. Otherwise, they're located by the whole text.
+                        let source = match (&text.content, &span) {
+                            (Cow::Borrowed(_), Some(s)) if s.len() == content.len() => {
+                                Some(s.start)
+                            }
+                            _ => None,
+                        };
+
+                        Box::new(content.split_whitespace().map(move |word| {
+                            let location = match source {
+                                Some(start) => {
+                                    let start =
+                                        start + word.as_ptr() as usize - content.as_ptr() as usize;
+                                    Some(start..start + word.len())
+                                }
+                                None => span.clone(),
+                            };
+                            Operand::Literal {
+                                text: word,
+                                location,
+                            }
+                        }))
+                    }
+                    EntityRef::Element(_) => {
+                        Box::new(std::iter::once(Operand::Entity(child.clone())))
+                    }
+                    EntityRef::Comment(_) => Box::new(std::iter::empty()),
+                }
+            })
+    }
+}
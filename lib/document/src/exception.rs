@@ -0,0 +1,322 @@
+use std::{collections::VecDeque, ops::Range};
+
+use thiserror::Error;
+
+use crate::{
+    Attribute, DecodeError, Diagnostic, DocumentParseError, DropEntityError, EvalError, Fragment,
+    InsertElementError, ModifyEntityError, Name, PatchError, SchemaError, Type, UrlError, Value,
+    ValueError,
+};
+
+/// An exception raised at runtime, named after the spec's exceptions so that it can be reported
+/// back to the other end of a connection. See [`Exception::to_fragment`].
+///
+/// Errors which don't have an exception in the spec are [`Exception::Runtime`] exceptions named
+/// after the error.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum Exception {
+    /// A modifier with the `asEval` prefix evaluated to something other than a `bool`.
+    #[error("`{modifier}` is a modifier, but `{url}` evaluated to {} `{value}`", value.ty())]
+    AsEvalModifierTypeMismatch {
+        /// The name of the modifier.
+        modifier: String,
+        /// The URL queried.
+        url: String,
+        /// The returned value.
+        value: Box<Value<'static>>,
+    },
+
+    /// Attempting to divide by zero.
+    #[error("division by zero")]
+    DivideByZero,
+
+    /// Parsing an invalid document.
+    #[error("{description}")]
+    DocumentParseException {
+        /// The range of bytes in the source where the problem is, or `None` if it's `undefined`.
+        location: Option<Range<usize>>,
+        /// A description of the problem.
+        description: String,
+    },
+
+    /// An error which doesn't have an exception in the spec.
+    #[error("{description}")]
+    Runtime {
+        /// The kind of error, like `TypeMismatch` or `NotFound`.
+        name: String,
+        /// The range of bytes in the source where the problem is, if known.
+        location: Option<Range<usize>>,
+        /// A description of the problem.
+        description: String,
+    },
+}
+
+impl Exception {
+    fn runtime<E: ToString>(name: &str, location: Option<Range<usize>>, error: E) -> Self {
+        Exception::Runtime {
+            name: name.to_string(),
+            location,
+            description: error.to_string(),
+        }
+    }
+
+    /// The name of the exception, like `DivideByZero`.
+    pub fn name(&self) -> &str {
+        match self {
+            Exception::AsEvalModifierTypeMismatch { .. } => "AsEvalModifierTypeMismatch",
+            Exception::DivideByZero => "DivideByZero",
+            Exception::DocumentParseException { .. } => "DocumentParseException",
+            Exception::Runtime { name, .. } => name,
+        }
+    }
+
+    /// The range of bytes in the source where the problem is, if known.
+    pub fn location(&self) -> Option<&Range<usize>> {
+        match self {
+            Exception::DocumentParseException { location, .. }
+            | Exception::Runtime { location, .. } => location.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Convert the exception to an `<exception>` element, with its name and context as
+    /// attributes. Context which is `undefined` is left out. Values are escaped when the element
+    /// is displayed, so it's read back as it was by [`Exception::from_fragment`].
+    ///
+    /// ```rust
+    /// use trax_document::Exception;
+    ///
+    /// let exception = Exception::DocumentParseException {
+    ///     location: Some(15..19),
+    ///     description: "`b` was closed".to_string(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     exception.to_fragment().to_string(),
+    ///     r#"<exception name="DocumentParseException" location="15..19" description="`b` was closed" />"#
+    /// );
+    /// ```
+    pub fn to_fragment(&self) -> Fragment<'static> {
+        let mut attributes = VecDeque::new();
+        let mut push = |key: &'static str, value: String| {
+            attributes.push_back(Attribute::new("", key, Some(value)));
+        };
+
+        push("name", self.name().to_string());
+        match self {
+            Exception::AsEvalModifierTypeMismatch {
+                modifier,
+                url,
+                value,
+            } => {
+                push("modifier", modifier.clone());
+                push("url", url.clone());
+                push("value", value.to_string());
+            }
+            Exception::DivideByZero => (),
+            Exception::DocumentParseException {
+                location,
+                description,
+            }
+            | Exception::Runtime {
+                location,
+                description,
+                ..
+            } => {
+                if let Some(location) = location {
+                    push("location", format!("{}..{}", location.start, location.end));
+                }
+                push("description", description.clone());
+            }
+        }
+
+        Fragment::Element {
+            prefix: Name::default(),
+            local: Name::Borrowed("exception"),
+            attributes,
+            children: Vec::new(),
+        }
+    }
+
+    /// Read an exception written by [`Exception::to_fragment`]. Returns `None` if the fragment
+    /// isn't an `<exception>` element, or is missing any of its context.
+    pub fn from_fragment(fragment: &Fragment) -> Option<Self> {
+        let Fragment::Element {
+            prefix,
+            local,
+            attributes,
+            ..
+        } = fragment
+        else {
+            return None;
+        };
+
+        if !prefix.is_empty() || *local != "exception" {
+            return None;
+        }
+
+        let get = |key: &str| {
+            attributes
+                .iter()
+                .find(|a| a.prefix.is_empty() && a.local == key)
+                .and_then(|a| a.value())
+        };
+        let location = match get("location") {
+            Some(location) => {
+                let range = Value::parse(Type::Range, location).ok()?.as_range()?;
+                Some(range.start.try_into().ok()?..range.end.try_into().ok()?)
+            }
+            None => None,
+        };
+        let description = || get("description").map(str::to_string);
+
+        Some(match get("name")? {
+            "AsEvalModifierTypeMismatch" => Exception::AsEvalModifierTypeMismatch {
+                modifier: get("modifier")?.to_string(),
+                url: get("url")?.to_string(),
+                value: Box::new(
+                    get("value").map_or(Value::Undefined, |v| Value::infer(v).into_owned()),
+                ),
+            },
+            "DivideByZero" => Exception::DivideByZero,
+            "DocumentParseException" => Exception::DocumentParseException {
+                location,
+                description: description()?,
+            },
+            name => Exception::Runtime {
+                name: name.to_string(),
+                location,
+                description: description()?,
+            },
+        })
+    }
+}
+
+impl From<DocumentParseError> for Exception {
+    fn from(error: DocumentParseError) -> Self {
+        let location = match &error {
+            DocumentParseError::InvalidRootElement(_, span)
+            | DocumentParseError::InvalidTreeStructure { span, .. } => Some(span.clone()),
+            DocumentParseError::EmptyDocument | DocumentParseError::SyntaxError(_) => None,
+        };
+        Exception::DocumentParseException {
+            location,
+            description: error.to_string(),
+        }
+    }
+}
+
+impl From<Diagnostic> for Exception {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Exception::DocumentParseException {
+            location: diagnostic.span,
+            description: diagnostic.message,
+        }
+    }
+}
+
+impl From<DecodeError> for Exception {
+    fn from(error: DecodeError) -> Self {
+        Exception::DocumentParseException {
+            location: None,
+            description: error.to_string(),
+        }
+    }
+}
+
+impl From<EvalError> for Exception {
+    fn from(error: EvalError) -> Self {
+        let location = error.location().cloned();
+        match error {
+            EvalError::DivideByZero { .. } => Exception::DivideByZero,
+            EvalError::AsEvalModifierTypeMismatch {
+                modifier,
+                url,
+                value,
+                ..
+            } => Exception::AsEvalModifierTypeMismatch {
+                modifier,
+                url,
+                value,
+            },
+            EvalError::InvalidValue(error) => error.into(),
+            EvalError::TypeMismatch { .. } => Self::runtime("TypeMismatch", location, error),
+            EvalError::UnexpectedOperand { .. } => {
+                Self::runtime("UnexpectedOperand", location, error)
+            }
+            EvalError::Overflow { .. } => Self::runtime("Overflow", location, error),
+        }
+    }
+}
+
+impl From<ValueError> for Exception {
+    fn from(error: ValueError) -> Self {
+        Self::runtime("TypeMismatch", error.location().cloned(), error)
+    }
+}
+
+impl From<UrlError> for Exception {
+    fn from(error: UrlError) -> Self {
+        Self::runtime("InvalidUrl", None, error)
+    }
+}
+
+impl From<InsertElementError> for Exception {
+    fn from(error: InsertElementError) -> Self {
+        let name = match &error {
+            InsertElementError::NotFound(_) => "NotFound",
+            InsertElementError::PositionOutOfRange(..) => "PositionOutOfRange",
+            InsertElementError::DropEntityError(..) => "ReplaceFailed",
+        };
+        Self::runtime(name, None, error)
+    }
+}
+
+impl From<DropEntityError> for Exception {
+    fn from(error: DropEntityError) -> Self {
+        let name = match &error {
+            DropEntityError::RefuseDropRoot => "RefuseDropRoot",
+            DropEntityError::NotFound(_) => "NotFound",
+        };
+        Self::runtime(name, None, error)
+    }
+}
+
+impl From<ModifyEntityError> for Exception {
+    fn from(error: ModifyEntityError) -> Self {
+        let name = match &error {
+            ModifyEntityError::NotFound(_) => "NotFound",
+            ModifyEntityError::AttributeNotFound(..) => "AttributeNotFound",
+            ModifyEntityError::IndexOutOfRange(..) => "IndexOutOfRange",
+            ModifyEntityError::RefuseMoveRoot => "RefuseMoveRoot",
+            ModifyEntityError::MissingPosition(_) => "MissingPosition",
+        };
+        Self::runtime(name, None, error)
+    }
+}
+
+impl From<PatchError> for Exception {
+    fn from(error: PatchError) -> Self {
+        match error {
+            PatchError::Insert(error) => error.into(),
+            PatchError::Drop(error) => error.into(),
+            PatchError::Modify(error) => error.into(),
+            PatchError::NotAChild(..) => Self::runtime("NotAChild", None, error),
+        }
+    }
+}
+
+impl From<SchemaError> for Exception {
+    fn from(error: SchemaError) -> Self {
+        match error {
+            SchemaError::Parse(error) => error.into(),
+            error => Self::runtime("InvalidSchema", None, error),
+        }
+    }
+}
+
+impl From<Exception> for Fragment<'static> {
+    fn from(exception: Exception) -> Self {
+        exception.to_fragment()
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::{self, Display, Write},
+};
+
+use trax_parser::Tokenizer;
+
+use crate::{
+    escape::write_text, Attribute, Document, DocumentParseError, Element, EntityRef, Interner,
+    Name, PlacePosition,
+};
+
+/// A detached subtree which isn't stored in any [`Document`].
+///
+/// With the `serde` feature, elements are serialized as maps, text as strings and comments as
+/// `{ "comment": "..." }`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum Fragment<'a> {
+    /// An element and its descendants.
+    Element {
+        /// The element's prefix.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Name::is_empty")
+        )]
+        prefix: Name<'a>,
+        /// The element's local name.
+        local: Name<'a>,
+        /// The element's attributes and modifiers.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "VecDeque::is_empty")
+        )]
+        attributes: VecDeque<Attribute<'a>>,
+        /// The element's children.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Vec::is_empty")
+        )]
+        children: Vec<Fragment<'a>>,
+    },
+    /// A segment of text.
+    Text(Cow<'a, str>),
+    /// A comment, without its `/*` and `*/` delimiters.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::comment"))]
+    Comment(Cow<'a, str>),
+}
+
+impl<'a> Fragment<'a> {
+    /// Parse any number of sibling elements and text, such as `<a /> text <b />`.
+    ///
+    /// ```rust
+    /// use trax_document::Fragment;
+    ///
+    /// let fragments = Fragment::parse("<a key=\"value\" /> text").unwrap();
+    /// assert_eq!(fragments.len(), 2);
+    /// assert_eq!(fragments[0].to_string(), "<a key=\"value\" />");
+    /// ```
+    pub fn parse(source: &'a str) -> Result<Vec<Self>, DocumentParseError> {
+        let mut frame = Document {
+            element_store: vec![Some(Element::default())],
+            ..Default::default()
+        };
+        frame.parse_children(source, Tokenizer::from_fragment(source, 0..source.len()))?;
+
+        let root = frame.element_store[0].as_ref().unwrap();
+        Ok(root
+            .children
+            .iter()
+            .filter_map(|c| frame.fragment(c))
+            .collect())
+    }
+
+    /// Converts the fragment into one that doesn't borrow from the source.
+    pub fn into_owned(self, interner: &mut Interner) -> Fragment<'static> {
+        match self {
+            Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            } => Fragment::Element {
+                prefix: prefix.into_owned(interner),
+                local: local.into_owned(interner),
+                attributes: attributes
+                    .into_iter()
+                    .map(|a| a.into_owned(interner))
+                    .collect(),
+                children: children
+                    .into_iter()
+                    .map(|c| c.into_owned(interner))
+                    .collect(),
+            },
+            Fragment::Text(content) => Fragment::Text(Cow::Owned(content.into_owned())),
+            Fragment::Comment(content) => Fragment::Comment(Cow::Owned(content.into_owned())),
+        }
+    }
+}
+
+impl Display for Fragment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            } => {
+                f.write_char('<')?;
+                write!(f, "{}", crate::gen_full_name(prefix, local))?;
+                for attr in attributes {
+                    write!(f, " {attr}")?;
+                }
+
+                if children.is_empty() {
+                    f.write_str(" />")
+                } else {
+                    f.write_char('>')?;
+                    for child in children {
+                        child.fmt(f)?;
+                    }
+                    write!(f, "</{}>", crate::gen_full_name(prefix, local))
+                }
+            }
+            Fragment::Text(content) => write_text(f, content, true),
+            Fragment::Comment(content) => write!(f, "/*{content}*/"),
+        }
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Create a document from a fragment of its root `<document>` element. Returns `None` if
+    /// the fragment is anything else.
+    pub fn from_fragment(fragment: Fragment<'a>) -> Option<Self> {
+        let Fragment::Element {
+            prefix,
+            local,
+            attributes,
+            children,
+        } = fragment
+        else {
+            return None;
+        };
+
+        if !prefix.is_empty() || local != "document" {
+            return None;
+        }
+
+        let mut document = Document {
+            element_store: vec![Some(Element {
+                local,
+                attributes,
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+
+        for child in children {
+            // inserting at the end of an existing element can't fail
+            let _ = document.insert_fragment(0, PlacePosition::End, child);
+        }
+
+        Some(document)
+    }
+
+    /// Copy an entity and its descendants out of the document.
+    pub fn fragment(&self, entity_ref: &EntityRef) -> Option<Fragment<'a>> {
+        match entity_ref {
+            EntityRef::Element(i) => {
+                let element = self.element_store.get(*i)?.as_ref()?;
+                Some(Fragment::Element {
+                    prefix: element.prefix.clone(),
+                    local: element.local.clone(),
+                    attributes: element.attributes.clone(),
+                    children: element
+                        .children
+                        .iter()
+                        .filter_map(|c| self.fragment(c))
+                        .collect(),
+                })
+            }
+            EntityRef::Text(i) => Some(Fragment::Text(
+                self.text_store.get(*i)?.as_ref()?.content.clone(),
+            )),
+            EntityRef::Comment(i) => Some(Fragment::Comment(
+                self.comment_store.get(*i)?.as_ref()?.content.clone(),
+            )),
+        }
+    }
+}
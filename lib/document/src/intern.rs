@@ -0,0 +1,156 @@
+use std::{
+    borrow::{Borrow, Cow},
+    collections::HashSet,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// The name (prefix or local part) of an element or attribute.
+///
+/// Names parsed from a source are borrowed from it. Owned names are reference-counted so that
+/// an [`Interner`] can share a single allocation between every occurrence of the same name.
+#[derive(Clone, Debug)]
+pub enum Name<'a> {
+    /// A name borrowed from the source document.
+    Borrowed(&'a str),
+    /// An owned name, possibly shared with other nodes.
+    Interned(Arc<str>),
+}
+
+impl<'a> Name<'a> {
+    /// Returns the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Name::Borrowed(s) => s,
+            Name::Interned(s) => s,
+        }
+    }
+
+    /// Returns `true` if the name is empty, e.g. the prefix of an unprefixed element.
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    /// Converts the name into one that doesn't borrow from the source, sharing the allocation
+    /// with any equal name previously seen by `interner`.
+    pub fn into_owned(self, interner: &mut Interner) -> Name<'static> {
+        Name::Interned(interner.intern(self.as_str()))
+    }
+}
+
+impl Default for Name<'_> {
+    fn default() -> Self {
+        Name::Borrowed("")
+    }
+}
+
+impl Deref for Name<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl Display for Name<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for Name<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Name<'_> {}
+
+impl PartialEq<str> for Name<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Name<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Name<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<'a> From<&'a str> for Name<'a> {
+    fn from(value: &'a str) -> Self {
+        Name::Borrowed(value)
+    }
+}
+
+impl From<String> for Name<'_> {
+    fn from(value: String) -> Self {
+        Name::Interned(value.into())
+    }
+}
+
+impl From<Arc<str>> for Name<'_> {
+    fn from(value: Arc<str>) -> Self {
+        Name::Interned(value)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Name<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        match value {
+            Cow::Borrowed(s) => Name::Borrowed(s),
+            Cow::Owned(s) => s.into(),
+        }
+    }
+}
+
+/// A set of shared strings used to deduplicate [`Name`]s when converting documents into owned ones.
+///
+/// The same interner can be reused across documents so that all of them share their names.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Create a new, empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of `s`, allocating it on first use.
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            interned.clone()
+        } else {
+            let interned: Arc<str> = s.into();
+            self.strings.insert(interned.clone());
+            interned
+        }
+    }
+
+    /// The number of distinct strings held by the interner.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if the interner holds no strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl Borrow<str> for Name<'_> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
@@ -26,19 +26,71 @@ println!("{document:#?}");
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
-use std::{borrow::Cow, collections::VecDeque, fmt::Display};
-
-use trax_parser::{span_text_range as r, ElementEnd, TextRange, Token, Tokenizer};
-
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::{Display, Write},
+    ops::Range,
+};
+
+use trax_parser::{span_text_range as r, ElementEnd, StrSpan, TextRange, Token, Tokenizer};
+
+use escape::unescape;
+
+mod binary;
+mod context;
+mod diagnostic;
+mod diff;
+mod escape;
+mod eval;
+mod exception;
+mod fragment;
+mod intern;
 mod manipulation;
+mod observe;
+mod render;
+mod schema;
+#[cfg(feature = "serde")]
+mod serialize;
+mod span;
+mod splice;
+mod transaction;
+mod url;
+mod value;
+
+pub use binary::{decode_message, encode_message, DecodeError, BINARY_VERSION};
+pub use context::{EvalContext, Target};
+pub use diagnostic::{Diagnostic, DiagnosticCode, Severity};
+pub use diff::{diff, Patch};
+pub use eval::EvalError;
+pub use exception::Exception;
+pub use fragment::Fragment;
+pub use intern::{Interner, Name};
+pub use manipulation::{DropEntityError, InsertElementError, ModifyEntityError, PlacePosition};
+pub use observe::{ChangeEvent, SubscriptionId};
+pub use render::{Indent, Quote, RenderOptions, Rendered, SelfClosing};
+pub use schema::{ElementSchema, PropertySchema, PropertyType, Schema, SchemaError};
+pub use span::{AttributeSpan, ElementSpan};
+pub use splice::Splice;
+pub use transaction::{Changeset, History, PatchError, Transaction};
+pub use url::{Query, Segment, Url, UrlError};
+pub use value::{ElementRef, Type, Value, ValueError};
+
+/// The id of an element in a [`Document`]'s element store. The root `<document>` is always `0`.
+pub type NodeId = usize;
+
+/// A [`Document`] which doesn't borrow from its source, see [`Document::into_owned`].
+pub type OwnedDocument = Document<'static>;
 
 /// A reference to an entity in one of the [`Document`] stores.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum EntityRef {
     /// A refrence to an element in the element store.
     Element(usize),
     /// A reference to text in the text store.
     Text(usize),
+    /// A reference to a comment in the comment store.
+    Comment(usize),
 }
 
 impl Display for EntityRef {
@@ -46,21 +98,33 @@ impl Display for EntityRef {
         match self {
             EntityRef::Element(i) => write!(f, "Element at {i}"),
             EntityRef::Text(i) => write!(f, "Text at {i}"),
+            EntityRef::Comment(i) => write!(f, "Comment at {i}"),
         }
     }
 }
 
 /// A TRAX attribute/modifier.
+///
+/// Modifiers are attributes without a value.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute<'a> {
-    prefix: Cow<'a, str>,
-    local: Cow<'a, str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Name::is_empty")
+    )]
+    prefix: Name<'a>,
+    local: Name<'a>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     value: Option<Cow<'a, str>>,
 }
 
 impl<'a> Attribute<'a> {
     /// Creates a new Attribute from its raw parts without allocating.
-    pub fn new<C: Into<Cow<'a, str>>, C2: Into<Cow<'a, str>>, C3: Into<Cow<'a, str>>>(
+    pub fn new<C: Into<Name<'a>>, C2: Into<Name<'a>>, C3: Into<Cow<'a, str>>>(
         prefix: C,
         local: C2,
         value: Option<C3>,
@@ -71,30 +135,189 @@ impl<'a> Attribute<'a> {
             value: value.map(|v| v.into()),
         }
     }
+
+    /// The attribute's prefix, or an empty string if it doesn't have one.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The attribute's local name.
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+
+    /// The attribute's value, or `None` for modifiers.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Returns `true` if the attribute is a modifier.
+    pub fn is_modifier(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Takes the attribute's value, or `None` for modifiers.
+    pub fn into_value(self) -> Option<Cow<'a, str>> {
+        self.value
+    }
+
+    /// Converts the attribute into one that doesn't borrow from the source.
+    pub fn into_owned(self, interner: &mut Interner) -> Attribute<'static> {
+        Attribute {
+            prefix: self.prefix.into_owned(interner),
+            local: self.local.into_owned(interner),
+            value: self.value.map(|v| Cow::Owned(v.into_owned())),
+        }
+    }
+}
+
+impl Display for Attribute<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&gen_full_name(&self.prefix, &self.local))?;
+        if let Some(value) = &self.value {
+            f.write_char('=')?;
+            escape::write_value(f, value, Quote::Double.for_value(value), true)?;
+        }
+        Ok(())
+    }
 }
 
 /// A TRAX element.
+///
+/// When serialized on its own, an element doesn't include its children. Serialize the
+/// [`Document`] or a [`Fragment`] to include them.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element<'a> {
-    parent: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    parent: NodeId,
+    #[cfg_attr(feature = "serde", serde(skip))]
     children: VecDeque<EntityRef>,
-    prefix: Cow<'a, str>,
-    local: Cow<'a, str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Name::is_empty")
+    )]
+    prefix: Name<'a>,
+    local: Name<'a>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "VecDeque::is_empty")
+    )]
     attributes: VecDeque<Attribute<'a>>,
 }
 
+impl<'a> Element<'a> {
+    /// The element's prefix, or an empty string if it doesn't have one.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The element's local name.
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+
+    /// The id of the element's parent. The root `<document>` is its own parent.
+    pub fn parent(&self) -> NodeId {
+        self.parent
+    }
+
+    /// The element's attributes and modifiers, in order.
+    pub fn attributes(&self) -> impl Iterator<Item = &Attribute<'a>> {
+        self.attributes.iter()
+    }
+
+    /// The element's children, in order.
+    pub fn children(&self) -> impl Iterator<Item = &EntityRef> {
+        self.children.iter()
+    }
+
+    /// Converts the element into one that doesn't borrow from the source.
+    pub fn into_owned(self, interner: &mut Interner) -> Element<'static> {
+        Element {
+            parent: self.parent,
+            children: self.children,
+            prefix: self.prefix.into_owned(interner),
+            local: self.local.into_owned(interner),
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(|a| a.into_owned(interner))
+                .collect(),
+        }
+    }
+}
+
 /// A segment of text.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Text<'a> {
-    parent: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    parent: NodeId,
+    content: Cow<'a, str>,
+}
+
+impl<'a> Text<'a> {
+    /// The id of the element containing the text.
+    pub fn parent(&self) -> NodeId {
+        self.parent
+    }
+
+    /// The text's content.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Converts the text into one that doesn't borrow from the source.
+    pub fn into_owned(self) -> Text<'static> {
+        Text {
+            parent: self.parent,
+            content: Cow::Owned(self.content.into_owned()),
+        }
+    }
+}
+
+/// A `/* ... */` comment.
+///
+/// Only comments inside the root `<document>` element are kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comment<'a> {
+    parent: NodeId,
     content: Cow<'a, str>,
 }
 
+impl<'a> Comment<'a> {
+    /// The id of the element containing the comment.
+    pub fn parent(&self) -> NodeId {
+        self.parent
+    }
+
+    /// The comment's content, without its `/*` and `*/` delimiters.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Converts the comment into one that doesn't borrow from the source.
+    pub fn into_owned(self) -> Comment<'static> {
+        Comment {
+            parent: self.parent,
+            content: Cow::Owned(self.content.into_owned()),
+        }
+    }
+}
+
 /// A TRAX document.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Document<'a> {
     element_store: Vec<Option<Element<'a>>>,
     text_store: Vec<Option<Text<'a>>>,
+    comment_store: Vec<Option<Comment<'a>>>,
+    spans: span::Spans<'a>,
+    observers: observe::Observers,
 }
 
 /// An error encountered when parsing/creating a [`Document`].
@@ -104,9 +327,10 @@ pub enum DocumentParseError {
     #[error("Can't parse an empty document")]
     EmptyDocument,
 
-    /// An error when the root `<document>` element is invalid.
+    /// An error when the root `<document>` element is invalid, at a range of text and of bytes in
+    /// the source.
     #[error("at {0}: Document must have a single root <document> element")]
-    InvalidRootElement(TextRange),
+    InvalidRootElement(TextRange, Range<usize>),
 
     /// A generic syntax error. See [`trax_parser::Error`].
     #[error("syntax error")]
@@ -121,6 +345,8 @@ pub enum DocumentParseError {
         current_open_elem: String,
         /// The location of the attempted closed element.
         location: TextRange,
+        /// The range of bytes in the source of the attempted closed element.
+        span: Range<usize>,
     },
 }
 
@@ -129,30 +355,85 @@ impl<'a> Document<'a> {
     pub fn new(source: &'a str) -> Result<Self, DocumentParseError> {
         let mut tokenizer = Tokenizer::from(source);
 
-        validate_document_start(source, tokenizer.next())?;
+        let root_span = validate_document_start(source, tokenizer.next())?;
 
-        let mut element_num = 1;
-        let mut text_num = 0;
-        let mut hierarchy = vec![0];
-        let mut text_store = Vec::new();
-        let mut element_store = vec![Some(Element {
-            local: Cow::Borrowed("document"),
+        let mut document = Self {
+            element_store: vec![Some(Element {
+                local: Name::Borrowed("document"),
+                ..Default::default()
+            })],
             ..Default::default()
-        })];
+        };
+        document.spans.start_element(0, root_span);
+
+        document.parse_children(source, tokenizer)?;
 
-        for token in tokenizer {
-            let token = token?;
-            let top_elem = *hierarchy.last().unwrap();
+        Ok(document)
+    }
+
+    // Parses the remaining tokens as descendants of the root element.
+    pub(crate) fn parse_children(
+        &mut self,
+        source: &'a str,
+        tokenizer: Tokenizer<'a>,
+    ) -> Result<(), DocumentParseError> {
+        self.parse_tokens(source, tokenizer, None).map(|_| ())
+    }
+
+    // Like `parse_children`, but problems are reported to `diagnostics` instead of stopping the
+    // parser when it's provided. Returns the elements left open.
+    pub(crate) fn parse_tokens<I: Iterator<Item = Result<Token<'a>, trax_parser::Error>>>(
+        &mut self,
+        source: &'a str,
+        tokens: I,
+        mut diagnostics: Option<&mut Vec<Diagnostic>>,
+    ) -> Result<Vec<NodeId>, DocumentParseError> {
+        let Self {
+            element_store,
+            text_store,
+            comment_store,
+            spans,
+            ..
+        } = self;
+
+        let mut element_num = element_store.len();
+        let mut text_num = text_store.len();
+        let mut comment_num = comment_store.len();
+        let mut hierarchy = vec![0];
+
+        for token in tokens {
+            let token = match (token, diagnostics.as_deref_mut()) {
+                (Ok(token), _) => token,
+                (Err(e), Some(diagnostics)) => {
+                    diagnostics.push(Diagnostic::syntax_error(source, e));
+                    break;
+                }
+                (Err(e), None) => return Err(e.into()),
+            };
+            // Only comments can follow the root element, and they aren't kept
+            let Some(&top_elem) = hierarchy.last() else {
+                continue;
+            };
 
             match token {
                 Token::ElementEnd {
                     end: ElementEnd::Empty,
-                    ..
+                    span,
                 } => {
+                    spans.end_start_tag(top_elem, span);
                     hierarchy.pop();
                 }
 
-                Token::ElementStart { prefix, local, .. } => {
+                Token::ElementEnd {
+                    end: ElementEnd::Open,
+                    span,
+                } => spans.end_start_tag(top_elem, span),
+
+                Token::ElementStart {
+                    prefix,
+                    local,
+                    span,
+                } => {
                     element_store[top_elem]
                         .as_mut()
                         .unwrap()
@@ -160,10 +441,11 @@ impl<'a> Document<'a> {
                         .push_back(EntityRef::Element(element_num));
 
                     hierarchy.push(element_num);
+                    spans.start_element(element_num, span);
                     element_store.push(Some(Element {
                         parent: top_elem,
-                        prefix: Cow::Borrowed(prefix.as_str()),
-                        local: Cow::Borrowed(local.as_str()),
+                        prefix: Name::Borrowed(prefix.as_str()),
+                        local: Name::Borrowed(local.as_str()),
                         ..Default::default()
                     }));
 
@@ -174,34 +456,45 @@ impl<'a> Document<'a> {
                     prefix,
                     local,
                     value,
-                    ..
-                } => element_store[top_elem]
-                    .as_mut()
-                    .unwrap()
-                    .attributes
-                    .push_back(Attribute::new(
-                        prefix.as_str(),
-                        local.as_str(),
-                        Some(value.as_str()),
-                    )),
-
-                Token::Modifier { prefix, local, .. } => element_store[top_elem]
-                    .as_mut()
-                    .unwrap()
-                    .attributes
-                    .push_back(Attribute::new(
-                        prefix.as_str(),
-                        local.as_str(),
-                        None::<&str>,
-                    )),
+                    span,
+                } => {
+                    spans.attribute(top_elem, prefix, local, Some(value), span);
+                    element_store[top_elem]
+                        .as_mut()
+                        .unwrap()
+                        .attributes
+                        .push_back(Attribute::new(
+                            prefix.as_str(),
+                            local.as_str(),
+                            Some(unescape(value.as_str())),
+                        ))
+                }
+
+                Token::Modifier {
+                    prefix,
+                    local,
+                    span,
+                } => {
+                    spans.attribute(top_elem, prefix, local, None, span);
+                    element_store[top_elem]
+                        .as_mut()
+                        .unwrap()
+                        .attributes
+                        .push_back(Attribute::new(
+                            prefix.as_str(),
+                            local.as_str(),
+                            None::<&str>,
+                        ))
+                }
 
                 // Ending the *current* open element
                 Token::ElementEnd {
                     end: ElementEnd::Close(prefix, local),
-                    ..
+                    span,
                 } if element_store[top_elem].as_ref().unwrap().prefix == prefix.as_str()
                     && element_store[top_elem].as_ref().unwrap().local == local.as_str() =>
                 {
+                    spans.end_element(top_elem, span);
                     hierarchy.pop();
                 }
 
@@ -212,17 +505,47 @@ impl<'a> Document<'a> {
                     span,
                 } => {
                     let current_open_elem = &element_store[top_elem];
-                    return Err(DocumentParseError::InvalidTreeStructure {
+                    let error = DocumentParseError::InvalidTreeStructure {
                         closed_elem: gen_full_name(prefix.as_str(), &local),
                         current_open_elem: gen_full_name(
                             &current_open_elem.as_ref().unwrap().prefix,
                             &current_open_elem.as_ref().unwrap().local,
                         ),
                         location: r(source, span),
+                        span: span.range(),
+                    };
+
+                    let Some(diagnostics) = diagnostics.as_deref_mut() else {
+                        return Err(error);
+                    };
+
+                    // Close everything up to the matching ancestor, or ignore the stray end tag
+                    let ancestor = hierarchy.iter().rposition(|id| {
+                        let element = element_store[*id].as_ref().unwrap();
+                        element.prefix == prefix.as_str() && element.local == local.as_str()
                     });
+
+                    match ancestor {
+                        Some(i) => {
+                            for id in hierarchy.drain(i + 1..) {
+                                let element = element_store[id].as_ref().unwrap();
+                                diagnostics
+                                    .push(Diagnostic::unclosed(element, spans.start_tag(id)));
+                            }
+                            spans.end_element(hierarchy[i], span);
+                            hierarchy.truncate(i);
+                        }
+                        None => diagnostics.push(Diagnostic::new(
+                            Severity::Error,
+                            DiagnosticCode::MismatchedTag,
+                            Some(span.range()),
+                            error.to_string(),
+                        )),
+                    }
                 }
 
                 Token::Text { text } => {
+                    spans.text(text_num, text);
                     element_store[top_elem]
                         .as_mut()
                         .unwrap()
@@ -231,100 +554,89 @@ impl<'a> Document<'a> {
 
                     text_store.push(Some(Text {
                         parent: top_elem,
-                        content: Cow::Borrowed(text.as_str()),
+                        content: unescape(text.as_str()),
                     }));
 
                     text_num += 1;
                 }
 
-                _ => (),
+                Token::Comment { text, span } => {
+                    spans.comment(comment_num, span);
+                    element_store[top_elem]
+                        .as_mut()
+                        .unwrap()
+                        .children
+                        .push_back(EntityRef::Comment(comment_num));
+
+                    comment_store.push(Some(Comment {
+                        parent: top_elem,
+                        content: Cow::Borrowed(text.as_str()),
+                    }));
+
+                    comment_num += 1;
+                }
             }
         }
 
-        Ok(Self {
-            element_store,
-            text_store,
-        })
+        Ok(hierarchy)
     }
 
-    /// Render the document to plaintext.
-    pub fn into_string(&self) -> String {
-        self.elem_into_string(0, 0)
+    /// Converts the document into one that doesn't borrow from its source, so it can outlive it.
+    ///
+    /// Element and attribute names are interned, so repeated names share a single allocation.
+    pub fn into_owned(self) -> OwnedDocument {
+        self.into_owned_with(&mut Interner::new())
     }
 
-    fn elem_into_string(&self, root: usize, tab_level: usize) -> String {
-        let mut res = String::new();
-
-        let element = &self.element_store[root].as_ref().unwrap();
-        let full_name = gen_full_name(&element.prefix, &element.local);
-
-        for _ in 0..tab_level {
-            res += "\t"
+    /// Like [`Document::into_owned`], but shares names with every other document converted using
+    /// the same `interner`.
+    pub fn into_owned_with(self, interner: &mut Interner) -> OwnedDocument {
+        Document {
+            element_store: self
+                .element_store
+                .into_iter()
+                .map(|e| e.map(|e| e.into_owned(interner)))
+                .collect(),
+            text_store: self
+                .text_store
+                .into_iter()
+                .map(|t| t.map(Text::into_owned))
+                .collect(),
+            comment_store: self
+                .comment_store
+                .into_iter()
+                .map(|c| c.map(Comment::into_owned))
+                .collect(),
+            spans: self.spans.into_owned(interner),
+            observers: self.observers,
         }
-
-        res += "<";
-        res += &full_name;
-
-        for attr in &element.attributes {
-            res += " ";
-            res += &gen_full_name(&attr.prefix, &attr.local);
-            if let Some(val) = &attr.value {
-                res += "=\"";
-                res += val;
-                res += "\"";
-            }
-        }
-
-        if element.children.is_empty() {
-            res += " />\n"
-        } else {
-            res += ">\n";
-            for entity_ref in &element.children {
-                match entity_ref {
-                    EntityRef::Element(child) => {
-                        res += &self.elem_into_string(*child, tab_level + 1)
-                    }
-                    EntityRef::Text(child) => {
-                        for _ in 0..tab_level + 1 {
-                            res += "\t"
-                        }
-                        res += &self.text_store[*child].as_ref().unwrap().content;
-                        res += "\n";
-                    }
-                };
-            }
-
-            for _ in 0..tab_level {
-                res += "\t"
-            }
-            res += "</";
-            res += &full_name;
-            res += ">\n";
-        }
-
-        res
     }
 }
 
-fn validate_document_start(
+fn validate_document_start<'a>(
     document_source: &str,
-    first_token: Option<Result<Token, trax_parser::Error>>,
-) -> Result<(), DocumentParseError> {
+    first_token: Option<Result<Token<'a>, trax_parser::Error>>,
+) -> Result<StrSpan<'a>, DocumentParseError> {
     match first_token {
-        Some(Ok(Token::ElementStart { prefix, local, .. }))
-            if local.as_str() == "document" && prefix.as_str().is_empty() =>
-        {
-            Ok(())
-        }
-        Some(Ok(token)) => Err(DocumentParseError::InvalidRootElement(r(
-            document_source,
-            token.span(),
-        ))),
+        Some(Ok(Token::ElementStart {
+            prefix,
+            local,
+            span,
+        })) if local.as_str() == "document" && prefix.as_str().is_empty() => Ok(span),
+        Some(Ok(token)) => Err(DocumentParseError::InvalidRootElement(
+            r(document_source, token.span()),
+            token.span().range(),
+        )),
         Some(Err(e)) => Err(e.into()),
         None => Err(DocumentParseError::EmptyDocument),
     }
 }
 
+// Splits `prefix:local` into its parts. Names without a prefix have an empty one.
+fn split_name(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("", name))
+}
+
 fn gen_full_name(prefix: &str, local: &str) -> String {
     if !prefix.is_empty() {
         format!("{prefix}:{local}")
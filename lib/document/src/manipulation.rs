@@ -2,57 +2,131 @@ use std::{borrow::Cow, collections::VecDeque};
 
 use thiserror::Error;
 
-use crate::{Attribute, Document, Element, EntityRef};
+use crate::{
+    gen_full_name, Attribute, ChangeEvent, Comment, Document, Element, EntityRef, Fragment, Name,
+    NodeId, Text,
+};
 
-#[derive(Debug, Error)]
+/// An error encountered when inserting into a [`Document`].
+#[derive(Debug, Error, PartialEq)]
 pub enum InsertElementError {
+    /// The parent element doesn't exist.
     #[error("couldn't insert into {0} because it wasn't found")]
     NotFound(EntityRef),
 
-    #[error("couldn't replace child {0} in {1} because {1} only has {2} children")]
-    ReplaceChildOutOfRange(usize, EntityRef, usize),
+    /// The requested position is outside of the parent's children.
+    #[error("couldn't insert at {0:?} in {1} because it only has {2} children")]
+    PositionOutOfRange(PlacePosition, EntityRef, usize),
 
+    /// The child being replaced couldn't be dropped.
     #[error("error while replacing child {0} in {1}: {2}")]
     DropEntityError(usize, EntityRef, DropEntityError),
 }
 
-#[derive(Debug, Error)]
+/// An error encountered when dropping an entity from a [`Document`].
+#[derive(Debug, Error, PartialEq)]
 pub enum DropEntityError {
+    /// The root `<document>` element can't be dropped.
     #[error("the root `<document>` tag cannot be dropped")]
     RefuseDropRoot,
 
+    /// The entity doesn't exist.
     #[error("couldn't drop the {0} because it wasn't found")]
     NotFound(EntityRef),
 }
 
+/// An error encountered when modifying an entity of a [`Document`].
+#[derive(Debug, Error, PartialEq)]
+pub enum ModifyEntityError {
+    /// The entity doesn't exist.
+    #[error("couldn't modify the {0} because it wasn't found")]
+    NotFound(EntityRef),
+
+    /// The element doesn't have the attribute.
+    #[error("couldn't find attribute `{1}` in {0}")]
+    AttributeNotFound(EntityRef, String),
+
+    /// The index is outside of the entity's children or attributes.
+    #[error("index {1} is out of range for {0}, which only has {2} items")]
+    IndexOutOfRange(EntityRef, usize, usize),
+
+    /// The root `<document>` element can't be moved.
+    #[error("the root `<document>` tag cannot be moved")]
+    RefuseMoveRoot,
+
+    /// A property can only be replaced by its position, but no position was given.
+    #[error("couldn't pick a property of {0} without a position")]
+    MissingPosition(EntityRef),
+}
+
 /// The position an item should be placed within its parent.
+///
+/// Mirrors the `start`, `end` and `index` properties of the spec's `<insert>` directive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlacePosition {
-    /// Insert at the front, pushing others to the back.
-    InsertFront,
-    /// Insert at the back.
-    InsertBack,
-    /// Insert at N, capped at the back of the parent.
-    InsertFrontN(usize),
-    /// Insert at the Nth last position, capped at the front of the parent.
-    InsertBackN(usize),
-    /// Replace the Nth item.
+    /// Place as the first child (`start`).
+    Start,
+    /// Place as the last child (`end`).
+    End,
+    /// Place at the Nth position, so that N children precede it (`start index="N"`).
+    StartIndex(usize),
+    /// Place at the Nth-to-last position, so that N children follow it (`end index="N"`).
+    EndIndex(usize),
+    /// Replace the Nth child (`index="N"`).
     Replace(usize),
 }
 
+impl PlacePosition {
+    /// Resolves the index the new child will occupy in a parent with `len` children, or `None`
+    /// if the position is out of range.
+    pub fn resolve(self, len: usize) -> Option<usize> {
+        match self {
+            PlacePosition::Start => Some(0),
+            PlacePosition::End => Some(len),
+            PlacePosition::StartIndex(n) => (n <= len).then_some(n),
+            PlacePosition::EndIndex(n) => len.checked_sub(n),
+            PlacePosition::Replace(n) => (n < len).then_some(n),
+        }
+    }
+
+    /// Reads a position from the `start` and `end` modifiers and `index` property of an
+    /// `<insert>`. Returns `None` if none of them are given, in which case the target itself is
+    /// replaced. `start` takes precedence over `end`.
+    ///
+    /// ```rust
+    /// use trax_document::PlacePosition;
+    ///
+    /// assert_eq!(PlacePosition::from_properties(false, true, Some(1)), Some(PlacePosition::EndIndex(1)));
+    /// assert_eq!(PlacePosition::from_properties(false, false, Some(1)), Some(PlacePosition::Replace(1)));
+    /// assert_eq!(PlacePosition::from_properties(false, false, None), None);
+    /// ```
+    pub fn from_properties(start: bool, end: bool, index: Option<usize>) -> Option<Self> {
+        Some(match (start, end, index) {
+            (true, _, None) => PlacePosition::Start,
+            (true, _, Some(n)) => PlacePosition::StartIndex(n),
+            (false, true, None) => PlacePosition::End,
+            (false, true, Some(n)) => PlacePosition::EndIndex(n),
+            (false, false, Some(n)) => PlacePosition::Replace(n),
+            (false, false, None) => return None,
+        })
+    }
+}
+
 impl<'a> Document<'a> {
-    /// Insert a new element into the document.
-    pub fn insert<
-        C: Into<Cow<'a, str>>,
-        C2: Into<Cow<'a, str>>,
-        VD: Into<VecDeque<Attribute<'a>>>,
-    >(
+    /// Insert a new element into the document, returning its id.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn insert<C: Into<Name<'a>>, C2: Into<Name<'a>>, VD: Into<VecDeque<Attribute<'a>>>>(
         &mut self,
-        parent_id: usize,
+        parent_id: NodeId,
         place_position: PlacePosition,
         prefix: C,
-        local: C,
+        local: C2,
         attributes: VD,
-    ) -> Result<(), InsertElementError> {
+    ) -> Result<NodeId, InsertElementError> {
+        let index = self.make_room(parent_id, place_position)?;
+        let element_id = self.element_store.len();
+
         self.element_store.push(Some(Element {
             parent: parent_id,
             prefix: prefix.into(),
@@ -61,90 +135,304 @@ impl<'a> Document<'a> {
             ..Default::default()
         }));
 
-        let element_num = self.element_store.len();
-
-        if self.element_store[parent_id].is_some() {
-            let parent_end = self.element_store[parent_id]
-                .as_mut()
-                .unwrap()
-                .children
-                .len()
-                - 1;
-            match place_position {
-                PlacePosition::InsertFront => {
-                    self.element_store[parent_id]
-                        .as_mut()
-                        .unwrap()
-                        .children
-                        .push_front(EntityRef::Element(element_num));
-                    Ok(())
-                }
-                PlacePosition::InsertBack => {
-                    self.element_store[parent_id]
-                        .as_mut()
-                        .unwrap()
-                        .children
-                        .push_back(EntityRef::Element(element_num));
-                    Ok(())
-                }
-                PlacePosition::InsertFrontN(n) => {
-                    self.element_store[parent_id]
-                        .as_mut()
-                        .unwrap()
-                        .children
-                        .insert(n.min(parent_end), EntityRef::Element(element_num));
+        self.element_store[parent_id]
+            .as_mut()
+            .unwrap()
+            .children
+            .insert(index, EntityRef::Element(element_id));
 
-                    Ok(())
-                }
-                PlacePosition::InsertBackN(n) => {
-                    self.element_store[parent_id]
-                        .as_mut()
-                        .unwrap()
-                        .children
-                        .insert(
-                            parent_end.saturating_sub(n),
-                            EntityRef::Element(element_num),
-                        );
+        self.notify(|| ChangeEvent::Inserted {
+            node: EntityRef::Element(element_id),
+            parent: parent_id,
+            index,
+        });
 
-                    Ok(())
-                }
-                PlacePosition::Replace(n) => {
-                    if n > self.element_store[parent_id]
-                        .as_ref()
-                        .unwrap()
-                        .children
-                        .len()
-                        - 1
-                    {
-                        Err(InsertElementError::ReplaceChildOutOfRange(
-                            n,
-                            EntityRef::Element(parent_id),
-                            self.element_store[parent_id]
-                                .as_ref()
-                                .unwrap()
-                                .children
-                                .len()
-                                - 1,
-                        ))
-                    } else {
-                        self.drop(
-                            self.element_store[parent_id].as_ref().unwrap().children[n].clone(),
-                        )
-                        .map_err(|e| {
-                            InsertElementError::DropEntityError(n, EntityRef::Element(parent_id), e)
-                        })?;
-                        Ok(())
-                    }
+        Ok(element_id)
+    }
+
+    /// Insert a new segment of text into the document, returning its id.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn insert_text<C: Into<Cow<'a, str>>>(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        content: C,
+    ) -> Result<usize, InsertElementError> {
+        let index = self.make_room(parent_id, place_position)?;
+        let text_id = self.text_store.len();
+
+        self.text_store.push(Some(Text {
+            parent: parent_id,
+            content: content.into(),
+        }));
+
+        self.element_store[parent_id]
+            .as_mut()
+            .unwrap()
+            .children
+            .insert(index, EntityRef::Text(text_id));
+
+        self.notify(|| ChangeEvent::Inserted {
+            node: EntityRef::Text(text_id),
+            parent: parent_id,
+            index,
+        });
+
+        Ok(text_id)
+    }
+
+    /// Insert a new comment into the document, returning its id. The content doesn't include the
+    /// `/*` and `*/` delimiters.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn insert_comment<C: Into<Cow<'a, str>>>(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        content: C,
+    ) -> Result<usize, InsertElementError> {
+        let index = self.make_room(parent_id, place_position)?;
+        let comment_id = self.comment_store.len();
+
+        self.comment_store.push(Some(Comment {
+            parent: parent_id,
+            content: content.into(),
+        }));
+
+        self.element_store[parent_id]
+            .as_mut()
+            .unwrap()
+            .children
+            .insert(index, EntityRef::Comment(comment_id));
+
+        self.notify(|| ChangeEvent::Inserted {
+            node: EntityRef::Comment(comment_id),
+            parent: parent_id,
+            index,
+        });
+
+        Ok(comment_id)
+    }
+
+    // Validates the position and drops the replaced child, if any. Returns the index the new
+    // child should be inserted at.
+    fn make_room(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+    ) -> Result<usize, InsertElementError> {
+        let Some(Some(parent)) = self.element_store.get(parent_id) else {
+            return Err(InsertElementError::NotFound(EntityRef::Element(parent_id)));
+        };
+
+        let len = parent.children.len();
+        let Some(index) = place_position.resolve(len) else {
+            return Err(InsertElementError::PositionOutOfRange(
+                place_position,
+                EntityRef::Element(parent_id),
+                len,
+            ));
+        };
+
+        if let PlacePosition::Replace(n) = place_position {
+            self.drop(parent.children[n].clone()).map_err(|e| {
+                InsertElementError::DropEntityError(n, EntityRef::Element(parent_id), e)
+            })?;
+        }
+
+        Ok(index)
+    }
+
+    /// Insert a detached subtree into the document, returning the id of its root.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn insert_fragment(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        fragment: Fragment<'a>,
+    ) -> Result<EntityRef, InsertElementError> {
+        match fragment {
+            Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            } => {
+                let id = self.insert(parent_id, place_position, prefix, local, attributes)?;
+                for child in children {
+                    self.insert_fragment(id, PlacePosition::End, child)?;
                 }
+                Ok(EntityRef::Element(id))
+            }
+            Fragment::Text(content) => self
+                .insert_text(parent_id, place_position, content)
+                .map(EntityRef::Text),
+            Fragment::Comment(content) => self
+                .insert_comment(parent_id, place_position, content)
+                .map(EntityRef::Comment),
+        }
+    }
+
+    /// Set an attribute of an element, replacing the attribute with the same name or appending
+    /// it. Returns the replaced attribute.
+    pub fn set_attribute(
+        &mut self,
+        element_id: NodeId,
+        attribute: Attribute<'a>,
+    ) -> Result<Option<Attribute<'a>>, ModifyEntityError> {
+        let name = gen_full_name(&attribute.prefix, &attribute.local);
+        let attributes = self.attributes_mut(element_id)?;
+
+        let old = match attributes
+            .iter()
+            .position(|a| a.prefix == attribute.prefix && a.local == attribute.local)
+        {
+            Some(i) => {
+                let old = std::mem::replace(&mut attributes[i], attribute);
+                self.spans
+                    .forget_attribute(element_id, &old.prefix, &old.local);
+                Some(old)
+            }
+            None => {
+                attributes.push_back(attribute);
+                None
+            }
+        };
+
+        self.notify(|| ChangeEvent::AttributeChanged {
+            element: element_id,
+            name,
+        });
+
+        Ok(old)
+    }
+
+    /// Remove an attribute from an element, returning it.
+    pub fn remove_attribute(
+        &mut self,
+        element_id: NodeId,
+        prefix: &str,
+        local: &str,
+    ) -> Result<Attribute<'a>, ModifyEntityError> {
+        let attributes = self.attributes_mut(element_id)?;
+
+        match attributes
+            .iter()
+            .position(|a| a.prefix == prefix && a.local == local)
+        {
+            Some(i) => {
+                let old = attributes.remove(i).unwrap();
+                self.spans.forget_attribute(element_id, prefix, local);
+                self.notify(|| ChangeEvent::AttributeChanged {
+                    element: element_id,
+                    name: gen_full_name(prefix, local),
+                });
+                Ok(old)
+            }
+            None => Err(ModifyEntityError::AttributeNotFound(
+                EntityRef::Element(element_id),
+                gen_full_name(prefix, local),
+            )),
+        }
+    }
+
+    /// Replace the content of a segment of text, returning the previous content.
+    pub fn set_text<C: Into<Cow<'a, str>>>(
+        &mut self,
+        text_id: usize,
+        content: C,
+    ) -> Result<Cow<'a, str>, ModifyEntityError> {
+        match self.text_store.get_mut(text_id) {
+            Some(Some(text)) => {
+                let old = std::mem::replace(&mut text.content, content.into());
+                self.spans.forget_text(text_id);
+                self.notify(|| ChangeEvent::TextChanged { text: text_id });
+                Ok(old)
             }
-        } else {
-            Err(InsertElementError::NotFound(EntityRef::Element(parent_id)))
+            _ => Err(ModifyEntityError::NotFound(EntityRef::Text(text_id))),
+        }
+    }
+
+    /// Move an entity to the Nth position within its parent, returning its previous position.
+    pub fn move_to(
+        &mut self,
+        entity_ref: EntityRef,
+        index: usize,
+    ) -> Result<usize, ModifyEntityError> {
+        if entity_ref == EntityRef::Element(0) {
+            return Err(ModifyEntityError::RefuseMoveRoot);
+        }
+
+        let Some((parent, from)) = self.locate(&entity_ref) else {
+            return Err(ModifyEntityError::NotFound(entity_ref));
+        };
+
+        let children = &mut self.element_store[parent].as_mut().unwrap().children;
+        if index >= children.len() {
+            return Err(ModifyEntityError::IndexOutOfRange(
+                entity_ref,
+                index,
+                children.len(),
+            ));
+        }
+
+        let child = children.remove(from).unwrap();
+        children.insert(index, child);
+
+        self.notify(|| ChangeEvent::Moved {
+            node: entity_ref,
+            parent,
+            from,
+            to: index,
+        });
+
+        Ok(from)
+    }
+
+    /// Returns the parent of an entity and its position within the parent's children.
+    pub(crate) fn locate(&self, entity_ref: &EntityRef) -> Option<(NodeId, usize)> {
+        let parent = match entity_ref {
+            EntityRef::Element(0) => return None,
+            EntityRef::Element(i) => self.element_store.get(*i)?.as_ref()?.parent,
+            EntityRef::Text(i) => self.text_store.get(*i)?.as_ref()?.parent,
+            EntityRef::Comment(i) => self.comment_store.get(*i)?.as_ref()?.parent,
+        };
+
+        let index = self.element_store[parent]
+            .as_ref()?
+            .children
+            .iter()
+            .position(|c| c == entity_ref)?;
+
+        Some((parent, index))
+    }
+
+    pub(crate) fn attributes_mut(
+        &mut self,
+        element_id: NodeId,
+    ) -> Result<&mut VecDeque<Attribute<'a>>, ModifyEntityError> {
+        match self.element_store.get_mut(element_id) {
+            Some(Some(element)) => Ok(&mut element.attributes),
+            _ => Err(ModifyEntityError::NotFound(EntityRef::Element(element_id))),
         }
     }
 
     /// Manually drop an entity and its children
     pub fn drop(&mut self, entity_ref: EntityRef) -> Result<(), DropEntityError> {
-        self.drop_impl(entity_ref, true)
+        let location = self.locate(&entity_ref);
+        self.drop_impl(entity_ref.clone(), true)?;
+
+        if let Some((parent, index)) = location {
+            self.notify(|| ChangeEvent::Dropped {
+                node: entity_ref,
+                parent,
+                index,
+            });
+        }
+
+        Ok(())
     }
 
     // this code may look dirty and disgusting, but it's incredibly fast. that's true beauty, baby
@@ -240,6 +528,23 @@ impl<'a> Document<'a> {
                     Err(DropEntityError::NotFound(entity_ref.clone()))
                 }
             }
+            EntityRef::Comment(i) => {
+                let Some(Some(comment)) = self.comment_store.get(i) else {
+                    return Err(DropEntityError::NotFound(entity_ref));
+                };
+
+                if is_parent {
+                    let parent = comment.parent;
+                    let children = &mut self.element_store[parent].as_mut().unwrap().children;
+                    if let Some(s) = children.iter().position(|c| c == &entity_ref) {
+                        children.remove(s);
+                    }
+                }
+
+                self.comment_store[i] = None;
+
+                Ok(())
+            }
         }
     }
 }
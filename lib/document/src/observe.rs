@@ -0,0 +1,135 @@
+use std::fmt::Debug;
+
+use crate::{Document, EntityRef, NodeId};
+
+/// A change made to a [`Document`]. See [`Document::subscribe`].
+///
+/// Events are emitted after the change is made, so the document can be inspected from the
+/// subscriber.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeEvent {
+    /// An entity was inserted. Inserting a [`Fragment`](crate::Fragment) emits an event for
+    /// each of its entities, parents first, while restoring a dropped subtree (e.g. when
+    /// reverting a [`Changeset`](crate::Changeset)) emits a single event for its root.
+    Inserted {
+        /// The inserted entity.
+        node: EntityRef,
+        /// The parent of the inserted entity.
+        parent: NodeId,
+        /// The position of the entity within its parent.
+        index: usize,
+    },
+    /// An entity and its descendants were dropped.
+    Dropped {
+        /// The dropped entity, which no longer exists.
+        node: EntityRef,
+        /// The former parent of the entity.
+        parent: NodeId,
+        /// The former position of the entity within its parent.
+        index: usize,
+    },
+    /// An entity was moved within its parent.
+    Moved {
+        /// The moved entity.
+        node: EntityRef,
+        /// The parent of the entity.
+        parent: NodeId,
+        /// The former position of the entity.
+        from: usize,
+        /// The new position of the entity.
+        to: usize,
+    },
+    /// An attribute was set or removed.
+    AttributeChanged {
+        /// The element owning the attribute.
+        element: NodeId,
+        /// The full name of the attribute, e.g. `pin:bottom`.
+        name: String,
+    },
+    /// The content of a segment of text changed.
+    TextChanged {
+        /// The id of the text.
+        text: usize,
+    },
+}
+
+/// Identifies a subscriber of a [`Document`], used to unsubscribe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(usize);
+
+type Subscriber = Box<dyn FnMut(&ChangeEvent) + Send + Sync>;
+
+/// The subscribers of a document.
+///
+/// Subscribers aren't part of the document's contents: they're not copied when the document is
+/// cloned and are ignored when comparing documents.
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: usize,
+    subscribers: Vec<(SubscriptionId, Subscriber)>,
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for Observers {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} subscribers", self.subscribers.len())
+    }
+}
+
+impl Document<'_> {
+    /// Call `subscriber` with every change made to the document from now on.
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use trax_document::{ChangeEvent, Document, EntityRef, PlacePosition};
+    ///
+    /// let mut doc = Document::new("<document></document>").unwrap();
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let sink = events.clone();
+    /// doc.subscribe(move |e| sink.lock().unwrap().push(e.clone()));
+    /// doc.insert(0, PlacePosition::End, "", "a", []).unwrap();
+    ///
+    /// assert_eq!(
+    ///     *events.lock().unwrap(),
+    ///     [ChangeEvent::Inserted { node: EntityRef::Element(1), parent: 0, index: 0 }]
+    /// );
+    /// ```
+    pub fn subscribe<F: FnMut(&ChangeEvent) + Send + Sync + 'static>(
+        &mut self,
+        subscriber: F,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers.subscribers.push((id, Box::new(subscriber)));
+        id
+    }
+
+    /// Stop calling a subscriber. Returns `false` if it wasn't subscribed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.observers.subscribers.len();
+        self.observers.subscribers.retain(|(i, _)| *i != id);
+        self.observers.subscribers.len() != len
+    }
+
+    // Only builds the event if anyone is listening.
+    pub(crate) fn notify<F: FnOnce() -> ChangeEvent>(&mut self, event: F) {
+        if !self.observers.subscribers.is_empty() {
+            let event = event();
+            for (_, subscriber) in &mut self.observers.subscribers {
+                subscriber(&event);
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    fmt::{self, Display, Write},
+    io,
+};
+
+use crate::{
+    escape::{value_width, write_text, write_value},
+    Attribute, Document, Element, EntityRef, NodeId,
+};
+
+/// How nested entities are indented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// One tab per level.
+    Tabs,
+    /// N spaces per level.
+    Spaces(usize),
+}
+
+/// How elements without children are closed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfClosing {
+    /// `<name />`
+    Spaced,
+    /// `<name/>`
+    Compact,
+    /// `<name></name>`
+    Never,
+}
+
+/// The preferred quote character for attribute values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quote {
+    /// `key="value"`
+    Double,
+    /// `key='value'`
+    Single,
+}
+
+impl Quote {
+    pub(crate) fn char(self) -> char {
+        match self {
+            Quote::Double => '"',
+            Quote::Single => '\'',
+        }
+    }
+
+    fn other(self) -> Quote {
+        match self {
+            Quote::Double => Quote::Single,
+            Quote::Single => Quote::Double,
+        }
+    }
+
+    // Prefers `self`, unless only the other quote can enclose the value without escaping.
+    pub(crate) fn for_value(self, value: &str) -> Quote {
+        if value.contains(self.char()) && !value.contains(self.other().char()) {
+            self.other()
+        } else {
+            self
+        }
+    }
+}
+
+/// Options controlling how a [`Document`] is rendered.
+///
+/// The default options reproduce the output of [`Document::into_string`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// The indentation used for each level of nesting.
+    pub indent: Indent,
+    /// The width of a tab, used when measuring lines against `max_width`.
+    pub tab_width: usize,
+    /// Start tags longer than this are wrapped, placing each attribute on its own line.
+    pub max_width: Option<usize>,
+    /// How elements without children are closed.
+    pub self_closing: SelfClosing,
+    /// The preferred quote character for attribute values.
+    pub quote: Quote,
+    /// Render everything on a single line without indentation.
+    pub compact: bool,
+    /// Escape `&` and `<` in text and attribute values, and quotes in attribute values, as
+    /// entity references, which are decoded when the output is parsed again. Without escaping,
+    /// text and values containing them can't be read back as they were.
+    pub escape: bool,
+    /// Leave out `/* ... */` comments.
+    pub strip_comments: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Tabs,
+            tab_width: 4,
+            max_width: None,
+            self_closing: SelfClosing::Spaced,
+            quote: Quote::Double,
+            compact: false,
+            escape: true,
+            strip_comments: false,
+        }
+    }
+}
+
+/// A [`Document`] paired with the [`RenderOptions`] used to display it. See [`Document::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct Rendered<'d, 'a> {
+    document: &'d Document<'a>,
+    options: &'d RenderOptions,
+}
+
+impl<'a> Document<'a> {
+    /// Render the document to plaintext.
+    pub fn into_string(&self) -> String {
+        self.render(&RenderOptions::default())
+    }
+
+    /// Render the document to plaintext using the given options.
+    pub fn render(&self, options: &RenderOptions) -> String {
+        self.display(options).to_string()
+    }
+
+    /// Returns a value which renders the document using the given options when displayed.
+    pub fn display<'d>(&'d self, options: &'d RenderOptions) -> Rendered<'d, 'a> {
+        Rendered {
+            document: self,
+            options,
+        }
+    }
+
+    /// Stream the rendered document into a writer.
+    pub fn write_to<W: io::Write>(&self, mut writer: W, options: &RenderOptions) -> io::Result<()> {
+        write!(writer, "{}", self.display(options))
+    }
+}
+
+impl Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(&RenderOptions::default()).fmt(f)
+    }
+}
+
+impl Display for Rendered<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_element(f, 0, 0)
+    }
+}
+
+impl Rendered<'_, '_> {
+    fn write_element(&self, f: &mut fmt::Formatter<'_>, id: NodeId, level: usize) -> fmt::Result {
+        let element = self.document.element_store[id].as_ref().unwrap();
+        let opts = self.options;
+
+        self.write_indent(f, level)?;
+        f.write_char('<')?;
+        write_name(f, &element.prefix, &element.local)?;
+
+        let wrap = opts.max_width.is_some_and(|max| {
+            !opts.compact
+                && !element.attributes.is_empty()
+                && self.start_tag_width(element, level) > max
+        });
+
+        for attr in &element.attributes {
+            if wrap {
+                f.write_char('\n')?;
+                self.write_indent(f, level + 1)?;
+            } else {
+                f.write_char(' ')?;
+            }
+            self.write_attribute(f, attr)?;
+        }
+
+        if !self.has_children(element) {
+            match opts.self_closing {
+                SelfClosing::Spaced if wrap => f.write_str("/>")?,
+                SelfClosing::Spaced => f.write_str(" />")?,
+                SelfClosing::Compact => f.write_str("/>")?,
+                SelfClosing::Never => {
+                    f.write_str("></")?;
+                    write_name(f, &element.prefix, &element.local)?;
+                    f.write_char('>')?;
+                }
+            }
+            return self.write_newline(f);
+        }
+
+        f.write_char('>')?;
+        self.write_newline(f)?;
+
+        for entity_ref in &element.children {
+            match entity_ref {
+                EntityRef::Element(child) => self.write_element(f, *child, level + 1)?,
+                EntityRef::Text(child) => {
+                    self.write_indent(f, level + 1)?;
+                    let content = &self.document.text_store[*child].as_ref().unwrap().content;
+                    write_text(f, content, opts.escape)?;
+                    self.write_newline(f)?;
+                }
+                EntityRef::Comment(_) if opts.strip_comments => (),
+                EntityRef::Comment(child) => {
+                    self.write_indent(f, level + 1)?;
+                    let content = &self.document.comment_store[*child]
+                        .as_ref()
+                        .unwrap()
+                        .content;
+                    write!(f, "/*{content}*/")?;
+                    self.write_newline(f)?;
+                }
+            }
+        }
+
+        self.write_indent(f, level)?;
+        f.write_str("</")?;
+        write_name(f, &element.prefix, &element.local)?;
+        f.write_char('>')?;
+        self.write_newline(f)
+    }
+
+    fn has_children(&self, element: &Element) -> bool {
+        if self.options.strip_comments {
+            element
+                .children
+                .iter()
+                .any(|c| !matches!(c, EntityRef::Comment(_)))
+        } else {
+            !element.children.is_empty()
+        }
+    }
+
+    fn write_attribute(&self, f: &mut fmt::Formatter<'_>, attr: &Attribute) -> fmt::Result {
+        write_name(f, &attr.prefix, &attr.local)?;
+
+        if let Some(value) = &attr.value {
+            f.write_char('=')?;
+            write_value(
+                f,
+                value,
+                self.options.quote.for_value(value),
+                self.options.escape,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn write_indent(&self, f: &mut fmt::Formatter<'_>, level: usize) -> fmt::Result {
+        if self.options.compact {
+            return Ok(());
+        }
+
+        match self.options.indent {
+            Indent::Tabs => (0..level).try_for_each(|_| f.write_char('\t')),
+            Indent::Spaces(n) => write!(f, "{:width$}", "", width = n * level),
+        }
+    }
+
+    fn write_newline(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.compact {
+            Ok(())
+        } else {
+            f.write_char('\n')
+        }
+    }
+
+    // The width of the element's start tag when rendered on a single line.
+    fn start_tag_width(&self, element: &Element, level: usize) -> usize {
+        let indent = match self.options.indent {
+            Indent::Tabs => self.options.tab_width * level,
+            Indent::Spaces(n) => n * level,
+        };
+
+        let attributes: usize = element
+            .attributes
+            .iter()
+            .map(|attr| {
+                let mut width = 1 + name_width(&attr.prefix, &attr.local);
+                if let Some(value) = &attr.value {
+                    let quote = self.options.quote.for_value(value);
+                    width += 3 + value_width(value, quote, self.options.escape);
+                }
+                width
+            })
+            .sum();
+
+        let end = if self.has_children(element) { 1 } else { 3 };
+
+        indent + 1 + name_width(&element.prefix, &element.local) + attributes + end
+    }
+}
+
+fn write_name(f: &mut fmt::Formatter<'_>, prefix: &str, local: &str) -> fmt::Result {
+    if !prefix.is_empty() {
+        f.write_str(prefix)?;
+        f.write_char(':')?;
+    }
+    f.write_str(local)
+}
+
+fn name_width(prefix: &str, local: &str) -> usize {
+    if prefix.is_empty() {
+        local.chars().count()
+    } else {
+        prefix.chars().count() + 1 + local.chars().count()
+    }
+}
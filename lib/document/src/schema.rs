@@ -0,0 +1,488 @@
+use std::{collections::HashMap, fmt::Display, ops::Range, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    diagnostic::KNOWN_PREFIXES, gen_full_name, split_name, Attribute, Diagnostic, DiagnosticCode,
+    Document, DocumentParseError, Element, EntityRef, NodeId, Severity, Type, Value,
+};
+
+/// An error encountered when loading a [`Schema`] from a document.
+#[derive(Debug, Error, PartialEq)]
+pub enum SchemaError {
+    /// The schema isn't a valid document. See [`DocumentParseError`].
+    #[error(transparent)]
+    Parse(#[from] DocumentParseError),
+
+    /// An element of the schema is missing a property.
+    #[error("`{0}` in the schema requires the `{1}` property")]
+    MissingProperty(String, &'static str),
+
+    /// An element of the schema isn't `<element>`, `<required>` or `<optional>`.
+    #[error("unexpected `{0}` in the schema")]
+    UnexpectedElement(String),
+
+    /// A property has a type which isn't in the spec's type list.
+    #[error("unknown type `{1}` for property `{0}`")]
+    UnknownType(String, String),
+
+    /// The same element is described more than once.
+    #[error("element `{0}` is described more than once")]
+    DuplicateElement(String),
+}
+
+/// The type of a property, from the spec's type list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PropertyType {
+    /// Any value, including none.
+    Any,
+    /// A modifier, which is `true` when present.
+    Bool,
+    /// Any string.
+    Str,
+    /// A signed 64-bit integer.
+    Int,
+    /// A floating point number.
+    Float,
+    /// A non-inclusive range of integers, e.g. `1..10`.
+    Range,
+    /// A TRAX URL.
+    Url,
+    /// The name of an element.
+    Element,
+}
+
+impl PropertyType {
+    /// Returns `true` if the value, or `None` for a modifier, is of this type. Values are checked
+    /// with [`Value::parse`], so they're accepted exactly when they can be read.
+    pub fn accepts(self, value: Option<&str>) -> bool {
+        let Some(value) = value else {
+            return matches!(self, PropertyType::Any | PropertyType::Bool);
+        };
+
+        let ty = match self {
+            PropertyType::Any | PropertyType::Str => return true,
+            PropertyType::Bool => return false,
+            PropertyType::Int => Type::Int,
+            PropertyType::Float => Type::Float,
+            PropertyType::Range => Type::Range,
+            PropertyType::Url => Type::Url,
+            // element names are written like modifiers, e.g. `with:prefix`
+            PropertyType::Element => Type::Modifier,
+        };
+        Value::parse(ty, value).is_ok()
+    }
+}
+
+impl Display for PropertyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PropertyType::Any => "any",
+            PropertyType::Bool => "bool",
+            PropertyType::Str => "str",
+            PropertyType::Int => "int",
+            PropertyType::Float => "float",
+            PropertyType::Range => "range",
+            PropertyType::Url => "url",
+            PropertyType::Element => "element",
+        })
+    }
+}
+
+impl FromStr for PropertyType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "any" => PropertyType::Any,
+            "bool" | "modifier" => PropertyType::Bool,
+            "str" => PropertyType::Str,
+            "int" => PropertyType::Int,
+            "float" => PropertyType::Float,
+            "range" => PropertyType::Range,
+            "url" | "URL" => PropertyType::Url,
+            "element" => PropertyType::Element,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// A property of an [`ElementSchema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertySchema {
+    /// The property's prefix.
+    pub prefix: String,
+    /// The property's local name.
+    pub local: String,
+    /// The type of the property's value.
+    pub ty: PropertyType,
+    /// Whether the element must have the property.
+    pub required: bool,
+    /// A description of the property.
+    pub description: String,
+}
+
+/// Describes the properties and children an element can have.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElementSchema {
+    /// The element's prefix.
+    pub prefix: String,
+    /// The element's local name.
+    pub local: String,
+    /// The element's required and optional properties.
+    pub properties: Vec<PropertySchema>,
+    /// The children allowed in the element, or `None` to allow anything. Each is the full name
+    /// of an element, `prefix:*` for any element with that prefix, or `#text` for text.
+    pub children: Option<Vec<String>>,
+}
+
+impl ElementSchema {
+    /// Returns the schema of a property by name.
+    pub fn property(&self, prefix: &str, local: &str) -> Option<&PropertySchema> {
+        self.properties
+            .iter()
+            .find(|p| p.prefix == prefix && p.local == local)
+    }
+
+    fn allows_child(&self, document: &Document, child: &EntityRef) -> bool {
+        let Some(children) = &self.children else {
+            return true;
+        };
+
+        match child {
+            EntityRef::Element(i) => {
+                let element = document.element_store[*i].as_ref().unwrap();
+                let name = gen_full_name(&element.prefix, &element.local);
+                children.iter().any(|allowed| {
+                    *allowed == name
+                        || allowed
+                            .strip_suffix(":*")
+                            .is_some_and(|prefix| element.prefix == prefix)
+                })
+            }
+            EntityRef::Text(_) => children.iter().any(|allowed| allowed == "#text"),
+            EntityRef::Comment(_) => true,
+        }
+    }
+}
+
+/// Describes a vocabulary of custom elements, used to check documents which use them.
+///
+/// A schema can be written in TRAX, describing each element with its required and optional
+/// properties:
+///
+/// ```rust
+/// use trax_document::{DiagnosticCode, Document, Schema};
+///
+/// let schema = Schema::parse(r##"<document>
+///     <element name="Button" children="#text">
+///         <required name="send" type="str">The event sent when clicked</required>
+///         <optional name="disabled" type="bool" />
+///     </element>
+/// </document>"##).unwrap();
+///
+/// let doc = Document::new(r#"<document><Button disabled="yes" /></document>"#).unwrap();
+/// let diagnostics = schema.validate(&doc);
+///
+/// assert_eq!(diagnostics[0].code, DiagnosticCode::MissingProperty);
+/// assert_eq!(diagnostics[1].code, DiagnosticCode::TypeMismatch);
+/// ```
+///
+/// Elements which aren't described by the schema aren't checked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    elements: HashMap<(String, String), ElementSchema>,
+}
+
+impl Schema {
+    /// Create an empty schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a schema written in TRAX.
+    pub fn parse(source: &str) -> Result<Self, SchemaError> {
+        Self::from_document(&Document::new(source)?)
+    }
+
+    /// Load a schema from a document.
+    pub fn from_document(document: &Document) -> Result<Self, SchemaError> {
+        let mut schema = Schema::new();
+
+        for element in document.child_elements(0) {
+            if !element.prefix.is_empty() || element.local != "element" {
+                return Err(SchemaError::UnexpectedElement(gen_full_name(
+                    &element.prefix,
+                    &element.local,
+                )));
+            }
+
+            let name = value_of(element, "name")
+                .ok_or(SchemaError::MissingProperty("element".into(), "name"))?;
+            let (prefix, local) = split_name(name);
+
+            let mut element_schema = ElementSchema {
+                prefix: prefix.to_string(),
+                local: local.to_string(),
+                properties: Vec::new(),
+                children: value_of(element, "children")
+                    .map(|c| c.split_whitespace().map(str::to_string).collect()),
+            };
+
+            for property in document.child_elements_of(element) {
+                let required = match (property.prefix.as_str(), property.local.as_str()) {
+                    ("", "required") => true,
+                    ("", "optional") => false,
+                    _ => {
+                        return Err(SchemaError::UnexpectedElement(gen_full_name(
+                            &property.prefix,
+                            &property.local,
+                        )))
+                    }
+                };
+
+                let name = value_of(property, "name").ok_or(SchemaError::MissingProperty(
+                    property.local.to_string(),
+                    "name",
+                ))?;
+                let ty = value_of(property, "type").unwrap_or("any");
+                let (prefix, local) = split_name(name);
+
+                element_schema.properties.push(PropertySchema {
+                    prefix: prefix.to_string(),
+                    local: local.to_string(),
+                    ty: ty
+                        .parse()
+                        .map_err(|_| SchemaError::UnknownType(name.into(), ty.into()))?,
+                    required,
+                    description: document.text_of(property),
+                });
+            }
+
+            if schema.get(prefix, local).is_some() {
+                return Err(SchemaError::DuplicateElement(name.into()));
+            }
+            schema.insert(element_schema);
+        }
+
+        Ok(schema)
+    }
+
+    /// Describe an element, replacing its previous description.
+    pub fn insert(&mut self, element: ElementSchema) -> Option<ElementSchema> {
+        self.elements
+            .insert((element.prefix.clone(), element.local.clone()), element)
+    }
+
+    /// Returns the description of an element by name.
+    pub fn get(&self, prefix: &str, local: &str) -> Option<&ElementSchema> {
+        self.elements.get(&(prefix.to_string(), local.to_string()))
+    }
+
+    /// Check every element described by the schema in the document.
+    ///
+    /// Properties with a runtime prefix, such as `pin:bottom` or `asEval:title`, are allowed on
+    /// any element. `asRef` and `asEval` properties are checked against the property they set,
+    /// without checking the type of their URL.
+    pub fn validate(&self, document: &Document) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(id) = stack.pop() {
+            let Some(Some(element)) = document.element_store.get(id) else {
+                continue;
+            };
+
+            if let Some(element_schema) = self.get(&element.prefix, &element.local) {
+                self.validate_element(document, id, element, element_schema, &mut diagnostics);
+            }
+
+            stack.extend(element.children.iter().rev().filter_map(|c| match c {
+                EntityRef::Element(i) => Some(*i),
+                _ => None,
+            }));
+        }
+
+        diagnostics
+    }
+
+    fn validate_element(
+        &self,
+        document: &Document,
+        id: NodeId,
+        element: &Element,
+        schema: &ElementSchema,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let span = document.element_span(id);
+        let start_tag = span.map(|s| s.start_tag.clone());
+        let name = gen_full_name(&element.prefix, &element.local);
+
+        let attribute_span = |attr: &Attribute, value: bool| -> Option<Range<usize>> {
+            let attr_span = span?.attribute(&attr.prefix, &attr.local)?;
+            if value {
+                attr_span.value.clone().or(Some(attr_span.full.clone()))
+            } else {
+                Some(attr_span.full.clone())
+            }
+        };
+
+        for property in &schema.properties {
+            let present = element.attributes.iter().any(|a| {
+                a.local == property.local.as_str()
+                    && (a.prefix == property.prefix.as_str()
+                        || (property.prefix.is_empty() && is_evaluated(a)))
+            });
+
+            if property.required && !present {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::MissingProperty,
+                    start_tag.clone(),
+                    format!(
+                        "`{name}` requires the `{}` property",
+                        gen_full_name(&property.prefix, &property.local)
+                    ),
+                ));
+            }
+        }
+
+        for attr in &element.attributes {
+            let attr_name = gen_full_name(&attr.prefix, &attr.local);
+
+            if is_evaluated(attr) {
+                if schema.property("", &attr.local).is_none() {
+                    diagnostics.push(unknown_property(
+                        &attr_name,
+                        &name,
+                        attribute_span(attr, false),
+                    ));
+                }
+                continue;
+            }
+
+            match schema.property(&attr.prefix, &attr.local) {
+                Some(property) if !property.ty.accepts(attr.value.as_deref()) => {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticCode::TypeMismatch,
+                        attribute_span(attr, true).or(start_tag.clone()),
+                        match &attr.value {
+                            Some(value) => format!(
+                                "`{attr_name}` on `{name}` should be {}, found `{value}`",
+                                describe(property.ty)
+                            ),
+                            None => format!(
+                                "`{attr_name}` on `{name}` should be {}, found a modifier",
+                                describe(property.ty)
+                            ),
+                        },
+                    ));
+                }
+                Some(_) => (),
+                None if KNOWN_PREFIXES.contains(&attr.prefix.as_str()) => (),
+                None => diagnostics.push(unknown_property(
+                    &attr_name,
+                    &name,
+                    attribute_span(attr, false),
+                )),
+            }
+        }
+
+        for child in &element.children {
+            if !schema.allows_child(document, child) {
+                let child_name = match child {
+                    EntityRef::Element(i) => {
+                        let child = document.element_store[*i].as_ref().unwrap();
+                        format!("`{}`", gen_full_name(&child.prefix, &child.local))
+                    }
+                    _ => "text".to_string(),
+                };
+
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::InvalidChild,
+                    document.span_of(child),
+                    format!("{child_name} isn't allowed in `{name}`"),
+                ));
+            }
+        }
+    }
+}
+
+impl TryFrom<&Document<'_>> for Schema {
+    type Error = SchemaError;
+
+    fn try_from(document: &Document) -> Result<Self, Self::Error> {
+        Schema::from_document(document)
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Check the document against a [`Schema`]. See [`Schema::validate`].
+    pub fn validate_with(&self, schema: &Schema) -> Vec<Diagnostic> {
+        schema.validate(self)
+    }
+
+    fn child_elements(&self, id: NodeId) -> impl Iterator<Item = &Element<'a>> {
+        self.child_elements_of(self.element_store[id].as_ref().unwrap())
+    }
+
+    fn child_elements_of<'s>(
+        &'s self,
+        element: &'s Element<'a>,
+    ) -> impl Iterator<Item = &'s Element<'a>> {
+        element.children.iter().filter_map(|c| match c {
+            EntityRef::Element(i) => self.element_store[*i].as_ref(),
+            _ => None,
+        })
+    }
+
+    fn text_of(&self, element: &Element) -> String {
+        element
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                EntityRef::Text(i) => Some(self.text_store[*i].as_ref()?.content.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn value_of<'e>(element: &'e Element, local: &str) -> Option<&'e str> {
+    element
+        .attributes
+        .iter()
+        .find(|a| a.prefix.is_empty() && a.local == local)?
+        .value
+        .as_deref()
+}
+
+// `asRef`/`asEval` attributes set the property of the same name from a URL.
+fn is_evaluated(attr: &Attribute) -> bool {
+    attr.prefix == "asRef" || attr.prefix == "asEval"
+}
+
+fn describe(ty: PropertyType) -> &'static str {
+    match ty {
+        PropertyType::Any => "anything",
+        PropertyType::Bool => "a modifier",
+        PropertyType::Str => "a string",
+        PropertyType::Int => "an integer",
+        PropertyType::Float => "a number",
+        PropertyType::Range => "a range like `1..10`",
+        PropertyType::Url => "a URL",
+        PropertyType::Element => "an element name",
+    }
+}
+
+fn unknown_property(attr_name: &str, name: &str, span: Option<Range<usize>>) -> Diagnostic {
+    Diagnostic::new(
+        Severity::Warning,
+        DiagnosticCode::UnknownProperty,
+        span,
+        format!("`{name}` doesn't have a `{attr_name}` property"),
+    )
+}
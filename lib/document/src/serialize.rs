@@ -0,0 +1,61 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Document, EntityRef, Fragment, Name};
+
+impl Serialize for Name<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Name<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Name::from(String::deserialize(deserializer)?))
+    }
+}
+
+/// Serialized as a tree of nested [`Fragment`]s rooted at the `<document>` element.
+impl Serialize for Document<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fragment(&EntityRef::Element(0))
+            .ok_or_else(|| serde::ser::Error::custom("document has no root element"))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Document::from_fragment(Fragment::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("Document must have a single root <document> element"))
+    }
+}
+
+// `Fragment::Comment` as `{ "comment": "..." }`, so it isn't confused with text.
+pub(crate) mod comment {
+    use std::borrow::Cow;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Comment<'a> {
+        comment: Cow<'a, str>,
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        content: &str,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Comment {
+            comment: Cow::Borrowed(content),
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, 'a, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Cow<'a, str>, D::Error> {
+        Ok(Cow::Owned(
+            Comment::deserialize(deserializer)?.comment.into_owned(),
+        ))
+    }
+}
@@ -0,0 +1,249 @@
+use std::ops::Range;
+
+use trax_parser::StrSpan;
+
+use crate::{Document, EntityRef, Interner, Name, NodeId};
+
+/// Where an element was found in the source of a [`Document`], as byte ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElementSpan<'a> {
+    /// The start tag, from `<` to `>` or `/>`.
+    pub start_tag: Range<usize>,
+    /// The whole element, including its children and end tag.
+    pub full: Range<usize>,
+    /// The attributes and modifiers of the element, in source order.
+    pub attributes: Vec<AttributeSpan<'a>>,
+}
+
+impl<'a> ElementSpan<'a> {
+    /// Returns the span of an attribute or modifier by name.
+    pub fn attribute(&self, prefix: &str, local: &str) -> Option<&AttributeSpan<'a>> {
+        self.attributes
+            .iter()
+            .find(|a| a.prefix == prefix && a.local == local)
+    }
+
+    fn into_owned(self, interner: &mut Interner) -> ElementSpan<'static> {
+        ElementSpan {
+            start_tag: self.start_tag,
+            full: self.full,
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(|a| a.into_owned(interner))
+                .collect(),
+        }
+    }
+}
+
+/// Where an attribute or modifier was found in the source of a [`Document`], as byte ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttributeSpan<'a> {
+    /// The attribute's prefix.
+    pub prefix: Name<'a>,
+    /// The attribute's local name.
+    pub local: Name<'a>,
+    /// The whole attribute, e.g. `key="value"`.
+    pub full: Range<usize>,
+    /// The name of the attribute, e.g. `with:modifier`.
+    pub key: Range<usize>,
+    /// The value of the attribute without its quotes, or `None` for modifiers.
+    pub value: Option<Range<usize>>,
+}
+
+impl AttributeSpan<'_> {
+    fn into_owned(self, interner: &mut Interner) -> AttributeSpan<'static> {
+        AttributeSpan {
+            prefix: self.prefix.into_owned(interner),
+            local: self.local.into_owned(interner),
+            full: self.full,
+            key: self.key,
+            value: self.value,
+        }
+    }
+}
+
+/// The source spans of a document's entities, indexed like its stores. Entities inserted after
+/// parsing don't have a span.
+///
+/// Spans describe where entities came from rather than what they contain, so they're ignored when
+/// comparing documents.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Spans<'a> {
+    elements: Vec<Option<ElementSpan<'a>>>,
+    texts: Vec<Option<Range<usize>>>,
+    comments: Vec<Option<Range<usize>>>,
+}
+
+impl PartialEq for Spans<'_> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<'a> Spans<'a> {
+    pub(crate) fn start_element(&mut self, id: NodeId, span: StrSpan) {
+        set(
+            &mut self.elements,
+            id,
+            ElementSpan {
+                start_tag: span.range(),
+                full: span.range(),
+                attributes: Vec::new(),
+            },
+        );
+    }
+
+    pub(crate) fn start_tag(&self, id: NodeId) -> Option<Range<usize>> {
+        Some(self.elements.get(id)?.as_ref()?.start_tag.clone())
+    }
+
+    // Extends the start tag, and the element with it, up to the end of `span`.
+    pub(crate) fn end_start_tag(&mut self, id: NodeId, span: StrSpan) {
+        if let Some(Some(element)) = self.elements.get_mut(id) {
+            element.start_tag.end = span.end();
+            element.full.end = span.end();
+        }
+    }
+
+    pub(crate) fn end_element(&mut self, id: NodeId, span: StrSpan) {
+        if let Some(Some(element)) = self.elements.get_mut(id) {
+            element.full.end = span.end();
+        }
+    }
+
+    pub(crate) fn attribute(
+        &mut self,
+        id: NodeId,
+        prefix: StrSpan<'a>,
+        local: StrSpan<'a>,
+        value: Option<StrSpan>,
+        span: StrSpan,
+    ) {
+        if let Some(Some(element)) = self.elements.get_mut(id) {
+            let key_start = if prefix.is_empty() {
+                local.start()
+            } else {
+                prefix.start()
+            };
+            let key = key_start..local.end();
+
+            element.attributes.push(AttributeSpan {
+                prefix: Name::Borrowed(prefix.as_str()),
+                local: Name::Borrowed(local.as_str()),
+                full: match value {
+                    Some(_) => span.range(),
+                    None => key.clone(),
+                },
+                key,
+                value: value.map(|v| v.range()),
+            });
+        }
+    }
+
+    pub(crate) fn text(&mut self, id: usize, span: StrSpan) {
+        set(&mut self.texts, id, span.range());
+    }
+
+    pub(crate) fn comment(&mut self, id: usize, span: StrSpan) {
+        set(&mut self.comments, id, span.range());
+    }
+
+    // The attribute no longer holds what was parsed.
+    pub(crate) fn forget_attribute(&mut self, id: NodeId, prefix: &str, local: &str) {
+        if let Some(Some(element)) = self.elements.get_mut(id) {
+            element
+                .attributes
+                .retain(|a| a.prefix != prefix || a.local != local);
+        }
+    }
+
+    // The text no longer holds what was parsed.
+    pub(crate) fn forget_text(&mut self, id: usize) {
+        if let Some(span) = self.texts.get_mut(id) {
+            *span = None;
+        }
+    }
+
+    // Converts the spans along with their document, sharing names through `interner`.
+    pub(crate) fn into_owned(self, interner: &mut Interner) -> Spans<'static> {
+        Spans {
+            elements: self
+                .elements
+                .into_iter()
+                .map(|e| e.map(|e| e.into_owned(interner)))
+                .collect(),
+            texts: self.texts,
+            comments: self.comments,
+        }
+    }
+}
+
+fn set<T>(store: &mut Vec<Option<T>>, id: usize, value: T) {
+    if store.len() <= id {
+        store.resize_with(id + 1, || None);
+    }
+    store[id] = Some(value);
+}
+
+impl<'a> Document<'a> {
+    /// Returns the range of bytes the entity was parsed from, or `None` if it doesn't exist or
+    /// wasn't parsed from the source.
+    ///
+    /// Spans aren't updated by changes to the document: a moved entity keeps its original span,
+    /// while changed attributes and text lose theirs.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, EntityRef};
+    ///
+    /// let src = "<document><a key=\"value\">text</a></document>";
+    /// let doc = Document::new(src).unwrap();
+    ///
+    /// let span = doc.span_of(&EntityRef::Element(1)).unwrap();
+    /// assert_eq!(&src[span], "<a key=\"value\">text</a>");
+    /// ```
+    pub fn span_of(&self, entity_ref: &EntityRef) -> Option<Range<usize>> {
+        match entity_ref {
+            EntityRef::Element(i) => self.element_span(*i).map(|s| s.full.clone()),
+            EntityRef::Text(i) => {
+                self.text_store.get(*i)?.as_ref()?;
+                self.spans.texts.get(*i)?.clone()
+            }
+            EntityRef::Comment(i) => {
+                self.comment_store.get(*i)?.as_ref()?;
+                self.spans.comments.get(*i)?.clone()
+            }
+        }
+    }
+
+    /// Returns the spans of an element's start tag and attributes. See [`Document::span_of`].
+    pub fn element_span(&self, element_id: NodeId) -> Option<&ElementSpan<'a>> {
+        self.element_store.get(element_id)?.as_ref()?;
+        self.spans.elements.get(element_id)?.as_ref()
+    }
+
+    /// Returns the innermost entity whose source contains the byte at `offset`.
+    ///
+    /// Entities without a span, and their descendants, are never found.
+    pub fn node_at(&self, offset: usize) -> Option<EntityRef> {
+        let contains = |entity_ref: &EntityRef| {
+            self.span_of(entity_ref)
+                .is_some_and(|span| span.contains(&offset))
+        };
+
+        let mut found = EntityRef::Element(0);
+        if !contains(&found) {
+            return None;
+        }
+
+        while let EntityRef::Element(i) = found {
+            let element = self.element_store[i].as_ref()?;
+            match element.children.iter().find(|c| contains(c)) {
+                Some(child) => found = child.clone(),
+                None => break,
+            }
+        }
+
+        Some(found)
+    }
+}
@@ -0,0 +1,354 @@
+use std::borrow::Cow;
+
+use crate::{
+    gen_full_name, split_name, Attribute, ChangeEvent, Document, DropEntityError, EntityRef,
+    Fragment, InsertElementError, ModifyEntityError, NodeId, PlacePosition, Transaction,
+};
+
+/// The entities changed by [`Document::splice`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Splice {
+    /// The entities which were inserted, in order.
+    pub inserted: Vec<EntityRef>,
+    /// The entities which were replaced or deleted.
+    pub dropped: Vec<EntityRef>,
+}
+
+impl<'a> Document<'a> {
+    /// Insert fragments the way the spec's `<insert>` directive does.
+    ///
+    /// - With a position, the fragments are placed among the children of `target`. Without
+    ///   fragments, [`PlacePosition::Replace`] deletes the child instead.
+    /// - Without a position, the fragments replace `target`, or it's deleted if there aren't
+    ///   any. The root is replaced by a single `<document>` fragment, which loads a new document.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, EntityRef, Fragment, PlacePosition};
+    ///
+    /// let mut doc = Document::new("<document><a /><b /></document>").unwrap();
+    /// let root = |doc: &Document| doc.fragment(&EntityRef::Element(0)).unwrap().to_string();
+    ///
+    /// doc.splice(0, Some(PlacePosition::StartIndex(1)), Fragment::parse("<c /><d />").unwrap()).unwrap();
+    /// assert_eq!(root(&doc), "<document><a /><c /><d /><b /></document>");
+    ///
+    /// doc.splice(1, None, Vec::new()).unwrap();
+    /// assert_eq!(root(&doc), "<document><c /><d /><b /></document>");
+    /// ```
+    pub fn splice(
+        &mut self,
+        target: NodeId,
+        position: Option<PlacePosition>,
+        fragments: Vec<Fragment<'a>>,
+    ) -> Result<Splice, InsertElementError> {
+        let mut transaction = self.transaction();
+        let splice = transaction.splice(target, position, fragments)?;
+        transaction.commit();
+        Ok(splice)
+    }
+
+    /// Insert, replace or delete a property or modifier the way the spec's `<insertProp>`
+    /// directive does:
+    ///
+    /// - With a `key`, the property is set to `value`, or inserted at the position if it's
+    ///   [`PlacePosition::Start`], [`PlacePosition::End`] or one of their indices. A modifier is
+    ///   inserted without a `value`.
+    /// - With a `key` and [`PlacePosition::Replace`], the property at that index is replaced, or
+    ///   only renamed without a `value`.
+    /// - Without a `key`, the value of the property at the position is replaced, or the
+    ///   property is deleted without a `value`. Without a position either, every property is
+    ///   deleted.
+    ///
+    /// Positions locating an existing property count from the start, or from the end for
+    /// [`PlacePosition::End`] and [`PlacePosition::EndIndex`].
+    pub fn splice_property(
+        &mut self,
+        target: NodeId,
+        key: Option<&str>,
+        value: Option<Cow<'a, str>>,
+        position: Option<PlacePosition>,
+    ) -> Result<(), ModifyEntityError> {
+        let mut transaction = self.transaction();
+        transaction.splice_property(target, key, value, position)?;
+        transaction.commit();
+        Ok(())
+    }
+
+    /// Insert an attribute among the attributes of an element, removing the attribute with the
+    /// same name if there is one. [`PlacePosition::Replace`] replaces the attribute at an index.
+    /// Returns the index of the attribute.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn insert_attribute(
+        &mut self,
+        element_id: NodeId,
+        place_position: PlacePosition,
+        attribute: Attribute<'a>,
+    ) -> Result<usize, ModifyEntityError> {
+        let attributes = self.attributes_mut(element_id)?;
+        let same_name = |a: &Attribute| a.prefix == attribute.prefix && a.local == attribute.local;
+        let existing = attributes.iter().position(same_name);
+
+        let len = attributes.len();
+        let remaining = len - usize::from(existing.is_some());
+        let out_of_range =
+            |n| ModifyEntityError::IndexOutOfRange(EntityRef::Element(element_id), n, len);
+
+        let mut removed = Vec::new();
+        let index = match place_position {
+            PlacePosition::Replace(n) => {
+                if n >= len {
+                    return Err(out_of_range(n));
+                }
+                removed.push(std::mem::replace(&mut attributes[n], attribute.clone()));
+                match existing.filter(|i| *i != n) {
+                    Some(i) => {
+                        removed.extend(attributes.remove(i));
+                        if i < n {
+                            n - 1
+                        } else {
+                            n
+                        }
+                    }
+                    None => n,
+                }
+            }
+            position => {
+                let index = position.resolve(remaining).ok_or(match position {
+                    PlacePosition::StartIndex(n) | PlacePosition::EndIndex(n) => out_of_range(n),
+                    _ => out_of_range(0),
+                })?;
+                removed.extend(existing.and_then(|i| attributes.remove(i)));
+                attributes.insert(index, attribute.clone());
+                index
+            }
+        };
+
+        let mut names = vec![gen_full_name(&attribute.prefix, &attribute.local)];
+        for old in removed {
+            self.spans
+                .forget_attribute(element_id, &old.prefix, &old.local);
+            names.push(gen_full_name(&old.prefix, &old.local));
+        }
+        names.dedup();
+
+        for name in names {
+            self.notify(|| ChangeEvent::AttributeChanged {
+                element: element_id,
+                name: name.clone(),
+            });
+        }
+
+        Ok(index)
+    }
+}
+
+impl<'a> Transaction<'_, 'a> {
+    /// Insert fragments the way the spec's `<insert>` directive does. See [`Document::splice`].
+    pub fn splice(
+        &mut self,
+        target: NodeId,
+        position: Option<PlacePosition>,
+        fragments: Vec<Fragment<'a>>,
+    ) -> Result<Splice, InsertElementError> {
+        match position {
+            Some(position) => self.splice_children(target, position, fragments),
+            None if target == 0 => self.replace_root(fragments),
+            None => {
+                let (parent, index) = self
+                    .locate(&EntityRef::Element(target))
+                    .ok_or(InsertElementError::NotFound(EntityRef::Element(target)))?;
+                self.splice_children(parent, PlacePosition::Replace(index), fragments)
+            }
+        }
+    }
+
+    /// Insert, replace or delete a property the way the spec's `<insertProp>` directive does. See
+    /// [`Document::splice_property`].
+    pub fn splice_property(
+        &mut self,
+        target: NodeId,
+        key: Option<&str>,
+        value: Option<Cow<'a, str>>,
+        position: Option<PlacePosition>,
+    ) -> Result<(), ModifyEntityError> {
+        let attributes: Vec<Attribute<'a>> = self
+            .element(target)
+            .ok_or(ModifyEntityError::NotFound(EntityRef::Element(target)))?
+            .attributes()
+            .cloned()
+            .collect();
+        let len = attributes.len();
+
+        // the existing property a position locates
+        let existing = |position: PlacePosition| {
+            let index = match position {
+                PlacePosition::Start => Some(0),
+                PlacePosition::End => len.checked_sub(1),
+                PlacePosition::StartIndex(n) | PlacePosition::Replace(n) => Some(n),
+                PlacePosition::EndIndex(n) => len.checked_sub(n + 1),
+            };
+            let n = match position {
+                PlacePosition::StartIndex(n)
+                | PlacePosition::EndIndex(n)
+                | PlacePosition::Replace(n) => n,
+                _ => 0,
+            };
+
+            index
+                .filter(|i| *i < len)
+                .ok_or(ModifyEntityError::IndexOutOfRange(
+                    EntityRef::Element(target),
+                    n,
+                    len,
+                ))
+        };
+
+        match (key, value, position) {
+            (Some(key), value, None) => {
+                let (prefix, local) = split_name(key);
+                self.replace_attribute(
+                    target,
+                    Attribute::new(prefix.to_string(), local.to_string(), value),
+                )?;
+            }
+            (Some(key), None, Some(PlacePosition::Replace(n))) => {
+                let i = existing(PlacePosition::Replace(n))?;
+                let (prefix, local) = split_name(key);
+                let value = attributes[i].clone().into_value();
+                self.insert_attribute(
+                    target,
+                    PlacePosition::Replace(i),
+                    Attribute::new(prefix.to_string(), local.to_string(), value),
+                )?;
+            }
+            (Some(key), value, Some(position)) => {
+                let (prefix, local) = split_name(key);
+                self.insert_attribute(
+                    target,
+                    position,
+                    Attribute::new(prefix.to_string(), local.to_string(), value),
+                )?;
+            }
+            (None, Some(value), Some(position)) => {
+                let i = existing(position)?;
+                let attribute = Attribute::new(
+                    attributes[i].prefix.clone(),
+                    attributes[i].local.clone(),
+                    Some(value),
+                );
+                self.insert_attribute(target, PlacePosition::Replace(i), attribute)?;
+            }
+            (None, None, Some(position)) => {
+                let i = existing(position)?;
+                self.take_attribute(target, attributes[i].prefix(), attributes[i].local())?;
+            }
+            (None, Some(_), None) => {
+                return Err(ModifyEntityError::MissingPosition(EntityRef::Element(
+                    target,
+                )))
+            }
+            (None, None, None) => {
+                for attribute in &attributes {
+                    self.take_attribute(target, attribute.prefix(), attribute.local())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn splice_children(
+        &mut self,
+        parent: NodeId,
+        position: PlacePosition,
+        fragments: Vec<Fragment<'a>>,
+    ) -> Result<Splice, InsertElementError> {
+        let children: Vec<EntityRef> = self
+            .element(parent)
+            .ok_or(InsertElementError::NotFound(EntityRef::Element(parent)))?
+            .children()
+            .cloned()
+            .collect();
+        let len = children.len();
+        let out_of_range =
+            || InsertElementError::PositionOutOfRange(position, EntityRef::Element(parent), len);
+
+        let first = position.resolve(len).ok_or_else(out_of_range)?;
+        let dropped: Vec<EntityRef> = match position {
+            PlacePosition::Replace(n) => children.get(n).cloned().into_iter().collect(),
+            _ => Vec::new(),
+        };
+
+        for child in &dropped {
+            self.drop_entity(child.clone()).map_err(|e| {
+                InsertElementError::DropEntityError(first, EntityRef::Element(parent), e)
+            })?;
+        }
+
+        // the fragments take the place of the replaced child, one after another
+        let mut inserted = Vec::new();
+        for (i, fragment) in fragments.into_iter().enumerate() {
+            inserted.push(self.attach_fragment(
+                parent,
+                PlacePosition::StartIndex(first + i),
+                fragment,
+            )?);
+        }
+
+        Ok(Splice { inserted, dropped })
+    }
+
+    fn replace_root(&mut self, fragments: Vec<Fragment<'a>>) -> Result<Splice, InsertElementError> {
+        let refused = || {
+            InsertElementError::DropEntityError(
+                0,
+                EntityRef::Element(0),
+                DropEntityError::RefuseDropRoot,
+            )
+        };
+
+        let mut fragments = fragments.into_iter();
+        let (
+            Some(Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            }),
+            None,
+        ) = (fragments.next(), fragments.next())
+        else {
+            return Err(refused());
+        };
+        if !prefix.is_empty() || &*local != "document" {
+            return Err(refused());
+        }
+
+        let dropped: Vec<EntityRef> = self
+            .element(0)
+            .map(|e| e.children().cloned().collect())
+            .unwrap_or_default();
+        let old: Vec<Attribute<'a>> = self
+            .element(0)
+            .map(|e| e.attributes().cloned().collect())
+            .unwrap_or_default();
+
+        for child in &dropped {
+            self.drop_entity(child.clone())
+                .map_err(|e| InsertElementError::DropEntityError(0, EntityRef::Element(0), e))?;
+        }
+        for attribute in old {
+            let _ = self.take_attribute(0, attribute.prefix(), attribute.local());
+        }
+        for attribute in attributes {
+            let _ = self.replace_attribute(0, attribute);
+        }
+
+        let mut inserted = Vec::new();
+        for child in children {
+            inserted.push(self.attach_fragment(0, PlacePosition::End, child)?);
+        }
+
+        Ok(Splice { inserted, dropped })
+    }
+}
@@ -0,0 +1,699 @@
+use std::{borrow::Cow, ops::Deref};
+
+use thiserror::Error;
+
+use crate::{
+    gen_full_name, Attribute, ChangeEvent, Comment, Document, DropEntityError, Element, EntityRef,
+    Fragment, InsertElementError, ModifyEntityError, NodeId, Patch, PlacePosition, Text,
+};
+
+/// An error encountered when applying a change to a [`Document`].
+#[derive(Debug, Error, PartialEq)]
+pub enum PatchError {
+    /// See [`InsertElementError`].
+    #[error(transparent)]
+    Insert(#[from] InsertElementError),
+
+    /// See [`DropEntityError`].
+    #[error(transparent)]
+    Drop(#[from] DropEntityError),
+
+    /// See [`ModifyEntityError`].
+    #[error(transparent)]
+    Modify(#[from] ModifyEntityError),
+
+    /// A patch moved an entity into a parent other than its own.
+    #[error("couldn't move {0} into Element at {1} because it isn't its parent")]
+    NotAChild(EntityRef, NodeId),
+}
+
+/// An entity taken out of a document along with its descendants, keeping their ids so that it
+/// can be put back exactly where it was.
+#[derive(Clone, Debug, PartialEq)]
+struct Detached<'a> {
+    root: EntityRef,
+    parent: NodeId,
+    index: usize,
+    elements: Vec<(NodeId, Element<'a>)>,
+    texts: Vec<(usize, Text<'a>)>,
+    comments: Vec<(usize, Comment<'a>)>,
+}
+
+/// A primitive, invertible change to a document.
+#[derive(Clone, Debug, PartialEq)]
+enum Operation<'a> {
+    Attached(EntityRef),
+    Detached(Detached<'a>),
+    Moved {
+        node: EntityRef,
+        from: usize,
+        to: usize,
+    },
+    Attribute {
+        element: NodeId,
+        index: usize,
+        old: Option<Attribute<'a>>,
+        new: Option<Attribute<'a>>,
+    },
+    Text {
+        text: usize,
+        old: Cow<'a, str>,
+        new: Cow<'a, str>,
+    },
+}
+
+impl<'a> Operation<'a> {
+    // Undoes the operation, returning the operation which redoes it.
+    fn revert(self, document: &mut Document<'a>) -> Result<Operation<'a>, PatchError> {
+        Ok(match self {
+            Operation::Attached(root) => Operation::Detached(document.detach(root)?),
+            Operation::Detached(detached) => Operation::Attached(document.attach(detached)?),
+            Operation::Moved { node, from, to } => {
+                document.move_to(node.clone(), from)?;
+                Operation::Moved {
+                    node,
+                    from: to,
+                    to: from,
+                }
+            }
+            Operation::Attribute {
+                element,
+                index,
+                old,
+                new,
+            } => {
+                let attributes = document.attributes_mut(element)?;
+                let len = attributes.len();
+                if index > len || (index == len && new.is_some()) {
+                    return Err(ModifyEntityError::IndexOutOfRange(
+                        EntityRef::Element(element),
+                        index,
+                        len,
+                    )
+                    .into());
+                }
+
+                match (&old, new.is_some()) {
+                    (Some(old), true) => attributes[index] = old.clone(),
+                    (Some(old), false) => attributes.insert(index, old.clone()),
+                    (None, true) => {
+                        attributes.remove(index);
+                    }
+                    (None, false) => (),
+                }
+
+                let name = old
+                    .as_ref()
+                    .or(new.as_ref())
+                    .map(|a| gen_full_name(&a.prefix, &a.local));
+                if let Some(name) = name {
+                    document.notify(|| ChangeEvent::AttributeChanged { element, name });
+                }
+
+                Operation::Attribute {
+                    element,
+                    index,
+                    old: new,
+                    new: old,
+                }
+            }
+            Operation::Text { text, old, new } => {
+                document.set_text(text, old.clone())?;
+                Operation::Text {
+                    text,
+                    old: new,
+                    new: old,
+                }
+            }
+        })
+    }
+}
+
+/// A committed group of changes which can be reverted as a whole.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Changeset<'a> {
+    operations: Vec<Operation<'a>>,
+}
+
+impl<'a> Changeset<'a> {
+    /// Undo the changes, returning a changeset which redoes them.
+    ///
+    /// Fails if the document was changed in a way that conflicts with the changeset, in which
+    /// case nothing is changed.
+    pub fn revert(self, document: &mut Document<'a>) -> Result<Changeset<'a>, PatchError> {
+        let mut transaction = document.transaction();
+        for operation in self.operations.into_iter().rev() {
+            let inverse = operation.revert(transaction.document)?;
+            transaction.operations.push(inverse);
+        }
+        Ok(transaction.commit())
+    }
+
+    /// The number of primitive operations in the changeset.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns `true` if the changeset doesn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// A group of changes to a [`Document`] which is rolled back unless committed.
+///
+/// See [`Document::transaction`].
+#[derive(Debug)]
+pub struct Transaction<'d, 'a> {
+    document: &'d mut Document<'a>,
+    operations: Vec<Operation<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Start a transaction. Changes made through it are rolled back when it's dropped without
+    /// being committed.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, PlacePosition};
+    ///
+    /// let mut doc = Document::new("<document><a /></document>").unwrap();
+    /// let before = doc.into_string();
+    ///
+    /// let mut tx = doc.transaction();
+    /// tx.insert(0, PlacePosition::End, "", "b", []).unwrap();
+    /// let changes = tx.commit();
+    ///
+    /// changes.revert(&mut doc).unwrap();
+    /// assert_eq!(doc.into_string(), before);
+    /// ```
+    pub fn transaction(&mut self) -> Transaction<'_, 'a> {
+        Transaction {
+            document: self,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Apply a single patch, such as one produced by [`diff`](crate::diff).
+    pub fn apply(&mut self, patch: Patch<'a>) -> Result<(), PatchError> {
+        self.apply_all([patch])
+    }
+
+    /// Apply patches in order. Nothing is changed if any of them fails.
+    pub fn apply_all<I: IntoIterator<Item = Patch<'a>>>(
+        &mut self,
+        patches: I,
+    ) -> Result<(), PatchError> {
+        let mut transaction = self.transaction();
+        for patch in patches {
+            transaction.apply(patch)?;
+        }
+        transaction.commit();
+        Ok(())
+    }
+
+    fn detach(&mut self, root: EntityRef) -> Result<Detached<'a>, DropEntityError> {
+        if root == EntityRef::Element(0) {
+            return Err(DropEntityError::RefuseDropRoot);
+        }
+
+        let Some((parent, index)) = self.locate(&root) else {
+            return Err(DropEntityError::NotFound(root));
+        };
+
+        self.element_store[parent]
+            .as_mut()
+            .unwrap()
+            .children
+            .remove(index);
+
+        let mut detached = Detached {
+            root: root.clone(),
+            parent,
+            index,
+            elements: Vec::new(),
+            texts: Vec::new(),
+            comments: Vec::new(),
+        };
+
+        let mut stack = vec![root.clone()];
+        while let Some(entity_ref) = stack.pop() {
+            match entity_ref {
+                EntityRef::Element(i) => {
+                    let element = self.element_store[i].take().unwrap();
+                    stack.extend(element.children.iter().cloned());
+                    detached.elements.push((i, element));
+                }
+                EntityRef::Text(i) => {
+                    detached.texts.push((i, self.text_store[i].take().unwrap()));
+                }
+                EntityRef::Comment(i) => {
+                    detached
+                        .comments
+                        .push((i, self.comment_store[i].take().unwrap()));
+                }
+            }
+        }
+
+        self.notify(|| ChangeEvent::Dropped {
+            node: root,
+            parent,
+            index,
+        });
+
+        Ok(detached)
+    }
+
+    fn attach(&mut self, detached: Detached<'a>) -> Result<EntityRef, InsertElementError> {
+        let len = match self.element_store.get(detached.parent) {
+            Some(Some(parent)) => parent.children.len(),
+            _ => {
+                return Err(InsertElementError::NotFound(EntityRef::Element(
+                    detached.parent,
+                )))
+            }
+        };
+
+        if detached.index > len {
+            return Err(InsertElementError::PositionOutOfRange(
+                PlacePosition::StartIndex(detached.index),
+                EntityRef::Element(detached.parent),
+                len,
+            ));
+        }
+
+        for (i, element) in detached.elements {
+            self.element_store[i] = Some(element);
+        }
+
+        for (i, text) in detached.texts {
+            self.text_store[i] = Some(text);
+        }
+
+        for (i, comment) in detached.comments {
+            self.comment_store[i] = Some(comment);
+        }
+
+        self.element_store[detached.parent]
+            .as_mut()
+            .unwrap()
+            .children
+            .insert(detached.index, detached.root.clone());
+
+        self.notify(|| ChangeEvent::Inserted {
+            node: detached.root.clone(),
+            parent: detached.parent,
+            index: detached.index,
+        });
+
+        Ok(detached.root)
+    }
+}
+
+impl<'a> Transaction<'_, 'a> {
+    /// Insert a new element. See [`Document::insert`].
+    pub fn insert<
+        C: Into<crate::Name<'a>>,
+        C2: Into<crate::Name<'a>>,
+        VD: Into<std::collections::VecDeque<Attribute<'a>>>,
+    >(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        prefix: C,
+        local: C2,
+        attributes: VD,
+    ) -> Result<NodeId, PatchError> {
+        let place_position = self.make_room(parent_id, place_position)?;
+        let id = self
+            .document
+            .insert(parent_id, place_position, prefix, local, attributes)?;
+        self.operations
+            .push(Operation::Attached(EntityRef::Element(id)));
+        Ok(id)
+    }
+
+    /// Insert a new segment of text. See [`Document::insert_text`].
+    pub fn insert_text<C: Into<Cow<'a, str>>>(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        content: C,
+    ) -> Result<usize, PatchError> {
+        let place_position = self.make_room(parent_id, place_position)?;
+        let id = self
+            .document
+            .insert_text(parent_id, place_position, content)?;
+        self.operations
+            .push(Operation::Attached(EntityRef::Text(id)));
+        Ok(id)
+    }
+
+    /// Insert a new comment. See [`Document::insert_comment`].
+    pub fn insert_comment<C: Into<Cow<'a, str>>>(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        content: C,
+    ) -> Result<usize, PatchError> {
+        let place_position = self.make_room(parent_id, place_position)?;
+        let id = self
+            .document
+            .insert_comment(parent_id, place_position, content)?;
+        self.operations
+            .push(Operation::Attached(EntityRef::Comment(id)));
+        Ok(id)
+    }
+
+    /// Insert a detached subtree. See [`Document::insert_fragment`].
+    pub fn insert_fragment(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        fragment: Fragment<'a>,
+    ) -> Result<EntityRef, PatchError> {
+        let place_position = self.make_room(parent_id, place_position)?;
+        Ok(self.attach_fragment(parent_id, place_position, fragment)?)
+    }
+
+    /// Drop an entity and its children. See [`Document::drop`].
+    pub fn drop(&mut self, entity_ref: EntityRef) -> Result<(), PatchError> {
+        Ok(self.drop_entity(entity_ref)?)
+    }
+
+    /// Set an attribute. See [`Document::set_attribute`].
+    pub fn set_attribute(
+        &mut self,
+        element_id: NodeId,
+        attribute: Attribute<'a>,
+    ) -> Result<Option<Attribute<'a>>, PatchError> {
+        Ok(self.replace_attribute(element_id, attribute)?)
+    }
+
+    /// Remove an attribute. See [`Document::remove_attribute`].
+    pub fn remove_attribute(
+        &mut self,
+        element_id: NodeId,
+        prefix: &str,
+        local: &str,
+    ) -> Result<Attribute<'a>, PatchError> {
+        Ok(self.take_attribute(element_id, prefix, local)?)
+    }
+
+    /// Replace the content of a segment of text. See [`Document::set_text`].
+    pub fn set_text<C: Into<Cow<'a, str>>>(
+        &mut self,
+        text_id: usize,
+        content: C,
+    ) -> Result<Cow<'a, str>, PatchError> {
+        let new = content.into();
+        let old = self.document.set_text(text_id, new.clone())?;
+        self.operations.push(Operation::Text {
+            text: text_id,
+            old: old.clone(),
+            new,
+        });
+        Ok(old)
+    }
+
+    /// Move an entity within its parent. See [`Document::move_to`].
+    pub fn move_to(&mut self, entity_ref: EntityRef, index: usize) -> Result<usize, PatchError> {
+        let from = self.document.move_to(entity_ref.clone(), index)?;
+        self.operations.push(Operation::Moved {
+            node: entity_ref,
+            from,
+            to: index,
+        });
+        Ok(from)
+    }
+
+    /// Apply a patch. See [`Document::apply`].
+    pub fn apply(&mut self, patch: Patch<'a>) -> Result<(), PatchError> {
+        match patch {
+            Patch::Insert {
+                parent,
+                index,
+                fragment,
+            } => self
+                .insert_fragment(parent, PlacePosition::StartIndex(index), fragment)
+                .map(|_| ()),
+            Patch::Drop { node } => self.drop(node),
+            Patch::Move {
+                node,
+                parent,
+                index,
+            } => match self.document.locate(&node) {
+                Some((p, _)) if p == parent => self.move_to(node, index).map(|_| ()),
+                Some(_) => Err(PatchError::NotAChild(node, parent)),
+                None => Err(ModifyEntityError::NotFound(node).into()),
+            },
+            Patch::SetAttribute { element, attribute } => {
+                self.set_attribute(element, attribute).map(|_| ())
+            }
+            Patch::RemoveAttribute {
+                element,
+                prefix,
+                local,
+            } => self.remove_attribute(element, &prefix, &local).map(|_| ()),
+            Patch::SetText { text, content } => self.set_text(text, content).map(|_| ()),
+        }
+    }
+
+    /// Keep the changes, returning them so they can be reverted later.
+    pub fn commit(mut self) -> Changeset<'a> {
+        Changeset {
+            operations: std::mem::take(&mut self.operations),
+        }
+    }
+
+    /// Discard the changes. Equivalent to dropping the transaction.
+    pub fn rollback(self) {}
+
+    // The changes below keep the errors of the document's own changes, so that changes made of
+    // them, like splices, can return the same errors either way.
+
+    // Inserts a subtree, which mustn't replace a child. See `make_room`.
+    pub(crate) fn attach_fragment(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+        fragment: Fragment<'a>,
+    ) -> Result<EntityRef, InsertElementError> {
+        let root = self
+            .document
+            .insert_fragment(parent_id, place_position, fragment)?;
+        self.operations.push(Operation::Attached(root.clone()));
+        Ok(root)
+    }
+
+    pub(crate) fn drop_entity(&mut self, entity_ref: EntityRef) -> Result<(), DropEntityError> {
+        let detached = self.document.detach(entity_ref)?;
+        self.operations.push(Operation::Detached(detached));
+        Ok(())
+    }
+
+    pub(crate) fn replace_attribute(
+        &mut self,
+        element_id: NodeId,
+        attribute: Attribute<'a>,
+    ) -> Result<Option<Attribute<'a>>, ModifyEntityError> {
+        let old = self.document.set_attribute(element_id, attribute.clone())?;
+        let attributes = self.document.attributes_mut(element_id)?;
+        let index = attributes
+            .iter()
+            .position(|a| a.prefix == attribute.prefix && a.local == attribute.local)
+            .unwrap();
+
+        self.operations.push(Operation::Attribute {
+            element: element_id,
+            index,
+            old: old.clone(),
+            new: Some(attribute),
+        });
+        Ok(old)
+    }
+
+    pub(crate) fn take_attribute(
+        &mut self,
+        element_id: NodeId,
+        prefix: &str,
+        local: &str,
+    ) -> Result<Attribute<'a>, ModifyEntityError> {
+        let index = self
+            .document
+            .attributes_mut(element_id)?
+            .iter()
+            .position(|a| a.prefix == prefix && a.local == local);
+        let old = self.document.remove_attribute(element_id, prefix, local)?;
+
+        self.operations.push(Operation::Attribute {
+            element: element_id,
+            index: index.unwrap(),
+            old: Some(old.clone()),
+            new: None,
+        });
+        Ok(old)
+    }
+
+    // Replacing an attribute at an index is recorded before removing the attribute with the
+    // same name, and removing it before inserting, so that they're reverted in reverse.
+    pub(crate) fn insert_attribute(
+        &mut self,
+        element_id: NodeId,
+        place_position: PlacePosition,
+        attribute: Attribute<'a>,
+    ) -> Result<usize, ModifyEntityError> {
+        let before = self.document.attributes_mut(element_id)?.clone();
+        let existing = before
+            .iter()
+            .position(|a| a.prefix == attribute.prefix && a.local == attribute.local);
+        let index =
+            self.document
+                .insert_attribute(element_id, place_position, attribute.clone())?;
+
+        let removed = |i: usize| Operation::Attribute {
+            element: element_id,
+            index: i,
+            old: Some(before[i].clone()),
+            new: None,
+        };
+        match place_position {
+            PlacePosition::Replace(n) => {
+                self.operations.push(Operation::Attribute {
+                    element: element_id,
+                    index: n,
+                    old: Some(before[n].clone()),
+                    new: Some(attribute),
+                });
+                self.operations
+                    .extend(existing.filter(|i| *i != n).map(removed));
+            }
+            _ => {
+                self.operations.extend(existing.map(removed));
+                self.operations.push(Operation::Attribute {
+                    element: element_id,
+                    index,
+                    old: None,
+                    new: Some(attribute),
+                });
+            }
+        }
+        Ok(index)
+    }
+
+    // Replacing is recorded as dropping the old child and inserting in its place, so that the
+    // old child is restored when the transaction is reverted.
+    fn make_room(
+        &mut self,
+        parent_id: NodeId,
+        place_position: PlacePosition,
+    ) -> Result<PlacePosition, PatchError> {
+        let PlacePosition::Replace(n) = place_position else {
+            return Ok(place_position);
+        };
+
+        let child = match self.document.element_store.get(parent_id) {
+            Some(Some(parent)) => parent.children.get(n).cloned(),
+            _ => return Err(InsertElementError::NotFound(EntityRef::Element(parent_id)).into()),
+        };
+
+        match child {
+            Some(child) => {
+                self.drop(child)?;
+                Ok(PlacePosition::StartIndex(n))
+            }
+            None => Err(InsertElementError::PositionOutOfRange(
+                place_position,
+                EntityRef::Element(parent_id),
+                self.document.element_store[parent_id]
+                    .as_ref()
+                    .unwrap()
+                    .children
+                    .len(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl<'a> Deref for Transaction<'_, 'a> {
+    type Target = Document<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.document
+    }
+}
+
+impl Drop for Transaction<'_, '_> {
+    fn drop(&mut self) {
+        while let Some(operation) = self.operations.pop() {
+            // reverting operations which were just applied can't fail
+            let _ = operation.revert(self.document);
+        }
+    }
+}
+
+/// An undo/redo log of [`Changeset`]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History<'a> {
+    undo: Vec<Changeset<'a>>,
+    redo: Vec<Changeset<'a>>,
+}
+
+impl<'a> History<'a> {
+    /// Create a new, empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a committed changeset, discarding anything that could be redone.
+    pub fn push(&mut self, changeset: Changeset<'a>) {
+        if !changeset.is_empty() {
+            self.undo.push(changeset);
+            self.redo.clear();
+        }
+    }
+
+    /// Undo the last changeset. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, document: &mut Document<'a>) -> Result<bool, PatchError> {
+        match self.undo.pop() {
+            Some(changeset) => match changeset.clone().revert(document) {
+                Ok(redo) => {
+                    self.redo.push(redo);
+                    Ok(true)
+                }
+                Err(e) => {
+                    self.undo.push(changeset);
+                    Err(e)
+                }
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Redo the last undone changeset. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self, document: &mut Document<'a>) -> Result<bool, PatchError> {
+        match self.redo.pop() {
+            Some(changeset) => match changeset.clone().revert(document) {
+                Ok(undo) => {
+                    self.undo.push(undo);
+                    Ok(true)
+                }
+                Err(e) => {
+                    self.redo.push(changeset);
+                    Err(e)
+                }
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Returns `true` if there's a changeset to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there's a changeset to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
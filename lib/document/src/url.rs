@@ -0,0 +1,363 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{split_name, Document, EntityRef, NodeId};
+
+/// An error encountered when parsing a [`Url`].
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum UrlError {
+    /// The URL is empty.
+    #[error("empty URL")]
+    Empty,
+
+    /// A path segment is empty or malformed.
+    #[error("invalid path segment `{0}`")]
+    InvalidSegment(String),
+
+    /// A `#` isn't followed by an index.
+    #[error("invalid index in `{0}`")]
+    InvalidIndex(String),
+
+    /// A property is accessed before the last path segment.
+    #[error("`{0}` accesses a property, so it must be the last segment")]
+    MisplacedProperty(String),
+}
+
+/// A TRAX URL, which locates a document and optionally an element or property in it.
+///
+/// ```rust
+/// use trax_document::Url;
+///
+/// let url = Url::parse("todo.trax#0/Frame/Body/Todo#1.title").unwrap();
+///
+/// assert_eq!(url.document, Some("todo.trax"));
+/// assert_eq!(url.connection, Some(0));
+/// assert_eq!(url.segments.len(), 3);
+/// assert_eq!(url.property, Some("title"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Url<'u> {
+    /// The document, e.g. `todo.trax` or `trax.tcp:example.com/todo.trax`. Paths without one are
+    /// in the current document.
+    pub document: Option<&'u str>,
+    /// The connection to the document, from the `#n` following it.
+    pub connection: Option<usize>,
+    /// The path to an element. When there's a document, the path starts at its root
+    /// `<document>` element.
+    pub segments: Vec<Segment<'u>>,
+    /// The property of the element, from a trailing `.name`.
+    pub property: Option<&'u str>,
+}
+
+/// A segment of a [`Url`]'s path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment<'u> {
+    /// `.`, the current element.
+    Current,
+    /// `..`, the parent of the current element.
+    Parent,
+    /// A child of the current element, such as `Todo#1` or `Section?title="Giant Rat"`.
+    Element {
+        /// The child's prefix.
+        prefix: &'u str,
+        /// The child's local name.
+        local: &'u str,
+        /// Which of the matching children to use, from `#n`. Defaults to the first one.
+        index: Option<usize>,
+        /// Conditions a child must meet to match, from `?a=b&c`.
+        queries: Vec<Query<'u>>,
+    },
+}
+
+/// A condition in a [`Segment`], such as `title="Giant Rat"` or `Obstruction.obstacle=road`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query<'u> {
+    /// A child element which must have the property, rather than the element itself.
+    pub child: Option<&'u str>,
+    /// The name of the property.
+    pub key: &'u str,
+    /// The value the property must have, or `None` if it only has to be present.
+    pub value: Option<&'u str>,
+}
+
+impl<'u> Url<'u> {
+    /// Parse a TRAX URL.
+    ///
+    /// Paths without a document start at the current element, unless they start with
+    /// `document`, the root element.
+    pub fn parse(url: &'u str) -> Result<Self, UrlError> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Err(UrlError::Empty);
+        }
+
+        let mut parsed = Url::default();
+        let mut path = url;
+
+        if let Some(end) = document_end(url) {
+            let (document, connection) = split_index(&url[..end])?;
+            parsed.document = Some(document);
+            parsed.connection = connection;
+            path = url[end..].trim_start_matches('/');
+        }
+
+        if path.is_empty() {
+            return Ok(parsed);
+        }
+
+        let mut segments = path.split('/').peekable();
+        while let Some(segment) = segments.next() {
+            let (segment, property) = parse_segment(segment)?;
+            parsed.segments.push(segment);
+
+            if let Some(property) = property {
+                if let Some(next) = segments.peek() {
+                    return Err(UrlError::MisplacedProperty(next.to_string()));
+                }
+                parsed.property = Some(property);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Returns `true` if the URL doesn't name a document, so it's in the current one.
+    pub fn is_local(&self) -> bool {
+        self.document.is_none()
+    }
+}
+
+impl Document<'_> {
+    /// The URL of an element, as a path from the root `document`. Indexes are only written for
+    /// elements which aren't the first of their name.
+    ///
+    /// ```rust
+    /// use trax_document::Document;
+    ///
+    /// let doc = Document::new("<document><Body><Todo /><Todo /></Body></document>").unwrap();
+    ///
+    /// assert_eq!(doc.url_of(3).unwrap().to_string(), "document/Body/Todo#1");
+    /// ```
+    pub fn url_of(&self, element_id: NodeId) -> Option<Url<'_>> {
+        let mut segments = Vec::new();
+        let mut id = element_id;
+
+        while id != 0 {
+            let element = self.element_store.get(id)?.as_ref()?;
+            let parent = self.element_store.get(element.parent)?.as_ref()?;
+            let index = parent
+                .children
+                .iter()
+                .take_while(|c| **c != EntityRef::Element(id))
+                .filter(|c| match c {
+                    EntityRef::Element(i) => self.element_store[*i]
+                        .as_ref()
+                        .is_some_and(|e| e.prefix == element.prefix && e.local == element.local),
+                    _ => false,
+                })
+                .count();
+
+            segments.push(Segment::Element {
+                prefix: element.prefix.as_str(),
+                local: element.local.as_str(),
+                index: (index > 0).then_some(index),
+                queries: Vec::new(),
+            });
+            id = element.parent;
+        }
+
+        self.element(0)?;
+        segments.push(Segment::Element {
+            prefix: "",
+            local: "document",
+            index: None,
+            queries: Vec::new(),
+        });
+        segments.reverse();
+
+        Some(Url {
+            segments,
+            ..Default::default()
+        })
+    }
+}
+
+impl Display for Url<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(document) = self.document {
+            f.write_str(document)?;
+            if let Some(connection) = self.connection {
+                write!(f, "#{connection}")?;
+            }
+        }
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 || self.document.is_some() {
+                f.write_str("/")?;
+            }
+            // a property of the current element is written `.name`
+            let is_last = i + 1 == self.segments.len();
+            if !(is_last && self.property.is_some() && *segment == Segment::Current) {
+                write!(f, "{segment}")?;
+            }
+        }
+
+        if let Some(property) = self.property {
+            write!(f, ".{property}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Segment<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Segment::Current => f.write_str("."),
+            Segment::Parent => f.write_str(".."),
+            Segment::Element {
+                prefix,
+                local,
+                index,
+                queries,
+            } => {
+                f.write_str(&crate::gen_full_name(prefix, local))?;
+                if let Some(index) = index {
+                    write!(f, "#{index}")?;
+                }
+                for (i, query) in queries.iter().enumerate() {
+                    f.write_str(if i == 0 { "?" } else { "&" })?;
+                    if let Some(child) = query.child {
+                        write!(f, "{child}.")?;
+                    }
+                    f.write_str(query.key)?;
+                    if let Some(value) = query.value {
+                        write!(f, "=\"{value}\"")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// The extensions of files which a lone name like `hello.png` refers to. Any other `name.ext`, like
+// `Body.title`, is a property of a child.
+const FILE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "bmp", "avif", "mp3", "wav", "ogg", "flac",
+    "mp4", "webm", "mov", "txt", "md", "json", "csv", "xml", "html", "css", "js", "wasm", "pdf",
+    "zip", "ttf", "otf", "woff", "woff2",
+];
+
+// The end of the document part of a URL: everything up to the last segment naming a `.trax` file,
+// the authority of a URL with a scheme like `trax.tcp:`, or the whole URL when it's a single file
+// name with a known extension, like `hello.png`.
+fn document_end(url: &str) -> Option<usize> {
+    let mut end = None;
+    let mut start = 0;
+
+    for segment in url.split('/') {
+        let name = segment.split(['#', '?']).next().unwrap_or_default();
+        if name.ends_with(".trax") {
+            end = Some(start + segment.len());
+        }
+        start += segment.len() + 1;
+    }
+
+    end.or_else(|| {
+        let first = url.split('/').next().unwrap_or_default();
+        let (scheme, _) = first.split_once(':')?;
+        scheme.contains('.').then_some(first.len())
+    })
+    .or_else(|| {
+        let name = url.split('#').next().unwrap_or_default();
+        let (stem, extension) = name.rsplit_once('.')?;
+        let is_file = !url.contains(['/', '?'])
+            && !stem.is_empty()
+            && !stem.ends_with('.')
+            && FILE_EXTENSIONS
+                .iter()
+                .any(|e| extension.eq_ignore_ascii_case(e));
+        is_file.then_some(url.len())
+    })
+}
+
+fn split_index(part: &str) -> Result<(&str, Option<usize>), UrlError> {
+    match part.split_once('#') {
+        Some((name, index)) => index
+            .parse()
+            .map(|i| (name, Some(i)))
+            .map_err(|_| UrlError::InvalidIndex(part.to_string())),
+        None => Ok((part, None)),
+    }
+}
+
+fn parse_segment(segment: &str) -> Result<(Segment<'_>, Option<&str>), UrlError> {
+    let invalid = || UrlError::InvalidSegment(segment.to_string());
+
+    match segment {
+        "." => return Ok((Segment::Current, None)),
+        ".." => return Ok((Segment::Parent, None)),
+        "" => return Err(invalid()),
+        _ => (),
+    }
+
+    let (head, query) = match segment.split_once('?') {
+        Some((head, query)) => (head, Some(query)),
+        None => (segment, None),
+    };
+    let (head, property) = match head.split_once('.') {
+        Some((head, property)) if !property.is_empty() => (head, Some(property)),
+        Some(_) => return Err(invalid()),
+        None => (head, None),
+    };
+    let (name, index) = split_index(head)?;
+
+    // `.name` is a property of the current element
+    if name.is_empty() {
+        return match (index, query, property) {
+            (None, None, Some(_)) => Ok((Segment::Current, property)),
+            _ => Err(invalid()),
+        };
+    }
+
+    let (prefix, local) = split_name(name);
+    let queries = match query {
+        Some(query) => query
+            .split('&')
+            .map(|q| parse_query(q).ok_or_else(invalid))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok((
+        Segment::Element {
+            prefix,
+            local,
+            index,
+            queries,
+        },
+        property,
+    ))
+}
+
+fn parse_query(query: &str) -> Option<Query<'_>> {
+    let (name, value) = match query.split_once('=') {
+        Some((name, value)) => {
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'')?.strip_suffix('\''))
+                .unwrap_or(value);
+            (name, Some(value))
+        }
+        None => (query, None),
+    };
+
+    let (child, key) = match name.split_once('.') {
+        Some((child, key)) => (Some(child), key),
+        None => (None, name),
+    };
+
+    (!key.is_empty() && child != Some("")).then_some(Query { child, key, value })
+}
@@ -0,0 +1,550 @@
+use std::{borrow::Cow, fmt::Display, ops::Range, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    split_name, Attribute, Comment, Document, Element, EntityRef, Fragment, Name, NodeId, Text,
+};
+
+/// A type from the spec's type system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// Any type.
+    Any,
+    /// The absence of a value where one was expected.
+    Undefined,
+    /// The expected absence of a value.
+    Void,
+    /// A boolean, represented in elements as the existence of a modifier.
+    Bool,
+    /// A string.
+    Str,
+    /// A signed 64-bit integer.
+    Int,
+    /// A 64-bit floating point number.
+    Float,
+    /// A non-inclusive range of integers, like `1..10`.
+    Range,
+    /// A string containing a URL.
+    Url,
+    /// An element.
+    Element,
+    /// An attribute.
+    Attribute,
+    /// A modifier.
+    Modifier,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Type::Any => "any",
+            Type::Undefined => "undefined",
+            Type::Void => "void",
+            Type::Bool => "bool",
+            Type::Str => "str",
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Range => "range",
+            Type::Url => "url",
+            Type::Element => "element",
+            Type::Attribute => "attribute",
+            Type::Modifier => "modifier",
+        })
+    }
+}
+
+impl FromStr for Type {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "any" => Type::Any,
+            "undefined" => Type::Undefined,
+            "void" => Type::Void,
+            "bool" => Type::Bool,
+            "str" => Type::Str,
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "range" => Type::Range,
+            "url" | "URL" => Type::Url,
+            "element" => Type::Element,
+            "attribute" => Type::Attribute,
+            "modifier" => Type::Modifier,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// A typed value. See [`Value::parse`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    /// The absence of a value where one was expected.
+    Undefined,
+    /// The expected absence of a value.
+    Void,
+    /// A boolean.
+    Bool(bool),
+    /// A string.
+    Str(Cow<'a, str>),
+    /// A signed 64-bit integer.
+    Int(i64),
+    /// A 64-bit floating point number.
+    Float(f64),
+    /// A non-inclusive range of integers.
+    Range(Range<i64>),
+    /// A string containing a URL.
+    Url(Cow<'a, str>),
+    /// An element and its descendants.
+    Element(Fragment<'a>),
+    /// An attribute with a value.
+    Attribute(Attribute<'a>),
+    /// A modifier, i.e. an attribute without a value.
+    Modifier(Attribute<'a>),
+}
+
+/// An error encountered when converting text or an attribute to a [`Value`].
+#[derive(Debug, PartialEq, Error)]
+pub enum ValueError {
+    /// The text isn't a valid value of the expected type.
+    #[error("expected {expected}, found `{found}`")]
+    InvalidValue {
+        /// The expected type.
+        expected: Type,
+        /// The text which couldn't be converted.
+        found: String,
+        /// The range of bytes in the source of the attribute's value, if known.
+        location: Option<Range<usize>>,
+    },
+
+    /// A modifier was found where an attribute with a value was expected.
+    #[error("expected {expected}, found a modifier")]
+    UnexpectedModifier {
+        /// The expected type.
+        expected: Type,
+        /// The range of bytes in the source of the modifier, if known.
+        location: Option<Range<usize>>,
+    },
+}
+
+impl ValueError {
+    /// The range of bytes in the source where the problem is, if known.
+    pub fn location(&self) -> Option<&Range<usize>> {
+        match self {
+            ValueError::InvalidValue { location, .. }
+            | ValueError::UnexpectedModifier { location, .. } => location.as_ref(),
+        }
+    }
+
+    fn at(mut self, span: Option<Range<usize>>) -> Self {
+        match &mut self {
+            ValueError::InvalidValue { location, .. }
+            | ValueError::UnexpectedModifier { location, .. } => *location = span,
+        }
+        self
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Parse text as a value of the given type. Surrounding whitespace is ignored, except by
+    /// strings.
+    ///
+    /// `any` infers the type: integers, then floats, then ranges, falling back to a string.
+    /// Booleans are `true` or `false`, elements are TRAX markup and attributes are written like
+    /// they are in an element, e.g. `key="value"` or `with:modifier`.
+    ///
+    /// ```rust
+    /// use trax_document::{Type, Value};
+    ///
+    /// assert_eq!(Value::parse(Type::Range, "1..10"), Ok(Value::Range(1..10)));
+    /// assert_eq!(Value::parse(Type::Any, " 0.5 "), Ok(Value::Float(0.5)));
+    /// assert!(Value::parse(Type::Int, "0.5").is_err());
+    /// ```
+    pub fn parse(ty: Type, text: &'a str) -> Result<Self, ValueError> {
+        let trimmed = text.trim();
+        let invalid = || ValueError::InvalidValue {
+            expected: ty,
+            found: text.to_string(),
+            location: None,
+        };
+
+        match ty {
+            Type::Any => Ok(Self::infer(text)),
+            Type::Undefined if trimmed == "undefined" => Ok(Value::Undefined),
+            Type::Void if trimmed == "void" => Ok(Value::Void),
+            Type::Bool => match trimmed {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(invalid()),
+            },
+            Type::Str => Ok(Value::Str(Cow::Borrowed(text))),
+            Type::Int => trimmed.parse().map(Value::Int).map_err(|_| invalid()),
+            Type::Float if is_number(trimmed) => {
+                trimmed.parse().map(Value::Float).map_err(|_| invalid())
+            }
+            Type::Range => parse_range(trimmed).map(Value::Range).ok_or_else(invalid),
+            Type::Url if !trimmed.is_empty() && !trimmed.contains(char::is_whitespace) => {
+                Ok(Value::Url(Cow::Borrowed(trimmed)))
+            }
+            Type::Element => match Fragment::parse(trimmed).as_deref() {
+                Ok([fragment @ Fragment::Element { .. }]) => Ok(Value::Element(fragment.clone())),
+                _ => Err(invalid()),
+            },
+            Type::Attribute => match parse_attribute(trimmed) {
+                Some(attribute) if !attribute.is_modifier() => Ok(Value::Attribute(attribute)),
+                _ => Err(invalid()),
+            },
+            Type::Modifier => match parse_attribute(trimmed) {
+                Some(attribute) if attribute.is_modifier() => Ok(Value::Modifier(attribute)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Parse text as `any`, inferring its type. See [`Value::parse`].
+    pub fn infer(text: &'a str) -> Self {
+        let trimmed = text.trim();
+
+        if let Ok(int) = trimmed.parse() {
+            Value::Int(int)
+        } else if let Some(float) = is_number(trimmed).then(|| trimmed.parse().ok()).flatten() {
+            Value::Float(float)
+        } else if let Some(range) = parse_range(trimmed) {
+            Value::Range(range)
+        } else {
+            Value::Str(Cow::Borrowed(text))
+        }
+    }
+
+    /// Convert an attribute to a value of the given type. Modifiers are `true` as a `bool`, and
+    /// are only accepted by `bool`, `modifier` and `any`.
+    pub fn from_attribute(ty: Type, attribute: &Attribute<'a>) -> Result<Self, ValueError> {
+        match (ty, &attribute.value) {
+            (Type::Attribute, Some(_)) => Ok(Value::Attribute(attribute.clone())),
+            (Type::Modifier | Type::Any, None) => Ok(Value::Modifier(attribute.clone())),
+            (Type::Bool, None) => Ok(Value::Bool(true)),
+            (_, None) => Err(ValueError::UnexpectedModifier {
+                expected: ty,
+                location: None,
+            }),
+            (_, Some(Cow::Borrowed(value))) => Value::parse(ty, value),
+            (_, Some(Cow::Owned(value))) => Value::parse(ty, value).map(Value::into_owned),
+        }
+    }
+
+    /// The type of the value.
+    pub fn ty(&self) -> Type {
+        match self {
+            Value::Undefined => Type::Undefined,
+            Value::Void => Type::Void,
+            Value::Bool(_) => Type::Bool,
+            Value::Str(_) => Type::Str,
+            Value::Int(_) => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Range(_) => Type::Range,
+            Value::Url(_) => Type::Url,
+            Value::Element(_) => Type::Element,
+            Value::Attribute(_) => Type::Attribute,
+            Value::Modifier(_) => Type::Modifier,
+        }
+    }
+
+    /// Returns the value if it's a `bool`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a `str` or `url`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) | Value::Url(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's an `int`.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a `float`, casting `int`s to `float`s.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a `range`.
+    pub fn as_range(&self) -> Option<Range<i64>> {
+        match self {
+            Value::Range(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+
+    /// Converts the value into one that doesn't borrow from the source.
+    pub fn into_owned(self) -> Value<'static> {
+        self.into_owned_with(&mut crate::Interner::new())
+    }
+
+    fn into_owned_with(self, interner: &mut crate::Interner) -> Value<'static> {
+        match self {
+            Value::Undefined => Value::Undefined,
+            Value::Void => Value::Void,
+            Value::Bool(b) => Value::Bool(b),
+            Value::Str(s) => Value::Str(Cow::Owned(s.into_owned())),
+            Value::Int(i) => Value::Int(i),
+            Value::Float(f) => Value::Float(f),
+            Value::Range(r) => Value::Range(r),
+            Value::Url(s) => Value::Url(Cow::Owned(s.into_owned())),
+            Value::Element(e) => Value::Element(e.into_owned(interner)),
+            Value::Attribute(a) => Value::Attribute(a.into_owned(interner)),
+            Value::Modifier(a) => Value::Modifier(a.into_owned(interner)),
+        }
+    }
+}
+
+/// Values are formatted so that [`Value::parse`] with the same type reads them back.
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Undefined => f.write_str("undefined"),
+            Value::Void => f.write_str("void"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Str(s) | Value::Url(s) => f.write_str(s),
+            Value::Int(i) => write!(f, "{i}"),
+            // Debug keeps the decimal point on whole numbers
+            Value::Float(v) => write!(f, "{v:?}"),
+            Value::Range(r) => write!(f, "{}..{}", r.start, r.end),
+            Value::Element(e) => write!(f, "{e}"),
+            Value::Attribute(a) | Value::Modifier(a) => write!(f, "{a}"),
+        }
+    }
+}
+
+impl From<bool> for Value<'_> {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value<'_> {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value<'_> {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<Range<i64>> for Value<'_> {
+    fn from(value: Range<i64>) -> Self {
+        Value::Range(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Str(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(value: String) -> Self {
+        Value::Str(Cow::Owned(value))
+    }
+}
+
+// `f64::from_str` also accepts `inf` and `NaN`, which are strings as far as TRAX is concerned.
+fn is_number(text: &str) -> bool {
+    text.trim_start_matches(['-', '+'])
+        .starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+fn parse_range(text: &str) -> Option<Range<i64>> {
+    let (start, end) = text.split_once("..")?;
+    Some(start.trim().parse().ok()?..end.trim().parse().ok()?)
+}
+
+fn parse_attribute(text: &str) -> Option<Attribute<'_>> {
+    let (name, value) = match text.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim();
+            let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+            let value = value.strip_prefix(quote)?.strip_suffix(quote)?;
+            if value.contains(quote) {
+                return None;
+            }
+            (name.trim(), Some(value))
+        }
+        None => (text, None),
+    };
+
+    let (prefix, local) = split_name(name);
+    let valid = |part: &str| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && part
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    };
+
+    (valid(local) && (prefix.is_empty() || valid(prefix)))
+        .then(|| Attribute::new(Name::from(prefix), Name::from(local), value))
+}
+
+/// An element of a [`Document`], with typed access to its attributes. See [`Document::element`].
+///
+/// Dereferences to the [`Element`] itself.
+#[derive(Clone, Copy, Debug)]
+pub struct ElementRef<'d, 'a> {
+    document: &'d Document<'a>,
+    id: NodeId,
+    element: &'d Element<'a>,
+}
+
+impl<'d, 'a> ElementRef<'d, 'a> {
+    /// The id of the element.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The document containing the element.
+    pub fn document(&self) -> &'d Document<'a> {
+        self.document
+    }
+
+    /// The element's attributes and modifiers, in order. Unlike [`Element::attributes`], they
+    /// borrow from the document rather than the reference.
+    pub fn attributes(&self) -> impl Iterator<Item = &'d Attribute<'a>> {
+        self.element.attributes.iter()
+    }
+
+    /// The element's children, in order. Unlike [`Element::children`], they borrow from the
+    /// document rather than the reference.
+    pub fn children(&self) -> impl Iterator<Item = &'d EntityRef> {
+        self.element.children.iter()
+    }
+
+    /// Returns the first attribute or modifier with the name, like `index` or `with:prefix`.
+    pub fn attribute(&self, name: &str) -> Option<&'d Attribute<'a>> {
+        let (prefix, local) = split_name(name);
+        self.element
+            .attributes
+            .iter()
+            .find(|a| a.prefix == prefix && a.local == local)
+    }
+
+    /// Convert an attribute to a value of the given type, or `None` if the element doesn't have
+    /// it. See [`Value::from_attribute`].
+    ///
+    /// Errors point at the attribute's value in the source, or at the whole attribute for
+    /// modifiers.
+    pub fn get(&self, name: &str, ty: Type) -> Result<Option<Value<'a>>, ValueError> {
+        let Some(attribute) = self.attribute(name) else {
+            return Ok(None);
+        };
+
+        Value::from_attribute(ty, attribute).map(Some).map_err(|e| {
+            let (prefix, local) = split_name(name);
+            let span = self
+                .document
+                .element_span(self.id)
+                .and_then(|s| s.attribute(prefix, local))
+                .map(|a| a.value.clone().unwrap_or_else(|| a.full.clone()));
+            e.at(span)
+        })
+    }
+
+    /// Returns `true` if the element has the modifier, or the attribute with a value of `true`.
+    pub fn get_bool(&self, name: &str) -> Result<bool, ValueError> {
+        Ok(self
+            .get(name, Type::Bool)?
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false))
+    }
+
+    /// Returns the value of an attribute.
+    pub fn get_str(&self, name: &str) -> Result<Option<&'d str>, ValueError> {
+        match self.attribute(name) {
+            Some(attribute) if attribute.is_modifier() => self.get(name, Type::Str).map(|_| None),
+            attribute => Ok(attribute.and_then(|a| a.value())),
+        }
+    }
+
+    /// Parse an attribute as an `int`.
+    pub fn get_int(&self, name: &str) -> Result<Option<i64>, ValueError> {
+        Ok(self.get(name, Type::Int)?.and_then(|v| v.as_int()))
+    }
+
+    /// Parse an attribute as a `float`. Integers are accepted too.
+    pub fn get_float(&self, name: &str) -> Result<Option<f64>, ValueError> {
+        Ok(self.get(name, Type::Float)?.and_then(|v| v.as_float()))
+    }
+
+    /// Parse an attribute as a non-inclusive `range`, like `1..10`.
+    pub fn get_range(&self, name: &str) -> Result<Option<Range<i64>>, ValueError> {
+        Ok(self.get(name, Type::Range)?.and_then(|v| v.as_range()))
+    }
+
+    /// Parse an attribute as a `url`.
+    pub fn get_url(&self, name: &str) -> Result<Option<Cow<'a, str>>, ValueError> {
+        Ok(self.get(name, Type::Url)?.and_then(|v| match v {
+            Value::Url(url) => Some(url),
+            _ => None,
+        }))
+    }
+}
+
+impl<'a> std::ops::Deref for ElementRef<'_, 'a> {
+    type Target = Element<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.element
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Returns an element by its id.
+    ///
+    /// ```rust
+    /// use trax_document::Document;
+    ///
+    /// let doc = Document::new(r#"<document><for in="1..10" index="2" /></document>"#).unwrap();
+    /// let element = doc.element(1).unwrap();
+    ///
+    /// assert_eq!(element.get_range("in"), Ok(Some(1..10)));
+    /// assert_eq!(element.get_int("index"), Ok(Some(2)));
+    /// assert_eq!(element.get_int("missing"), Ok(None));
+    /// ```
+    pub fn element(&self, id: NodeId) -> Option<ElementRef<'_, 'a>> {
+        Some(ElementRef {
+            document: self,
+            id,
+            element: self.element_store.get(id)?.as_ref()?,
+        })
+    }
+
+    /// Returns text by its id.
+    pub fn text(&self, id: usize) -> Option<&Text<'a>> {
+        self.text_store.get(id)?.as_ref()
+    }
+
+    /// Returns a comment by its id.
+    pub fn comment(&self, id: usize) -> Option<&Comment<'a>> {
+        self.comment_store.get(id)?.as_ref()
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{decode_message, encode_message, DecodeError, Document};
+
+    const TODO: &str = include_str!("../testfiles/todo.trax");
+
+    #[test]
+    fn round_trip_document() {
+        let doc = Document::new(TODO).unwrap();
+        let encoded = doc.encode();
+
+        assert_eq!(Document::decode(&encoded).unwrap(), doc);
+    }
+
+    #[test]
+    fn round_trip_modifiers() {
+        let src =
+            r#"<document><a one="1" two three="" four five six seven eight nine /></document>"#;
+        let doc = Document::new(src).unwrap();
+
+        assert_eq!(
+            Document::decode(&doc.encode()).unwrap().into_string(),
+            doc.into_string()
+        );
+    }
+
+    #[test]
+    fn smaller_than_text() {
+        let encoded = Document::new(TODO).unwrap().encode();
+        assert!(encoded.len() < TODO.len());
+    }
+
+    #[test]
+    fn round_trip_message() {
+        let src = r#"<get doc="todo.trax" />text<with:prefix flag><b /></with:prefix>"#;
+        let encoded = encode_message(src).unwrap();
+        let fragments = decode_message(&encoded).unwrap();
+
+        assert_eq!(
+            fragments.iter().map(|f| f.to_string()).collect::<String>(),
+            r#"<get doc="todo.trax" />text<with:prefix flag><b /></with:prefix>"#
+        );
+    }
+
+    #[test]
+    fn wrong_kind() {
+        let message = encode_message("<get />").unwrap();
+        assert_eq!(
+            Document::decode(&message),
+            Err(DecodeError::UnexpectedKind(1))
+        );
+
+        let doc = Document::new("<document />").unwrap().encode();
+        assert_eq!(decode_message(&doc), Err(DecodeError::UnexpectedKind(0)));
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            Document::decode(b"<document />"),
+            Err(DecodeError::InvalidMagic)
+        );
+        assert_eq!(
+            Document::decode(b"TRAX\x09\x00"),
+            Err(DecodeError::UnsupportedVersion(9))
+        );
+
+        let encoded = Document::new(TODO).unwrap().encode();
+        for len in 0..encoded.len() {
+            assert!(Document::decode(&encoded[..len]).is_err());
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(
+            Document::decode(&trailing),
+            Err(DecodeError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn invalid_root() {
+        let message = encode_message("<get />").unwrap();
+        let mut doc = message.clone();
+        // relabel the message as a document
+        doc[5] = 0;
+        assert_eq!(Document::decode(&doc), Err(DecodeError::InvalidRootElement));
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{diff, Document, EntityRef, Fragment, PlacePosition, RenderOptions};
+
+    const SRC: &str = r#"<document>
+	/* a note */
+	<one>
+		text
+		/*nested*/
+	</one>
+	<two>
+		/* only a comment */
+	</two>
+</document>
+"#;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(Document::new(SRC).unwrap().into_string(), SRC);
+    }
+
+    #[test]
+    fn strip() {
+        let options = RenderOptions {
+            strip_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Document::new(SRC).unwrap().render(&options),
+            "<document>\n\t<one>\n\t\ttext\n\t</one>\n\t<two />\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn edit() {
+        let mut doc = Document::new(SRC).unwrap();
+        doc.drop(EntityRef::Comment(0)).unwrap();
+        doc.insert_comment(2, PlacePosition::End, " added ")
+            .unwrap();
+
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<one>\n\t\ttext\n\t\t/*nested*/\n\t</one>\n\t<two>\n\t\t/* only a comment */\n\t\t/* added */\n\t</two>\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn fragment() {
+        let doc = Document::new(SRC).unwrap();
+        assert_eq!(
+            doc.fragment(&EntityRef::Element(1)).unwrap(),
+            Fragment::Element {
+                prefix: "".into(),
+                local: "one".into(),
+                attributes: [].into(),
+                children: vec![
+                    Fragment::Text("text".into()),
+                    Fragment::Comment("nested".into())
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn undo_drop() {
+        let mut doc = Document::new(SRC).unwrap();
+        let mut transaction = doc.transaction();
+        transaction.drop(EntityRef::Element(1)).unwrap();
+        let changeset = transaction.commit();
+
+        changeset.revert(&mut doc).unwrap();
+        assert_eq!(doc.into_string(), SRC);
+    }
+
+    #[test]
+    fn diff_comments() {
+        let src = SRC.replace("a note", "another note");
+        let old = Document::new(SRC).unwrap();
+        let new = Document::new(&src).unwrap();
+
+        let mut patched = old.clone();
+        patched.apply_all(diff(&old, &new)).unwrap();
+        assert_eq!(patched.into_string(), new.into_string());
+    }
+
+    #[test]
+    fn binary() {
+        let doc = Document::new(SRC).unwrap();
+        assert_eq!(Document::decode(&doc.encode()).unwrap(), doc);
+    }
+
+    #[test]
+    fn outside_root() {
+        let doc = Document::new("<document></document>\n/* trailing */").unwrap();
+        assert_eq!(doc.into_string(), "<document />\n");
+    }
+}
@@ -0,0 +1,216 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Document, EvalContext, EvalError, Target, Type, Url, Value};
+
+    const SRC: &str = r#"<document>
+    <Frame>
+        <Body>
+            <Todo title="Do Laundry" done />
+            <Todo title="Work on TRAX" count="3" />
+        </Body>
+        <mul> 2 <abs> -1.5 </abs> </mul>
+    </Frame>
+    <Todo
+        asRef:title="document/Frame/Body/Todo#1.title"
+        asEval:count="document/Frame/Body/Todo#1.count"
+        asRef:done="document/Frame/Missing"
+        asEval:sum="document/Frame/mul"
+        asRef:missing="document/Nothing"
+    />
+    <Todo asEval:done="document/Frame/mul" asEval:other="../Frame/Body/Todo.done" />
+</document>"#;
+
+    // ids of the `Todo`s outside of the `Frame`
+    const REFS: usize = 7;
+    const EVALS: usize = 8;
+
+    fn resolve(context: &EvalContext, from: usize, url: &str) -> Option<usize> {
+        match context.resolve(from, &Url::parse(url).unwrap())? {
+            Target::Element(id) => Some(id),
+            Target::Property(id, _) => Some(id),
+        }
+    }
+
+    #[test]
+    fn paths() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+
+        assert_eq!(
+            resolve(&context, REFS, "document/Frame/Body/Todo#1"),
+            Some(4)
+        );
+        assert_eq!(resolve(&context, 4, "../Todo"), Some(3));
+        assert_eq!(resolve(&context, 4, ".."), Some(2));
+        assert_eq!(resolve(&context, 0, "Frame/Body/Todo?done"), Some(3));
+        assert_eq!(
+            resolve(&context, 0, "Frame/Body?Todo.title=\"Work on TRAX\""),
+            Some(2)
+        );
+        assert_eq!(resolve(&context, 0, "Frame/Body/Todo#2"), None);
+        assert_eq!(resolve(&context, 0, ".."), None);
+        // naming a document needs the context to know its own name
+        assert_eq!(resolve(&context, 0, "todo.trax#0/Frame"), None);
+
+        let context = context.with_name("todo.trax");
+        assert_eq!(resolve(&context, 4, "todo.trax#0/Frame"), Some(1));
+        assert_eq!(resolve(&context, 4, "other.trax/Frame"), None);
+    }
+
+    #[test]
+    fn reference_and_evaluate() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+
+        assert_eq!(
+            context.reference(0, "document/Frame/mul").to_string(),
+            "<mul>2<abs>-1.5</abs></mul>"
+        );
+        assert_eq!(
+            context.evaluate(0, "document/Frame/mul"),
+            Ok(Value::Float(3.0))
+        );
+        assert_eq!(
+            context.reference(0, "Frame/Body/Todo#1.count"),
+            Value::from("3")
+        );
+        assert_eq!(
+            context.evaluate(0, "Frame/Body/Todo#1.count"),
+            Ok(Value::Int(3))
+        );
+        assert_eq!(
+            context.evaluate(0, "Frame/Body/Todo.done"),
+            Ok(Value::Bool(true))
+        );
+        // non-arithmetic elements evaluate to void
+        assert_eq!(context.evaluate(0, "Frame"), Ok(Value::Void));
+        // failures are undefined
+        assert_eq!(context.reference(0, "Frame/Nothing"), Value::Undefined);
+        assert_eq!(context.evaluate(0, "Frame//Body"), Ok(Value::Undefined));
+    }
+
+    #[test]
+    fn prefixed_properties() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+
+        assert_eq!(
+            context.property(REFS, "title", Type::Str),
+            Ok(Value::from("Work on TRAX"))
+        );
+        assert_eq!(
+            context.property(REFS, "count", Type::Int),
+            Ok(Value::Int(3))
+        );
+        assert_eq!(
+            context.property(REFS, "sum", Type::Float),
+            Ok(Value::Float(3.0))
+        );
+        assert_eq!(
+            context.property(REFS, "missing", Type::Str),
+            Ok(Value::Undefined)
+        );
+        assert_eq!(
+            context.property(REFS, "absent", Type::Str),
+            Ok(Value::Undefined)
+        );
+
+        // asRef modifiers are whether the URL is valid
+        assert_eq!(
+            context.property(REFS, "done", Type::Bool),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            context.property(EVALS, "other", Type::Bool),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            context.property(EVALS, "absent", Type::Bool),
+            Ok(Value::Bool(false))
+        );
+
+        // plain properties are still typed
+        assert_eq!(
+            context.property(3, "done", Type::Bool),
+            Ok(Value::Bool(true))
+        );
+        assert!(matches!(
+            context.property(3, "title", Type::Int),
+            Err(EvalError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn as_eval_modifier_type_mismatch() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+
+        let err = context.property(EVALS, "done", Type::Bool).unwrap_err();
+        assert_eq!(
+            err,
+            EvalError::AsEvalModifierTypeMismatch {
+                modifier: "done".to_string(),
+                url: "document/Frame/mul".to_string(),
+                value: Box::new(Value::Float(3.0)),
+                location: err.location().cloned(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "`done` is a modifier, but `document/Frame/mul` evaluated to float `3.0`"
+        );
+        assert_eq!(
+            &SRC[err.location().unwrap().clone()],
+            "asEval:done=\"document/Frame/mul\""
+        );
+    }
+
+    #[test]
+    fn eval_errors_propagate() {
+        let src = r#"<document><div> 1 0 </div><Todo asEval:count="../div" /></document>"#;
+        let doc = Document::new(src).unwrap();
+        let context = EvalContext::new(&doc);
+
+        assert!(matches!(
+            context.property(2, "count", Type::Int),
+            Err(EvalError::DivideByZero { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_all() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+        let all = |url: &str| -> Vec<usize> {
+            context
+                .resolve_all(0, &Url::parse(url).unwrap())
+                .into_iter()
+                .map(|t| match t {
+                    Target::Element(id) | Target::Property(id, _) => id,
+                })
+                .collect()
+        };
+
+        assert_eq!(all("document/Frame/Body/Todo"), [3, 4]);
+        assert_eq!(all("document/Frame/Body/Todo#1"), [4]);
+        assert_eq!(all("document/Frame/Body/Todo.done"), [3]);
+        assert_eq!(all("document/Frame/Body/Todo?title"), [3, 4]);
+        assert_eq!(all("document/Todo"), [REFS, EVALS]);
+        assert!(all("document/Missing/Todo").is_empty());
+        assert_eq!(all("document"), [0]);
+    }
+
+    #[test]
+    fn url_of() {
+        let doc = Document::new(SRC).unwrap();
+        let context = EvalContext::new(&doc);
+
+        for id in [0, 3, 4, 6, EVALS] {
+            let url = doc.url_of(id).unwrap();
+            assert_eq!(context.resolve(0, &url), Some(Target::Element(id)));
+        }
+        assert_eq!(doc.url_of(EVALS).unwrap().to_string(), "document/Todo#1");
+        assert_eq!(doc.url_of(100), None);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Attribute, Diagnostic, DiagnosticCode, Document, PlacePosition, Severity};
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<DiagnosticCode> {
+        diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn valid() {
+        let doc = Document::new(include_str!("../testfiles/todo.trax")).unwrap();
+        assert_eq!(doc.validate(), []);
+    }
+
+    #[test]
+    fn collects_everything() {
+        let src = r#"<document>
+    <a key="1" key="2" />
+    <insert start />
+    <redirect url="todo.trax" />
+    <b><document /></b>
+    <insert target="todo.trax"><document /></insert>
+    <with:prefix nope:mod />
+</document>"#;
+        let (_, diagnostics) = Document::parse_with_diagnostics(src);
+
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                DiagnosticCode::DuplicateAttribute,
+                DiagnosticCode::MissingProperty,
+                DiagnosticCode::MissingProperty,
+                DiagnosticCode::NestedDocument,
+                DiagnosticCode::UnknownPrefix,
+                DiagnosticCode::UnknownPrefix,
+            ]
+        );
+
+        assert_eq!(&src[diagnostics[0].span.clone().unwrap()], "key=\"2\"");
+        assert_eq!(
+            &src[diagnostics[1].span.clone().unwrap()],
+            "<insert start />"
+        );
+        assert_eq!(
+            diagnostics[2].message,
+            "`redirect` requires the `connection` property"
+        );
+        assert_eq!(&src[diagnostics[5].span.clone().unwrap()], "nope:mod");
+        assert_eq!(diagnostics[5].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn mismatched_and_unclosed() {
+        let src = "<document><a><b></a><c></d></c><e>";
+        let (doc, diagnostics) = Document::parse_with_diagnostics(src);
+
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                DiagnosticCode::UnclosedTag,
+                DiagnosticCode::MismatchedTag,
+                DiagnosticCode::UnclosedTag,
+                DiagnosticCode::UnclosedTag,
+            ]
+        );
+        assert_eq!(&src[diagnostics[0].span.clone().unwrap()], "<b>");
+        assert_eq!(&src[diagnostics[1].span.clone().unwrap()], "</d>");
+        assert_eq!(diagnostics[2].message, "`document` is never closed");
+        assert_eq!(diagnostics[3].message, "`e` is never closed");
+        assert_eq!(
+            doc.render(&Default::default()),
+            "<document>\n\t<a>\n\t\t<b />\n\t</a>\n\t<c />\n\t<e />\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn syntax_error() {
+        let src = "<document>\n\t<a>\n\t<b =\"\" />\n</document>";
+        let (doc, diagnostics) = Document::parse_with_diagnostics(src);
+
+        assert_eq!(codes(&diagnostics)[0], DiagnosticCode::SyntaxError);
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<a>\n\t\t<b />\n\t</a>\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_root() {
+        let (doc, diagnostics) = Document::parse_with_diagnostics("<a>text</a>");
+        assert_eq!(codes(&diagnostics), [DiagnosticCode::InvalidRootElement]);
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<a>\n\t\ttext\n\t</a>\n</document>\n"
+        );
+
+        let (_, diagnostics) = Document::parse_with_diagnostics("");
+        assert_eq!(codes(&diagnostics), [DiagnosticCode::EmptyDocument]);
+    }
+
+    #[test]
+    fn edited() {
+        let mut doc = Document::new("<document />").unwrap();
+        let id = doc.insert(0, PlacePosition::End, "", "insert", []).unwrap();
+        assert_eq!(doc.validate()[0].span, None);
+
+        doc.set_attribute(id, Attribute::new("", "target", Some("todo.trax")))
+            .unwrap();
+        assert_eq!(doc.validate(), []);
+    }
+
+    #[test]
+    fn display() {
+        let (_, diagnostics) = Document::parse_with_diagnostics(r#"<document a="" a="" />"#);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error[DuplicateAttribute] at 15..19: `a` is set more than once on `document`"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+macro_rules! diff {
+    ($name: ident, $old: literal, $new: literal, [$($patch: literal),* $(,)?]) => {
+        #[test]
+        fn $name() {
+            let old = Document::new($old).unwrap();
+            let new = Document::new($new).unwrap();
+            let patches = diff(&old, &new)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            assert_eq!(patches, Vec::<&str>::from([$($patch),*]));
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{diff, Document};
+
+    diff!(
+        identical,
+        "<document><a k=\"v\">text</a></document>",
+        "<document><a k=\"v\">text</a></document>",
+        []
+    );
+
+    diff!(
+        attributes,
+        "<document><a one=\"1\" two=\"2\" three /></document>",
+        "<document><a one=\"1\" two=\"two\" four /></document>",
+        [
+            "~ Element at 1: two=\"two\"",
+            "~ Element at 1: four",
+            "~ Element at 1: -three"
+        ]
+    );
+
+    diff!(
+        text,
+        "<document><a>old</a></document>",
+        "<document><a>new</a></document>",
+        ["~ Text at 0: \"new\""]
+    );
+
+    diff!(
+        insert_and_drop,
+        "<document><a /><b /><c /></document>",
+        "<document><a /><new with:attr=\"x\">text</new><c /></document>",
+        [
+            "- Element at 2",
+            "+ Element at 0[1]: <new with:attr=\"x\">text</new>"
+        ]
+    );
+
+    diff!(
+        reorder,
+        "<document><a /><b /><c /></document>",
+        "<document><c /><a /><b /></document>",
+        ["> Element at 3 to Element at 0[0]"]
+    );
+
+    diff!(
+        rotate,
+        "<document><a /><b /><c /></document>",
+        "<document><b /><c /><a /></document>",
+        ["> Element at 1 to Element at 0[2]"]
+    );
+
+    diff!(
+        reverse,
+        "<document><a /><b /><c /></document>",
+        "<document><c /><b /><a /></document>",
+        [
+            "> Element at 2 to Element at 0[0]",
+            "> Element at 3 to Element at 0[0]"
+        ]
+    );
+
+    diff!(
+        move_and_insert,
+        "<document><a /><b /><c /><d /></document>",
+        "<document><b /><new /><c /><a /><d /></document>",
+        [
+            "> Element at 1 to Element at 0[2]",
+            "+ Element at 0[1]: <new />"
+        ]
+    );
+
+    diff!(
+        keyed_by_attributes,
+        "<document><Todo title=\"one\" /><Todo title=\"two\" /></document>",
+        "<document><Todo title=\"two\" /><Todo title=\"one\" done /></document>",
+        ["> Element at 2 to Element at 0[0]", "~ Element at 1: done"]
+    );
+
+    diff!(
+        nested,
+        "<document><Frame><Body><Todo /></Body></Frame></document>",
+        "<document><Frame><Body><Todo /><Todo title=\"x\" /></Body></Frame></document>",
+        ["+ Element at 2[1]: <Todo title=\"x\" />"]
+    );
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Document, EntityRef, EvalError, Type, Value};
+
+    fn eval(src: &str) -> Result<Value<'static>, EvalError> {
+        let src = format!("<document>{src}</document>");
+        let doc = Document::new(&src).unwrap();
+        let value = doc.evaluate(&EntityRef::Element(1));
+        value.map(Value::into_owned)
+    }
+
+    #[test]
+    fn spec_examples() {
+        assert_eq!(
+            eval("<add>\n\t1\n\t2\n\t<abs> -3 </abs>\n</add>"),
+            Ok(Value::Int(6))
+        );
+        assert_eq!(eval("<sub> 1 2 </sub>"), Ok(Value::Int(-1)));
+        assert_eq!(
+            eval("<mul>\n\t3\n\t<abs> -4 </abs>\n\t5\n\t6\n</mul>"),
+            Ok(Value::Int(360))
+        );
+        assert_eq!(eval("<div> 1 4 </div>"), Ok(Value::Float(0.25)));
+        assert_eq!(eval("<mod> 35 6 </mod>"), Ok(Value::Int(5)));
+        assert_eq!(eval("<abs> -3 </abs>"), Ok(Value::Int(3)));
+    }
+
+    #[test]
+    fn promotion() {
+        assert_eq!(eval("<add> 1 0.5 </add>"), Ok(Value::Float(1.5)));
+        assert_eq!(
+            eval("<mul> 2 <abs> -1.5 </abs> </mul>"),
+            Ok(Value::Float(3.0))
+        );
+        assert_eq!(eval("<div> 8 2 </div>"), Ok(Value::Int(4)));
+        // floats stay floats even when they're whole
+        assert_eq!(eval("<sub> 2.5 0.5 </sub>"), Ok(Value::Float(2.0)));
+        assert_eq!(eval("<add />"), Ok(Value::Int(0)));
+        assert_eq!(eval("<mul />"), Ok(Value::Int(1)));
+    }
+
+    #[test]
+    fn child_indexing() {
+        let doc =
+            Document::new("<document><sub> 1 /* skipped */ <add> 2 3 </add> </sub></document>")
+                .unwrap();
+
+        assert_eq!(doc.eval_child(1, 0), Ok(Value::Int(1)));
+        assert_eq!(doc.eval_child(1, 1), Ok(Value::Int(5)));
+        assert_eq!(doc.eval_child(1, 2), Ok(Value::Undefined));
+        assert_eq!(doc.evaluate(&EntityRef::Element(1)), Ok(Value::Int(-4)));
+    }
+
+    #[test]
+    fn non_arithmetic() {
+        let doc = Document::new("<document><Text> 5 </Text></document>").unwrap();
+
+        assert_eq!(doc.evaluate(&EntityRef::Element(1)), Ok(Value::Void));
+        assert_eq!(doc.evaluate(&EntityRef::Text(0)), Ok(Value::Int(5)));
+        assert_eq!(doc.evaluate(&EntityRef::Element(9)), Ok(Value::Undefined));
+    }
+
+    #[test]
+    fn divide_by_zero() {
+        let src = "<document><div> 1 <sub> 2 2 </sub> </div><mod> 1.5 0 </mod></document>";
+        let doc = Document::new(src).unwrap();
+
+        let err = doc.evaluate(&EntityRef::Element(1)).unwrap_err();
+        assert_eq!(err.to_string(), "division by zero");
+        assert_eq!(&src[err.location().unwrap().clone()], "<div>");
+
+        assert!(matches!(
+            doc.evaluate(&EntityRef::Element(3)),
+            Err(EvalError::DivideByZero { .. })
+        ));
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let src = "<document><add> 1 two </add><sub> 1 </sub></document>";
+        let doc = Document::new(src).unwrap();
+
+        let err = doc.evaluate(&EntityRef::Element(1)).unwrap_err();
+        assert_eq!(err.to_string(), "expected int or float, found str `two`");
+        assert_eq!(&src[err.location().unwrap().clone()], "two");
+
+        // a missing operand is undefined, and is reported at the element
+        let err = doc.evaluate(&EntityRef::Element(2)).unwrap_err();
+        assert!(matches!(
+            err,
+            EvalError::TypeMismatch {
+                found: Type::Undefined,
+                ..
+            }
+        ));
+        assert_eq!(&src[err.location().unwrap().clone()], "<sub>");
+    }
+
+    #[test]
+    fn escaped_operand() {
+        // unescaped words aren't slices of the source, so they're located by the whole text
+        let src = "<document><add>&#49; 2 &lt;3</add></document>";
+        let doc = Document::new(src).unwrap();
+
+        let err = doc.evaluate(&EntityRef::Element(1)).unwrap_err();
+        assert_eq!(err.to_string(), "expected int or float, found str `<3`");
+        assert_eq!(&src[err.location().unwrap().clone()], "&#49; 2 &lt;3");
+    }
+
+    #[test]
+    fn unexpected_operand() {
+        let src = "<document><sub> 3 2 1 </sub></document>";
+        let doc = Document::new(src).unwrap();
+
+        let err = doc.evaluate(&EntityRef::Element(1)).unwrap_err();
+        assert_eq!(err.to_string(), "`sub` doesn't use #2");
+        assert_eq!(err.location(), Some(&(20..21)));
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(matches!(
+            eval("<add> 9223372036854775807 1 </add>"),
+            Err(EvalError::Overflow { .. })
+        ));
+        assert!(matches!(
+            eval("<abs> -9223372036854775808 </abs>"),
+            Err(EvalError::Overflow { .. })
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{
+        Document, DocumentParseError, DropEntityError, EntityRef, EvalContext, Exception, Fragment,
+        Type, Value,
+    };
+
+    #[test]
+    fn from_errors() {
+        let doc = Document::new("<document><div> 1 0 </div><add> x </add></document>").unwrap();
+
+        let divide: Exception = doc.evaluate(&EntityRef::Element(1)).unwrap_err().into();
+        assert_eq!(divide, Exception::DivideByZero);
+
+        let mismatch: Exception = doc.evaluate(&EntityRef::Element(2)).unwrap_err().into();
+        assert_eq!(mismatch.name(), "TypeMismatch");
+        assert_eq!(mismatch.location(), Some(&(32..33)));
+
+        let parse: Exception = Document::new("<doxument />").unwrap_err().into();
+        assert_eq!(parse.name(), "DocumentParseException");
+        assert_eq!(parse.location(), Some(&(0..9)));
+
+        let src = "<document><a><b></a></b></document>";
+        let parse: Exception = Document::new(src).unwrap_err().into();
+        assert_eq!(&src[parse.location().unwrap().clone()], "</a>");
+
+        let parse: Exception = DocumentParseError::EmptyDocument.into();
+        assert_eq!(parse.location(), None);
+
+        let dropped: Exception = DropEntityError::RefuseDropRoot.into();
+        assert_eq!(dropped.name(), "RefuseDropRoot");
+        assert_eq!(
+            dropped.to_string(),
+            DropEntityError::RefuseDropRoot.to_string()
+        );
+    }
+
+    #[test]
+    fn from_diagnostics() {
+        let (_, diagnostics) = Document::parse_with_diagnostics("<document><a></b></document>");
+        let exception = Exception::from(diagnostics[0].clone());
+
+        assert_eq!(
+            exception,
+            Exception::DocumentParseException {
+                location: diagnostics[0].span.clone(),
+                description: diagnostics[0].message.clone(),
+            }
+        );
+    }
+
+    #[test]
+    fn as_eval_modifier_type_mismatch() {
+        let src = r#"<document><add> 1 2 </add><Todo asEval:done="../add" /></document>"#;
+        let doc = Document::new(src).unwrap();
+        let err = EvalContext::new(&doc)
+            .property(2, "done", Type::Bool)
+            .unwrap_err();
+
+        assert_eq!(
+            Exception::from(err),
+            Exception::AsEvalModifierTypeMismatch {
+                modifier: "done".to_string(),
+                url: "../add".to_string(),
+                value: Box::new(Value::Int(3)),
+            }
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let exceptions = [
+            Exception::DivideByZero,
+            Exception::AsEvalModifierTypeMismatch {
+                modifier: "done".to_string(),
+                url: "../add".to_string(),
+                value: Box::new(Value::Float(0.5)),
+            },
+            Exception::DocumentParseException {
+                location: None,
+                description: "Can't parse an empty document".to_string(),
+            },
+            Exception::from(DocumentParseError::EmptyDocument),
+            Exception::Runtime {
+                name: "NotFound".to_string(),
+                location: Some(1..4),
+                description: "not found".to_string(),
+            },
+            Exception::Runtime {
+                name: "Quoted".to_string(),
+                location: None,
+                description: r#"`<a b="it's">` & 'c' &amp; "d""#.to_string(),
+            },
+            Exception::from(Document::new("<doxument />").unwrap_err()),
+        ];
+
+        for exception in exceptions {
+            let fragment = exception.to_fragment();
+            let markup = fragment.to_string();
+            let parsed = Fragment::parse(&markup).unwrap();
+
+            assert_eq!(Exception::from_fragment(&parsed[0]), Some(exception));
+        }
+
+        assert_eq!(
+            Exception::DivideByZero.to_fragment().to_string(),
+            "<exception name=\"DivideByZero\" />"
+        );
+    }
+
+    #[test]
+    fn invalid_elements() {
+        for src in [
+            "<error name=\"DivideByZero\" />",
+            "<exception />",
+            "<exception name=\"DocumentParseException\" />",
+            "<exception name=\"NotFound\" location=\"x\" description=\"\" />",
+            "text",
+        ] {
+            let fragments = Fragment::parse(src).unwrap();
+            assert_eq!(Exception::from_fragment(&fragments[0]), None, "{src}");
+        }
+    }
+}
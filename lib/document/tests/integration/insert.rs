@@ -0,0 +1,161 @@
+const SRC: &str = "<document><a /><b />text<c /></document>";
+
+// Renders the expected document from the rendered lines of the root's children.
+fn expected(children: &[&str]) -> String {
+    let mut res = String::from("<document>\n");
+    for child in children {
+        res += "\t";
+        res += child;
+        res += "\n";
+    }
+    res + "</document>\n"
+}
+
+macro_rules! insert {
+    ($name: ident, $position: expr, [$($child: literal),*]) => {
+        #[test]
+        fn $name() {
+            let mut doc = Document::new(SRC).unwrap();
+            let id = doc.insert(0, $position, "", "new", []).unwrap();
+            assert_eq!(id, 4);
+            assert_eq!(doc.into_string(), expected(&[$($child),*]));
+        }
+    };
+
+    ($name: ident, $position: expr, $err: expr) => {
+        #[test]
+        fn $name() {
+            let mut doc = Document::new(SRC).unwrap();
+            assert_eq!(doc.insert(0, $position, "", "new", []), Err($err));
+            assert_eq!(doc, Document::new(SRC).unwrap());
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use trax_document::{Attribute, Document, EntityRef, InsertElementError, PlacePosition::*};
+
+    insert!(start, Start, ["<new />", "<a />", "<b />", "text", "<c />"]);
+    insert!(end, End, ["<a />", "<b />", "text", "<c />", "<new />"]);
+
+    insert!(
+        start_index_0,
+        StartIndex(0),
+        ["<new />", "<a />", "<b />", "text", "<c />"]
+    );
+    insert!(
+        start_index_2,
+        StartIndex(2),
+        ["<a />", "<b />", "<new />", "text", "<c />"]
+    );
+    insert!(
+        start_index_4,
+        StartIndex(4),
+        ["<a />", "<b />", "text", "<c />", "<new />"]
+    );
+    insert!(
+        start_index_5,
+        StartIndex(5),
+        InsertElementError::PositionOutOfRange(StartIndex(5), EntityRef::Element(0), 4)
+    );
+
+    insert!(
+        end_index_0,
+        EndIndex(0),
+        ["<a />", "<b />", "text", "<c />", "<new />"]
+    );
+    insert!(
+        end_index_1,
+        EndIndex(1),
+        ["<a />", "<b />", "text", "<new />", "<c />"]
+    );
+    insert!(
+        end_index_4,
+        EndIndex(4),
+        ["<new />", "<a />", "<b />", "text", "<c />"]
+    );
+    insert!(
+        end_index_5,
+        EndIndex(5),
+        InsertElementError::PositionOutOfRange(EndIndex(5), EntityRef::Element(0), 4)
+    );
+
+    insert!(replace_0, Replace(0), ["<new />", "<b />", "text", "<c />"]);
+    insert!(
+        replace_text,
+        Replace(2),
+        ["<a />", "<b />", "<new />", "<c />"]
+    );
+    insert!(replace_3, Replace(3), ["<a />", "<b />", "text", "<new />"]);
+    insert!(
+        replace_4,
+        Replace(4),
+        InsertElementError::PositionOutOfRange(Replace(4), EntityRef::Element(0), 4)
+    );
+
+    #[test]
+    fn into_empty_parent() {
+        for position in [Start, End, StartIndex(0), EndIndex(0)] {
+            let mut doc = Document::new(SRC).unwrap();
+            let id = doc.insert(1, position, "", "new", []).unwrap();
+            doc.insert(
+                id,
+                End,
+                "with",
+                "attrs",
+                [Attribute::new("", "k", Some("v"))],
+            )
+            .unwrap();
+            assert_eq!(
+                doc.into_string(),
+                expected(&[
+                    "<a>",
+                    "\t<new>",
+                    "\t\t<with:attrs k=\"v\" />",
+                    "\t</new>",
+                    "</a>",
+                    "<b />",
+                    "text",
+                    "<c />"
+                ])
+            );
+        }
+
+        for position in [StartIndex(1), EndIndex(1), Replace(0)] {
+            let mut doc = Document::new(SRC).unwrap();
+            assert_eq!(
+                doc.insert(1, position, "", "new", []),
+                Err(InsertElementError::PositionOutOfRange(
+                    position,
+                    EntityRef::Element(1),
+                    0
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn into_missing_parent() {
+        let mut doc = Document::new(SRC).unwrap();
+        doc.drop(EntityRef::Element(2)).unwrap();
+
+        for parent in [2, 100] {
+            assert_eq!(
+                doc.insert(parent, End, "", "new", []),
+                Err(InsertElementError::NotFound(EntityRef::Element(parent)))
+            );
+        }
+    }
+
+    #[test]
+    fn insert_text() {
+        let mut doc = Document::new(SRC).unwrap();
+        assert_eq!(doc.insert_text(0, EndIndex(1), "more"), Ok(1));
+        assert_eq!(
+            doc.into_string(),
+            expected(&["<a />", "<b />", "text", "more", "<c />"])
+        );
+    }
+}
@@ -1,7 +1,32 @@
 #![feature(macro_metavar_expr)]
 
+use trax_document::{Document, EntityRef, NodeId};
+
+mod binary;
+mod comment;
+mod context;
+mod diagnostic;
+mod diff;
 mod drop;
+mod eval;
+mod exception;
+mod insert;
+mod observe;
+mod owned;
 mod parse;
+mod render;
+mod schema;
+mod serialize;
+mod span;
+mod splice;
+mod transaction;
+mod url;
+mod value;
+
+// The markup of an element.
+fn render(doc: &Document, id: NodeId) -> String {
+    doc.fragment(&EntityRef::Element(id)).unwrap().to_string()
+}
 
 // This macro **should** work but I'm using an experimental method to
 // generate macros that generate macros that generate macros tha-
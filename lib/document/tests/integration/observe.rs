@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use pretty_assertions::assert_eq;
+    use trax_document::{Attribute, ChangeEvent, Document, EntityRef, Fragment, PlacePosition};
+
+    const SRC: &str = "<document><a k=\"v\">text</a><b /></document>";
+
+    fn observed() -> (Document<'static>, Arc<Mutex<Vec<ChangeEvent>>>) {
+        let mut doc = Document::new(SRC).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        doc.subscribe(move |e| sink.lock().unwrap().push(e.clone()));
+        (doc, events)
+    }
+
+    fn take(events: &Mutex<Vec<ChangeEvent>>) -> Vec<ChangeEvent> {
+        std::mem::take(&mut events.lock().unwrap())
+    }
+
+    #[test]
+    fn manipulation_events() {
+        let (mut doc, events) = observed();
+
+        doc.insert(0, PlacePosition::Replace(1), "", "c", [])
+            .unwrap();
+        assert_eq!(
+            take(&events),
+            [
+                ChangeEvent::Dropped {
+                    node: EntityRef::Element(2),
+                    parent: 0,
+                    index: 1
+                },
+                ChangeEvent::Inserted {
+                    node: EntityRef::Element(3),
+                    parent: 0,
+                    index: 1
+                }
+            ]
+        );
+
+        doc.set_attribute(1, Attribute::new("with", "mod", None::<&str>))
+            .unwrap();
+        doc.remove_attribute(1, "", "k").unwrap();
+        doc.set_text(0, "changed").unwrap();
+        doc.move_to(EntityRef::Element(3), 0).unwrap();
+        assert_eq!(
+            take(&events),
+            [
+                ChangeEvent::AttributeChanged {
+                    element: 1,
+                    name: "with:mod".into()
+                },
+                ChangeEvent::AttributeChanged {
+                    element: 1,
+                    name: "k".into()
+                },
+                ChangeEvent::TextChanged { text: 0 },
+                ChangeEvent::Moved {
+                    node: EntityRef::Element(3),
+                    parent: 0,
+                    from: 1,
+                    to: 0
+                }
+            ]
+        );
+
+        doc.insert_fragment(
+            3,
+            PlacePosition::End,
+            Fragment::Element {
+                prefix: "".into(),
+                local: "d".into(),
+                attributes: Default::default(),
+                children: vec![Fragment::Text("inner".into())],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            take(&events),
+            [
+                ChangeEvent::Inserted {
+                    node: EntityRef::Element(4),
+                    parent: 3,
+                    index: 0
+                },
+                ChangeEvent::Inserted {
+                    node: EntityRef::Text(1),
+                    parent: 4,
+                    index: 0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn rollback_events() {
+        let (mut doc, events) = observed();
+
+        let mut tx = doc.transaction();
+        tx.drop(EntityRef::Element(1)).unwrap();
+        tx.rollback();
+
+        let dropped = ChangeEvent::Dropped {
+            node: EntityRef::Element(1),
+            parent: 0,
+            index: 0,
+        };
+        let restored = ChangeEvent::Inserted {
+            node: EntityRef::Element(1),
+            parent: 0,
+            index: 0,
+        };
+        assert_eq!(take(&events), [dropped, restored]);
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let mut doc = Document::new(SRC).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let id = doc.subscribe(move |e| sink.lock().unwrap().push(e.clone()));
+
+        let mut cloned = doc.clone();
+        cloned.drop(EntityRef::Element(2)).unwrap();
+        assert!(take(&events).is_empty());
+
+        assert!(doc.unsubscribe(id));
+        assert!(!doc.unsubscribe(id));
+        doc.drop(EntityRef::Element(2)).unwrap();
+        assert!(take(&events).is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod test {
+    use trax_document::{Document, Interner, OwnedDocument};
+
+    fn owned_todo() -> OwnedDocument {
+        let src = include_str!("../testfiles/todo.trax").to_string();
+        let doc = Document::new(&src).unwrap().into_owned();
+        drop(src);
+        doc
+    }
+
+    #[test]
+    fn owned_outlives_source() {
+        assert_eq!(
+            owned_todo().into_string(),
+            include_str!("../testfiles/todo.trax")
+        );
+    }
+
+    #[test]
+    fn owned_is_send() {
+        let doc = owned_todo();
+        let rendered = std::thread::spawn(move || doc.into_string())
+            .join()
+            .unwrap();
+        assert_eq!(rendered, include_str!("../testfiles/todo.trax"));
+    }
+
+    #[test]
+    fn owned_equals_borrowed() {
+        let src = include_str!("../testfiles/todo.trax");
+        let borrowed = Document::new(src).unwrap();
+        assert_eq!(borrowed.clone().into_owned(), borrowed);
+    }
+
+    #[test]
+    fn names_are_interned() {
+        let mut interner = Interner::new();
+        let src = "<document><Todo a=\"1\" /><Todo a=\"2\" /><Todo b /></document>";
+
+        Document::new(src).unwrap().into_owned_with(&mut interner);
+        // "", "document", "Todo", "a", "b"
+        assert_eq!(interner.len(), 5);
+
+        Document::new(src).unwrap().into_owned_with(&mut interner);
+        assert_eq!(interner.len(), 5);
+    }
+}
@@ -33,7 +33,7 @@ macro_rules! range {
 
 #[cfg(test)]
 mod test {
-    use trax_document::{Document, DocumentParseError};
+    use trax_document::{Document, DocumentParseError, EntityRef, Fragment, PlacePosition};
     use trax_parser::{TextPos, TextRange};
 
     parse_document_err!(err_empty_document, "", EmptyDocument);
@@ -41,19 +41,19 @@ mod test {
     parse_document_err!(
         err_invalid_root_element_1,
         "<doxument> </doxument>",
-        InvalidRootElement(range!(1, 1..10))
+        InvalidRootElement(range!(1, 1..10), 0..9)
     );
 
     parse_document_err!(
         err_invalid_root_element_2,
         "<prefix:document> </prefix:document>",
-        InvalidRootElement(range!(1, 1..17))
+        InvalidRootElement(range!(1, 1..17), 0..16)
     );
 
     parse_document_err!(
         err_invalid_root_element_3,
         "/* comment */",
-        InvalidRootElement(range!(1, 1..14))
+        InvalidRootElement(range!(1, 1..14), 0..13)
     );
 
     parse_document_err!(
@@ -62,10 +62,25 @@ mod test {
         InvalidTreeStructure {
             closed_elem: "one".into(),
             current_open_elem: "two".into(),
-            location: range!(1, 21..27)
+            location: range!(1, 21..27),
+            span: 20..26
         }
     );
 
+    #[test]
+    fn fragment_escaping() {
+        let src = r#"<document><a k='it&apos;s "x" &lt;b>' /></document>"#;
+        let mut doc = Document::new(src).unwrap();
+        doc.insert_text(1, PlacePosition::End, "x < y & z").unwrap();
+
+        let fragment = doc.fragment(&EntityRef::Element(1)).unwrap();
+        assert_eq!(
+            fragment.to_string(),
+            r#"<a k="it's &quot;x&quot; &lt;b>">x &lt; y &amp; z</a>"#
+        );
+        assert_eq!(Fragment::parse(&fragment.to_string()), Ok(vec![fragment]));
+    }
+
     #[test]
     fn can_reproduce_input() {
         let src = include_str!("../testfiles/todo.trax");
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{
+        Attribute, Document, Indent, PlacePosition, Quote, RenderOptions, SelfClosing,
+    };
+
+    const SRC: &str = r#"<document><one key="value" with:modifier>text</one><two /></document>"#;
+
+    fn render(src: &str, options: RenderOptions) -> String {
+        Document::new(src).unwrap().render(&options)
+    }
+
+    #[test]
+    fn default_matches_into_string() {
+        let doc = Document::new(SRC).unwrap();
+        assert_eq!(doc.render(&RenderOptions::default()), doc.into_string());
+        assert_eq!(doc.to_string(), doc.into_string());
+    }
+
+    #[test]
+    fn write_to() {
+        let doc = Document::new(SRC).unwrap();
+        let mut out = Vec::new();
+        doc.write_to(&mut out, &RenderOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), doc.into_string());
+    }
+
+    #[test]
+    fn spaces() {
+        let options = RenderOptions {
+            indent: Indent::Spaces(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(SRC, options),
+            "<document>\n  <one key=\"value\" with:modifier>\n    text\n  </one>\n  <two />\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let options = RenderOptions {
+            compact: true,
+            self_closing: SelfClosing::Compact,
+            ..Default::default()
+        };
+        assert_eq!(
+            render(SRC, options),
+            "<document><one key=\"value\" with:modifier>text</one><two/></document>"
+        );
+    }
+
+    #[test]
+    fn never_self_close() {
+        let options = RenderOptions {
+            compact: true,
+            self_closing: SelfClosing::Never,
+            ..Default::default()
+        };
+        assert_eq!(
+            render("<document><two /></document>", options),
+            "<document><two></two></document>"
+        );
+    }
+
+    #[test]
+    fn quotes() {
+        let src = r#"<document><a one="x" two='say "hi"' three="it's" /></document>"#;
+
+        assert_eq!(
+            render(src, RenderOptions::default()),
+            "<document>\n\t<a one=\"x\" two='say \"hi\"' three=\"it's\" />\n</document>\n"
+        );
+
+        let options = RenderOptions {
+            quote: Quote::Single,
+            ..Default::default()
+        };
+        assert_eq!(
+            render(src, options),
+            "<document>\n\t<a one='x' two='say \"hi\"' three=\"it's\" />\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let mut doc = Document::new("<document><a /></document>").unwrap();
+        doc.insert_text(0, trax_document::PlacePosition::End, "1 < 2")
+            .unwrap();
+        doc.insert(
+            1,
+            trax_document::PlacePosition::End,
+            "",
+            "b",
+            [trax_document::Attribute::new(
+                "",
+                "k",
+                Some(r#"'both' "quotes""#),
+            )],
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<a>\n\t\t<b k=\"'both' &quot;quotes&quot;\" />\n\t</a>\n\t1 &lt; 2\n</document>\n"
+        );
+
+        let options = RenderOptions {
+            escape: false,
+            compact: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            doc.render(&options),
+            "<document><a><b k=\"'both' \"quotes\"\" /></a>1 < 2</document>"
+        );
+    }
+
+    #[test]
+    fn escaping_round_trip() {
+        let text = "x < y && y > z";
+        let value = r#"<a href='&amp;'> "quoted""#;
+
+        let mut doc = Document::new("<document />").unwrap();
+        doc.insert_text(0, PlacePosition::End, text).unwrap();
+        doc.insert(
+            0,
+            PlacePosition::End,
+            "",
+            "a",
+            [Attribute::new("", "k", Some(value))],
+        )
+        .unwrap();
+
+        let rendered = doc.into_string();
+        assert_eq!(
+            rendered,
+            "<document>\n\tx &lt; y &amp;&amp; y > z\n\t<a k=\"&lt;a href='&amp;amp;'> &quot;quoted&quot;\" />\n</document>\n"
+        );
+        assert_eq!(Document::new(&rendered).unwrap(), doc);
+    }
+
+    #[test]
+    fn decode_references() {
+        let doc = Document::new(
+            r#"<document><a k="&#x41;&#66;&unknown; &amp" />&lt;&#160;&gt;</document>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<a k=\"AB&amp;unknown; &amp;amp\" />\n\t&lt;\u{a0}>\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn wrap_attributes() {
+        let src = r#"<document><Tooltip direction="right" visible:hover pin:bottom="parent:top">Created</Tooltip><a b="c" /></document>"#;
+        let options = RenderOptions {
+            max_width: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(src, options),
+            "<document>\n\t<Tooltip\n\t\tdirection=\"right\"\n\t\tvisible:hover\n\t\tpin:bottom=\"parent:top\">\n\t\tCreated\n\t</Tooltip>\n\t<a b=\"c\" />\n</document>\n"
+        );
+    }
+}
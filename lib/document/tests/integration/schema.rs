@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{
+        DiagnosticCode, Document, ElementSchema, PropertySchema, PropertyType, Schema, SchemaError,
+        Severity, Type, Value,
+    };
+
+    const COMPONENTS: &str = include_str!("../../../../doc/components.trax");
+
+    const SCHEMA: &str = r##"<document>
+    <element name="Todo" children="Card #text">
+        <required name="title" type="str">The title of the todo</required>
+        <optional name="done" type="bool" />
+        <optional name="priority" type="int" />
+        <optional name="weight" type="float" />
+        <optional name="days" type="range" />
+        <optional name="link" type="url" />
+        <optional name="kind" type="element" />
+    </element>
+    <element name="with:prefix" children="bind:*" />
+</document>"##;
+
+    fn codes(schema: &str, src: &str) -> Vec<DiagnosticCode> {
+        let schema = Schema::parse(schema).unwrap();
+        let doc = Document::new(src).unwrap();
+        doc.validate_with(&schema).iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn load() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        let todo = schema.get("", "Todo").unwrap();
+
+        assert_eq!(
+            todo.property("", "title"),
+            Some(&PropertySchema {
+                prefix: "".into(),
+                local: "title".into(),
+                ty: PropertyType::Str,
+                required: true,
+                description: "The title of the todo".into(),
+            })
+        );
+        assert_eq!(todo.children, Some(vec!["Card".into(), "#text".into()]));
+        assert_eq!(schema.get("with", "prefix").unwrap().properties, []);
+        assert_eq!(schema.get("", "Card"), None);
+    }
+
+    #[test]
+    fn components() {
+        let schema = Schema::parse(COMPONENTS).unwrap();
+        for src in [
+            include_str!("../testfiles/todo.trax"),
+            include_str!("../../../../doc/calculator.trax"),
+        ] {
+            assert_eq!(Document::new(src).unwrap().validate_with(&schema), []);
+        }
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(
+            codes(
+                SCHEMA,
+                r#"<document><Todo title="a" done priority="-3" weight="0.5" days="1..10" link="todo.trax/Frame" kind="bind:done" /></document>"#
+            ),
+            []
+        );
+
+        assert_eq!(
+            codes(
+                SCHEMA,
+                r#"<document><Todo title done="yes" priority="1.5" weight="x" days="1-10" link="a b" kind="1a" /></document>"#
+            ),
+            [DiagnosticCode::TypeMismatch; 7]
+        );
+
+        // values are accepted exactly when they can be read
+        for (property, ty, value) in [
+            (PropertyType::Range, Type::Range, " 1 .. 10 "),
+            (PropertyType::Float, Type::Float, "inf"),
+            (PropertyType::Url, Type::Url, " todo.trax "),
+            (PropertyType::Element, Type::Modifier, "with:prefix"),
+        ] {
+            assert_eq!(
+                property.accepts(Some(value)),
+                Value::parse(ty, value).is_ok(),
+                "{property} {value:?}"
+            );
+        }
+        assert!(PropertyType::Range.accepts(Some("1 .. 10")));
+    }
+
+    #[test]
+    fn properties() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        let src = r#"<document><Todo color="red" pin:bottom="parent:top" /><Todo asEval:title="./title" /></document>"#;
+        let diagnostics = Document::new(src).unwrap().validate_with(&schema);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::MissingProperty);
+        assert_eq!(
+            &src[diagnostics[0].span.clone().unwrap()],
+            r#"<Todo color="red" pin:bottom="parent:top" />"#
+        );
+        assert_eq!(diagnostics[1].code, DiagnosticCode::UnknownProperty);
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(&src[diagnostics[1].span.clone().unwrap()], r#"color="red""#);
+    }
+
+    #[test]
+    fn children() {
+        assert_eq!(
+            codes(
+                SCHEMA,
+                "<document><Todo title=\"a\">text<Card />/* note */<Button /></Todo><with:prefix><bind:x /><read:x /></with:prefix></document>"
+            ),
+            [DiagnosticCode::InvalidChild, DiagnosticCode::InvalidChild]
+        );
+    }
+
+    #[test]
+    fn built() {
+        let mut schema = Schema::new();
+        schema.insert(ElementSchema {
+            local: "Button".into(),
+            children: Some(vec![]),
+            ..Default::default()
+        });
+
+        let doc = Document::new("<document><Button>click</Button></document>").unwrap();
+        assert_eq!(
+            doc.validate_with(&schema)[0].message,
+            "text isn't allowed in `Button`"
+        );
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        assert_eq!(
+            Schema::parse("<document><elem /></document>"),
+            Err(SchemaError::UnexpectedElement("elem".into()))
+        );
+        assert_eq!(
+            Schema::parse("<document><element /></document>"),
+            Err(SchemaError::MissingProperty("element".into(), "name"))
+        );
+        assert_eq!(
+            Schema::parse(
+                r#"<document><element name="a"><optional name="b" type="bytes" /></element></document>"#
+            ),
+            Err(SchemaError::UnknownType("b".into(), "bytes".into()))
+        );
+        assert_eq!(
+            Schema::parse(r#"<document><element name="a" /><element name="a" /></document>"#),
+            Err(SchemaError::DuplicateElement("a".into()))
+        );
+    }
+}
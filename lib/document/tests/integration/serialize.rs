@@ -0,0 +1,85 @@
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use trax_document::{Attribute, Document, Fragment};
+
+    const SRC: &str =
+        r#"<document><Todo title="Do Laundry" empty="" done>text</Todo><with:prefix /></document>"#;
+
+    fn tree() -> serde_json::Value {
+        json!({
+            "local": "document",
+            "children": [
+                {
+                    "local": "Todo",
+                    "attributes": [
+                        { "local": "title", "value": "Do Laundry" },
+                        { "local": "empty", "value": "" },
+                        { "local": "done" }
+                    ],
+                    "children": ["text"]
+                },
+                { "prefix": "with", "local": "prefix" }
+            ]
+        })
+    }
+
+    #[test]
+    fn serialize_document() {
+        let doc = Document::new(SRC).unwrap();
+        assert_eq!(serde_json::to_value(&doc).unwrap(), tree());
+    }
+
+    #[test]
+    fn deserialize_document() {
+        let doc: Document = serde_json::from_value(tree()).unwrap();
+        assert_eq!(doc.into_string(), Document::new(SRC).unwrap().into_string());
+    }
+
+    #[test]
+    fn round_trip_todo() {
+        let doc = Document::new(include_str!("../testfiles/todo.trax")).unwrap();
+        let json = serde_json::to_string(&doc).unwrap();
+        let parsed: Document = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.into_string(), doc.into_string());
+    }
+
+    #[test]
+    fn invalid_root() {
+        let err = serde_json::from_value::<Document>(json!({ "local": "doxument" }));
+        assert!(err.is_err());
+        let err = serde_json::from_value::<Document>(json!("text"));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn attributes() {
+        let modifier: Attribute = serde_json::from_value(json!({ "local": "done" })).unwrap();
+        assert_eq!(modifier, Attribute::new("", "done", None::<&str>));
+
+        let empty: Attribute =
+            serde_json::from_value(json!({ "local": "done", "value": "" })).unwrap();
+        assert_eq!(empty, Attribute::new("", "done", Some("")));
+    }
+
+    #[test]
+    fn fragments() {
+        let fragment: Fragment =
+            serde_json::from_value(json!({ "local": "a", "children": ["x"] })).unwrap();
+        assert_eq!(fragment.to_string(), "<a>x</a>");
+    }
+
+    #[test]
+    fn comments() {
+        let doc = Document::new("<document>/* note */text</document>").unwrap();
+        let json = serde_json::to_value(&doc).unwrap();
+        assert_eq!(
+            json,
+            json!({ "local": "document", "children": [{ "comment": " note " }, "text"] })
+        );
+
+        let parsed: Document = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.into_string(), doc.into_string());
+    }
+}
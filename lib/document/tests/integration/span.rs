@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Attribute, Document, EntityRef, PlacePosition};
+
+    const SRC: &str = r#"<document>
+    <Todo title="Do Laundry" done>
+        laundry
+        /* note */
+    </Todo>
+    <with:prefix />
+</document>"#;
+
+    fn source_of(doc: &Document, entity_ref: EntityRef) -> &'static str {
+        &SRC[doc.span_of(&entity_ref).unwrap()]
+    }
+
+    #[test]
+    fn entities() {
+        let doc = Document::new(SRC).unwrap();
+
+        assert_eq!(source_of(&doc, EntityRef::Element(0)), SRC);
+        assert_eq!(
+            source_of(&doc, EntityRef::Element(1)),
+            "<Todo title=\"Do Laundry\" done>\n        laundry\n        /* note */\n    </Todo>"
+        );
+        assert_eq!(source_of(&doc, EntityRef::Element(2)), "<with:prefix />");
+        assert_eq!(source_of(&doc, EntityRef::Text(0)), "laundry");
+        assert_eq!(source_of(&doc, EntityRef::Comment(0)), "/* note */");
+    }
+
+    #[test]
+    fn attributes() {
+        let doc = Document::new(SRC).unwrap();
+        let span = doc.element_span(1).unwrap();
+
+        assert_eq!(
+            &SRC[span.start_tag.clone()],
+            "<Todo title=\"Do Laundry\" done>"
+        );
+
+        let title = span.attribute("", "title").unwrap();
+        assert_eq!(&SRC[title.full.clone()], "title=\"Do Laundry\"");
+        assert_eq!(&SRC[title.key.clone()], "title");
+        assert_eq!(&SRC[title.value.clone().unwrap()], "Do Laundry");
+
+        let done = span.attribute("", "done").unwrap();
+        assert_eq!(&SRC[done.key.clone()], "done");
+        assert_eq!(done.value, None);
+    }
+
+    #[test]
+    fn node_at() {
+        let doc = Document::new(SRC).unwrap();
+
+        assert_eq!(
+            doc.node_at(SRC.find("Laundry").unwrap()),
+            Some(EntityRef::Element(1))
+        );
+        assert_eq!(
+            doc.node_at(SRC.find("laundry").unwrap()),
+            Some(EntityRef::Text(0))
+        );
+        assert_eq!(
+            doc.node_at(SRC.find("note").unwrap()),
+            Some(EntityRef::Comment(0))
+        );
+        assert_eq!(
+            doc.node_at(SRC.find("prefix").unwrap()),
+            Some(EntityRef::Element(2))
+        );
+        assert_eq!(doc.node_at(1), Some(EntityRef::Element(0)));
+        assert_eq!(doc.node_at(SRC.len()), None);
+    }
+
+    #[test]
+    fn edits() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let id = doc.insert(0, PlacePosition::Start, "", "new", []).unwrap();
+        assert_eq!(doc.span_of(&EntityRef::Element(id)), None);
+
+        doc.set_attribute(1, Attribute::new("", "title", Some("Do Dishes")))
+            .unwrap();
+        assert_eq!(doc.element_span(1).unwrap().attribute("", "title"), None);
+
+        doc.set_text(0, "dishes").unwrap();
+        assert_eq!(doc.span_of(&EntityRef::Text(0)), None);
+
+        doc.drop(EntityRef::Element(2)).unwrap();
+        assert_eq!(doc.span_of(&EntityRef::Element(2)), None);
+
+        doc.move_to(EntityRef::Element(1), 1).unwrap();
+        assert_eq!(source_of(&doc, EntityRef::Comment(0)), "/* note */");
+    }
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{
+        Attribute, Document, EntityRef, Fragment, InsertElementError, ModifyEntityError,
+        PlacePosition::*, Splice,
+    };
+
+    use crate::render;
+
+    const SRC: &str =
+        r#"<document><List><a /><b /></List><Todo title="Laundry" done desc="soon" /></document>"#;
+
+    #[test]
+    fn splice() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let splice = doc
+            .splice(1, Some(EndIndex(1)), Fragment::parse("<c />text").unwrap())
+            .unwrap();
+        assert_eq!(
+            splice,
+            Splice {
+                inserted: vec![EntityRef::Element(5), EntityRef::Text(0)],
+                dropped: Vec::new(),
+            }
+        );
+        assert_eq!(render(&doc, 1), "<List><a /><c />text<b /></List>");
+
+        let splice = doc
+            .splice(1, Some(Replace(0)), Fragment::parse("<d />").unwrap())
+            .unwrap();
+        assert_eq!(splice.dropped, [EntityRef::Element(2)]);
+        assert_eq!(render(&doc, 1), "<List><d /><c />text<b /></List>");
+
+        // deleting a child by its index
+        let splice = doc.splice(1, Some(Replace(2)), Vec::new()).unwrap();
+        assert_eq!(splice.dropped, [EntityRef::Text(0)]);
+        assert_eq!(render(&doc, 1), "<List><d /><c /><b /></List>");
+
+        // nothing to insert at the start
+        assert_eq!(
+            doc.splice(1, Some(Start), Vec::new()),
+            Ok(Splice::default())
+        );
+
+        assert_eq!(
+            doc.splice(1, Some(StartIndex(4)), Fragment::parse("<e />").unwrap()),
+            Err(InsertElementError::PositionOutOfRange(
+                StartIndex(4),
+                EntityRef::Element(1),
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn replace_target() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let splice = doc
+            .splice(3, None, Fragment::parse("<x /><y />").unwrap())
+            .unwrap();
+        assert_eq!(splice.dropped, [EntityRef::Element(3)]);
+        assert_eq!(render(&doc, 1), "<List><a /><x /><y /></List>");
+
+        doc.splice(1, None, Vec::new()).unwrap();
+        assert_eq!(
+            render(&doc, 0),
+            r#"<document><Todo title="Laundry" done desc="soon" /></document>"#
+        );
+
+        assert_eq!(
+            doc.splice(100, None, Vec::new()),
+            Err(InsertElementError::NotFound(EntityRef::Element(100)))
+        );
+    }
+
+    #[test]
+    fn replace_root() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let splice = doc
+            .splice(
+                0,
+                None,
+                Fragment::parse(r#"<document lang="en"><H1>welcome</H1></document>"#).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            splice.dropped,
+            [EntityRef::Element(1), EntityRef::Element(4)]
+        );
+        assert_eq!(
+            render(&doc, 0),
+            r#"<document lang="en"><H1>welcome</H1></document>"#
+        );
+
+        for contents in ["", "<H1 />", "<document /><document />"] {
+            assert!(matches!(
+                doc.splice(0, None, Fragment::parse(contents).unwrap()),
+                Err(InsertElementError::DropEntityError(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn splice_property() {
+        let mut doc = Document::new(SRC).unwrap();
+        let mut splice = |key, value: Option<&'static str>, position| {
+            doc.splice_property(4, key, value.map(Into::into), position)
+                .map(|_| render(&doc, 4))
+        };
+
+        // set, or insert at a position
+        assert_eq!(
+            splice(Some("title"), Some("Dishes"), None),
+            Ok(r#"<Todo title="Dishes" done desc="soon" />"#.to_string())
+        );
+        assert_eq!(
+            splice(Some("late"), None, Some(Start)),
+            Ok(r#"<Todo late title="Dishes" done desc="soon" />"#.to_string())
+        );
+        assert_eq!(
+            splice(Some("late"), None, Some(EndIndex(1))),
+            Ok(r#"<Todo title="Dishes" done late desc="soon" />"#.to_string())
+        );
+
+        // replace, or rename, by index
+        assert_eq!(
+            splice(Some("created"), Some("today"), Some(Replace(2))),
+            Ok(r#"<Todo title="Dishes" done created="today" desc="soon" />"#.to_string())
+        );
+        assert_eq!(
+            splice(Some("name"), None, Some(Replace(0))),
+            Ok(r#"<Todo name="Dishes" done created="today" desc="soon" />"#.to_string())
+        );
+
+        // replace the value, or delete, by position
+        assert_eq!(
+            splice(None, Some("later"), Some(End)),
+            Ok(r#"<Todo name="Dishes" done created="today" desc="later" />"#.to_string())
+        );
+        assert_eq!(
+            splice(None, None, Some(StartIndex(1))),
+            Ok(r#"<Todo name="Dishes" created="today" desc="later" />"#.to_string())
+        );
+
+        assert_eq!(
+            splice(None, None, Some(Replace(3))),
+            Err(ModifyEntityError::IndexOutOfRange(
+                EntityRef::Element(4),
+                3,
+                3
+            ))
+        );
+        assert_eq!(
+            splice(None, Some("x"), None),
+            Err(ModifyEntityError::MissingPosition(EntityRef::Element(4)))
+        );
+
+        // delete everything
+        assert_eq!(splice(None, None, None), Ok("<Todo />".to_string()));
+    }
+
+    #[test]
+    fn insert_attribute() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        assert_eq!(
+            doc.insert_attribute(4, StartIndex(1), Attribute::new("", "desc", Some("now"))),
+            Ok(1)
+        );
+        assert_eq!(
+            render(&doc, 4),
+            r#"<Todo title="Laundry" desc="now" done />"#
+        );
+
+        // the attribute with the same name is removed when replacing another
+        assert_eq!(
+            doc.insert_attribute(4, Replace(2), Attribute::new("", "title", None::<&str>)),
+            Ok(1)
+        );
+        assert_eq!(render(&doc, 4), r#"<Todo desc="now" title />"#);
+
+        assert_eq!(
+            doc.insert_attribute(4, StartIndex(3), Attribute::new("", "x", None::<&str>)),
+            Err(ModifyEntityError::IndexOutOfRange(
+                EntityRef::Element(4),
+                3,
+                2
+            ))
+        );
+        assert_eq!(render(&doc, 4), r#"<Todo desc="now" title />"#);
+    }
+
+    #[test]
+    fn transaction() {
+        let mut doc = Document::new(SRC).unwrap();
+        let before = render(&doc, 0);
+
+        let mut transaction = doc.transaction();
+        transaction
+            .splice(1, Some(Replace(0)), Fragment::parse("<c />text").unwrap())
+            .unwrap();
+        transaction.splice(4, None, Vec::new()).unwrap();
+        transaction
+            .splice_property(1, Some("done"), None, Some(Start))
+            .unwrap();
+        transaction
+            .splice_property(1, Some("id"), Some("1".into()), Some(Replace(0)))
+            .unwrap();
+        let changes = transaction.commit();
+        assert_eq!(
+            render(&doc, 0),
+            r#"<document><List id="1"><c />text<b /></List></document>"#
+        );
+
+        changes.revert(&mut doc).unwrap();
+        assert_eq!(render(&doc, 0), before);
+    }
+}
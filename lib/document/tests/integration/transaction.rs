@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{
+        diff, Attribute, Document, DropEntityError, EntityRef, History, PatchError, PlacePosition,
+    };
+
+    const TODO: &str = include_str!("../testfiles/todo.trax");
+    const SRC: &str = "<document><a k=\"v\">text</a><b /><c /></document>";
+
+    fn apply_diff(old: &str, new: &str) {
+        let mut doc = Document::new(old).unwrap();
+        let new = Document::new(new).unwrap();
+        doc.apply_all(diff(&doc, &new)).unwrap();
+        assert_eq!(doc.into_string(), new.into_string());
+    }
+
+    #[test]
+    fn apply_diffs() {
+        apply_diff(
+            SRC,
+            "<document><c /><a k=\"w\" x>changed</a><new>x</new></document>",
+        );
+        apply_diff(SRC, "<document></document>");
+        apply_diff("<document></document>", SRC);
+        apply_diff(TODO, include_str!("../testfiles/drop/todo.1.trax"));
+        apply_diff(include_str!("../testfiles/drop/todo.1.trax"), TODO);
+    }
+
+    #[test]
+    fn rollback_on_error() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let result = (|| -> Result<(), PatchError> {
+            let mut tx = doc.transaction();
+            tx.insert(0, PlacePosition::Start, "", "new", [])?;
+            tx.set_attribute(1, Attribute::new("", "k", Some("w")))?;
+            tx.set_text(0, "changed")?;
+            tx.drop(EntityRef::Element(2))?;
+            tx.drop(EntityRef::Element(2))?;
+            tx.commit();
+            Ok(())
+        })();
+
+        assert_eq!(
+            result,
+            Err(PatchError::Drop(DropEntityError::NotFound(
+                EntityRef::Element(2)
+            )))
+        );
+        assert_eq!(doc.into_string(), Document::new(SRC).unwrap().into_string());
+    }
+
+    #[test]
+    fn revert_replace() {
+        let mut doc = Document::new(SRC).unwrap();
+
+        let mut tx = doc.transaction();
+        tx.insert(0, PlacePosition::Replace(0), "", "new", [])
+            .unwrap();
+        assert_eq!(
+            tx.into_string(),
+            "<document>\n\t<new />\n\t<b />\n\t<c />\n</document>\n"
+        );
+        let changes = tx.commit();
+
+        let redo = changes.revert(&mut doc).unwrap();
+        assert_eq!(doc.into_string(), Document::new(SRC).unwrap().into_string());
+
+        redo.revert(&mut doc).unwrap();
+        assert_eq!(
+            doc.into_string(),
+            "<document>\n\t<new />\n\t<b />\n\t<c />\n</document>\n"
+        );
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut doc = Document::new(SRC).unwrap();
+        let mut history = History::new();
+        let original = doc.into_string();
+
+        let mut tx = doc.transaction();
+        tx.remove_attribute(1, "", "k").unwrap();
+        tx.move_to(EntityRef::Element(3), 0).unwrap();
+        history.push(tx.commit());
+        let first = doc.into_string();
+
+        let mut tx = doc.transaction();
+        tx.insert_text(2, PlacePosition::End, "in b").unwrap();
+        history.push(tx.commit());
+        let second = doc.into_string();
+
+        assert_eq!(history.undo(&mut doc), Ok(true));
+        assert_eq!(doc.into_string(), first);
+        assert_eq!(history.undo(&mut doc), Ok(true));
+        assert_eq!(doc.into_string(), original);
+        assert_eq!(history.undo(&mut doc), Ok(false));
+
+        assert_eq!(history.redo(&mut doc), Ok(true));
+        assert_eq!(doc.into_string(), first);
+        assert_eq!(history.redo(&mut doc), Ok(true));
+        assert_eq!(doc.into_string(), second);
+        assert_eq!(history.redo(&mut doc), Ok(false));
+    }
+
+    #[test]
+    fn failed_undo_keeps_history() {
+        let mut doc = Document::new(SRC).unwrap();
+        let mut history = History::new();
+
+        let mut tx = doc.transaction();
+        tx.insert(2, PlacePosition::End, "", "new", []).unwrap();
+        history.push(tx.commit());
+
+        // the parent of the inserted element is dropped outside of the history
+        doc.drop(EntityRef::Element(2)).unwrap();
+        assert!(history.undo(&mut doc).is_err());
+        assert!(history.can_undo());
+    }
+}
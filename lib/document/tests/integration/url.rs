@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Query, Segment, Url, UrlError};
+
+    fn element<'u>(local: &'u str, index: Option<usize>) -> Segment<'u> {
+        Segment::Element {
+            prefix: "",
+            local,
+            index,
+            queries: vec![],
+        }
+    }
+
+    #[test]
+    fn documents() {
+        let url = Url::parse("todo.trax#0/Frame/Body/Todo#1").unwrap();
+        assert_eq!(url.document, Some("todo.trax"));
+        assert_eq!(url.connection, Some(0));
+        assert_eq!(
+            url.segments,
+            vec![
+                element("Frame", None),
+                element("Body", None),
+                element("Todo", Some(1))
+            ]
+        );
+
+        let url = Url::parse("trax.tcp:qoogle.com/search.trax").unwrap();
+        assert_eq!(url.document, Some("trax.tcp:qoogle.com/search.trax"));
+        assert!(url.segments.is_empty());
+
+        let url = Url::parse("trax.tcp:example.com#0").unwrap();
+        assert_eq!(url.document, Some("trax.tcp:example.com"));
+        assert_eq!(url.connection, Some(0));
+    }
+
+    #[test]
+    fn spec_examples() {
+        let url = Url::parse("trax.quic:website.com").unwrap();
+        assert_eq!(url.document, Some("trax.quic:website.com"));
+        assert!(url.segments.is_empty());
+
+        let url = Url::parse("hello.png").unwrap();
+        assert_eq!(url.document, Some("hello.png"));
+        assert!(url.segments.is_empty());
+        assert_eq!(url.property, None);
+
+        let url = Url::parse("todo.trax/Frame/Body/Todo#1/H1").unwrap();
+        assert_eq!(url.document, Some("todo.trax"));
+        assert_eq!(url.connection, None);
+        assert_eq!(
+            url.segments,
+            vec![
+                element("Frame", None),
+                element("Body", None),
+                element("Todo", Some(1)),
+                element("H1", None)
+            ]
+        );
+
+        let url = Url::parse(
+            "trax.tcp:airports.info/db.trax/Airport?iata=DAB/Runway?Obstruction.obstacle=road",
+        )
+        .unwrap();
+        assert_eq!(url.document, Some("trax.tcp:airports.info/db.trax"));
+        assert_eq!(url.segments.len(), 2);
+        assert_eq!(url.property, None);
+    }
+
+    #[test]
+    fn files() {
+        let url = Url::parse("hello.png#0").unwrap();
+        assert_eq!(url.document, Some("hello.png"));
+        assert_eq!(url.connection, Some(0));
+
+        let url = Url::parse("./Body.title").unwrap();
+        assert!(url.is_local());
+        assert_eq!(url.property, Some("title"));
+
+        // only known file extensions make a lone name a file
+        let url = Url::parse("Body.title").unwrap();
+        assert!(url.is_local());
+        assert_eq!(url.segments, vec![element("Body", None)]);
+        assert_eq!(url.property, Some("title"));
+
+        let url = Url::parse("Photo.PNG").unwrap();
+        assert_eq!(url.document, Some("Photo.PNG"));
+
+        for local in ["1.5", "../.var", ".."] {
+            assert!(Url::parse(local).unwrap().is_local(), "{local}");
+        }
+    }
+
+    #[test]
+    fn paths() {
+        let url = Url::parse("document/Card/Blank#0/for.var").unwrap();
+        assert!(url.is_local());
+        assert_eq!(url.segments.len(), 4);
+        assert_eq!(url.property, Some("var"));
+
+        let url = Url::parse("../.var").unwrap();
+        assert_eq!(url.segments, vec![Segment::Parent, Segment::Current]);
+        assert_eq!(url.property, Some("var"));
+
+        let url = Url::parse("./action:insert").unwrap();
+        assert_eq!(
+            url.segments[1],
+            Segment::Element {
+                prefix: "action",
+                local: "insert",
+                index: None,
+                queries: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let url = Url::parse(
+            "trax.tcp:airports.info/db.trax/Airport?iata=DAB/Runway?Obstruction.obstacle=road",
+        )
+        .unwrap();
+        assert_eq!(url.document, Some("trax.tcp:airports.info/db.trax"));
+
+        let Segment::Element { queries, .. } = &url.segments[1] else {
+            panic!("expected an element");
+        };
+        assert_eq!(
+            queries,
+            &vec![Query {
+                child: Some("Obstruction"),
+                key: "obstacle",
+                value: Some("road")
+            }]
+        );
+
+        let url = Url::parse(r#"document.trax#0/Section?title="Giant Rat"&done"#).unwrap();
+        let Segment::Element { queries, .. } = &url.segments[0] else {
+            panic!("expected an element");
+        };
+        assert_eq!(queries[0].value, Some("Giant Rat"));
+        assert_eq!(queries[1].value, None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for src in [
+            "todo.trax#0/Frame/Body/Todo#1.title",
+            "../.var",
+            "./action:insert",
+            "document/Section?title=\"Giant Rat\"",
+        ] {
+            assert_eq!(Url::parse(src).unwrap().to_string(), src);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Url::parse(" "), Err(UrlError::Empty));
+        assert_eq!(
+            Url::parse("a//b"),
+            Err(UrlError::InvalidSegment(String::new()))
+        );
+        assert_eq!(
+            Url::parse("Todo#one"),
+            Err(UrlError::InvalidIndex("Todo#one".to_string()))
+        );
+        assert_eq!(
+            Url::parse("for.var/Text"),
+            Err(UrlError::MisplacedProperty("Text".to_string()))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Attribute, Document, Type, Value, ValueError};
+
+    const SRC: &str = r#"<document>
+    <for in="1..10" index="2" ratio="0.5" label="five" done url="todos.trax#0" />
+    <bad index="two" in="1-10" with:prefix />
+</document>"#;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Value::parse(Type::Int, " -3 "), Ok(Value::Int(-3)));
+        assert_eq!(Value::parse(Type::Float, "6"), Ok(Value::Float(6.0)));
+        assert_eq!(Value::parse(Type::Range, "1..10"), Ok(Value::Range(1..10)));
+        assert_eq!(Value::parse(Type::Bool, "true"), Ok(Value::Bool(true)));
+        assert_eq!(Value::parse(Type::Str, " a "), Ok(Value::from(" a ")));
+        assert_eq!(Value::parse(Type::Void, "void"), Ok(Value::Void));
+        assert_eq!(
+            Value::parse(Type::Attribute, r#"with:key="value""#),
+            Ok(Value::Attribute(Attribute::new(
+                "with",
+                "key",
+                Some("value")
+            )))
+        );
+        assert_eq!(
+            Value::parse(Type::Modifier, "done"),
+            Ok(Value::Modifier(Attribute::new("", "done", None::<&str>)))
+        );
+
+        let Ok(Value::Element(element)) = Value::parse(Type::Element, "<a key=\"value\">x</a>")
+        else {
+            panic!("expected an element");
+        };
+        assert_eq!(element.to_string(), "<a key=\"value\">x</a>");
+
+        assert!(Value::parse(Type::Float, "NaN").is_err());
+        assert!(Value::parse(Type::Url, "two words").is_err());
+        assert!(Value::parse(Type::Element, "<a /><b />").is_err());
+        assert!(Value::parse(Type::Attribute, "done").is_err());
+        assert_eq!(
+            Value::parse(Type::Int, "two"),
+            Err(ValueError::InvalidValue {
+                expected: Type::Int,
+                found: "two".to_string(),
+                location: None
+            })
+        );
+    }
+
+    #[test]
+    fn infer() {
+        assert_eq!(Value::infer("1"), Value::Int(1));
+        assert_eq!(Value::infer("1.5"), Value::Float(1.5));
+        assert_eq!(Value::infer("0..3"), Value::Range(0..3));
+        assert_eq!(Value::infer("inf"), Value::from("inf"));
+        assert_eq!(Value::infer("Do Laundry").ty(), Type::Str);
+    }
+
+    #[test]
+    fn format() {
+        for (ty, text) in [
+            (Type::Int, "-3"),
+            (Type::Float, "6.0"),
+            (Type::Float, "0.25"),
+            (Type::Range, "1..10"),
+            (Type::Bool, "false"),
+            (Type::Url, "todos.trax#0"),
+            (Type::Element, "<a key=\"value\" />"),
+            (Type::Attribute, "with:key=\"value\""),
+            (Type::Modifier, "done"),
+            (Type::Undefined, "undefined"),
+        ] {
+            let value = Value::parse(ty, text).unwrap();
+            assert_eq!(value.to_string(), text);
+            assert_eq!(value.ty(), ty);
+        }
+    }
+
+    #[test]
+    fn getters() {
+        let doc = Document::new(SRC).unwrap();
+        let element = doc.element(1).unwrap();
+
+        assert_eq!(element.local(), "for");
+        assert_eq!(element.get_range("in"), Ok(Some(1..10)));
+        assert_eq!(element.get_int("index"), Ok(Some(2)));
+        assert_eq!(element.get_float("index"), Ok(Some(2.0)));
+        assert_eq!(element.get_float("ratio"), Ok(Some(0.5)));
+        assert_eq!(element.get_str("label"), Ok(Some("five")));
+        assert_eq!(element.get_url("url"), Ok(Some("todos.trax#0".into())));
+        assert_eq!(element.get_bool("done"), Ok(true));
+        assert_eq!(element.get_bool("missing"), Ok(false));
+        assert_eq!(element.get_int("missing"), Ok(None));
+        assert_eq!(
+            element.get("done", Type::Any),
+            Ok(Some(Value::Modifier(Attribute::new(
+                "",
+                "done",
+                None::<&str>
+            ))))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_attribute() {
+        let doc = Document::new(SRC).unwrap();
+        let element = doc.element(2).unwrap();
+
+        let err = element.get_int("index").unwrap_err();
+        assert_eq!(err.to_string(), "expected int, found `two`");
+        assert_eq!(&SRC[err.location().unwrap().clone()], "two");
+
+        let err = element.get_range("in").unwrap_err();
+        assert_eq!(&SRC[err.location().unwrap().clone()], "1-10");
+
+        let err = element.get_str("with:prefix").unwrap_err();
+        assert_eq!(err.to_string(), "expected str, found a modifier");
+        assert_eq!(&SRC[err.location().unwrap().clone()], "with:prefix");
+    }
+
+    #[test]
+    fn changed_attributes_have_no_location() {
+        let mut doc = Document::new(SRC).unwrap();
+        doc.set_attribute(2, Attribute::new("", "index", Some("three")))
+            .unwrap();
+
+        let err = doc.element(2).unwrap().get_int("index").unwrap_err();
+        assert_eq!(err.location(), None);
+    }
+}
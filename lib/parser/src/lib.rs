@@ -292,6 +292,11 @@ impl<'a> Tokenizer<'a> {
             State::Elements => {
                 s.skip_spaces();
 
+                // Trailing whitespace after the last node of a fragment
+                if s.at_end() && self.fragment_parsing {
+                    return None;
+                }
+
                 // Use `match` only here, because only this section is performance-critical.
                 match s.curr_byte() {
                     Ok(b'<') => match s.next_byte() {
@@ -493,22 +498,21 @@ impl<'a> Tokenizer<'a> {
         let start = s.pos();
 
         let mut chars = s.chars();
-        loop {
-            if let Some(c) = chars.next() {
-                if !c.is_xml_char() {
-                    return Err(StreamError::NonXmlChar(c, s.gen_text_pos()));
-                } else if c == '/' {
-                    if let Some(next) = chars.next() {
-                        if next == '*' {
-                            break;
-                        }
+        // fragments can end with text
+        while let Some(c) = chars.next() {
+            if !c.is_xml_char() {
+                return Err(StreamError::NonXmlChar(c, s.gen_text_pos()));
+            } else if c == '/' {
+                if let Some(next) = chars.next() {
+                    if next == '*' {
+                        break;
                     }
-                    s.advance(c.len_utf8());
-                } else if c != '<' {
-                    s.advance(c.len_utf8());
-                } else {
-                    break;
                 }
+                s.advance(c.len_utf8());
+            } else if c != '<' {
+                s.advance(c.len_utf8());
+            } else {
+                break;
             }
         }
 
@@ -54,4 +54,20 @@ mod test {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn parse_fragment_trailing_text() {
+        let s = "<p/> text ";
+        let mut p = trax_parser::Tokenizer::from_fragment(s, 0..s.len());
+
+        p.next().unwrap().unwrap();
+        p.next().unwrap().unwrap();
+
+        match p.next().unwrap().unwrap() {
+            trax_parser::Token::Text { text } => assert_eq!(text.as_str(), "text"),
+            _ => panic!(),
+        }
+
+        assert!(p.next().is_none());
+    }
 }
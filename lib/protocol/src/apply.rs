@@ -0,0 +1,146 @@
+use std::borrow::Cow;
+
+use trax_document::{
+    Document, EntityRef, EvalContext, Exception, Interner, NodeId, Target, Transaction, Url,
+};
+
+use crate::Message;
+
+/// The entities changed by [`Message::apply`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Applied {
+    /// The entities which were inserted, in order.
+    pub inserted: Vec<EntityRef>,
+    /// The entities which were replaced or deleted.
+    pub dropped: Vec<EntityRef>,
+    /// The elements whose properties were changed.
+    pub modified: Vec<NodeId>,
+}
+
+impl<'a> Message<'a> {
+    /// Apply an `<insert>` or `<insertProp>` message to the document named `name`, like
+    /// `todo.trax`, which its target has to name. See [`Document::splice`] and
+    /// [`Document::splice_property`] for what the positions do. Nothing is changed if it fails.
+    ///
+    /// ```rust
+    /// use trax_document::{Document, EntityRef};
+    /// use trax_protocol::Message;
+    ///
+    /// let mut doc = Document::new("<document><Body><Todo /><Todo /></Body></document>").unwrap();
+    ///
+    /// let messages = Message::parse(r#"<insert target="todo.trax#0/Body/Todo#1" />"#).unwrap();
+    /// let applied = messages[0].clone().apply(&mut doc, "todo.trax").unwrap();
+    ///
+    /// assert_eq!(applied.dropped, [EntityRef::Element(3)]);
+    /// ```
+    pub fn apply<'d>(self, document: &mut Document<'d>, name: &str) -> Result<Applied, Exception>
+    where
+        'a: 'd,
+    {
+        let mut transaction = document.transaction();
+        let applied = self.apply_in(&mut transaction, name)?;
+        transaction.commit();
+        Ok(applied)
+    }
+
+    /// Apply an `<insert>` or `<insertProp>` message as part of a transaction, so that it can be
+    /// reverted along with the rest of it. See [`Message::apply`].
+    pub fn apply_in<'d>(
+        self,
+        transaction: &mut Transaction<'_, 'd>,
+        name: &str,
+    ) -> Result<Applied, Exception>
+    where
+        'a: 'd,
+    {
+        match self {
+            Message::Insert {
+                target,
+                position,
+                contents,
+            } => {
+                let target = resolve(transaction, name, &target)?;
+                let splice = transaction.splice(target, position, contents)?;
+                Ok(Applied {
+                    inserted: splice.inserted,
+                    dropped: splice.dropped,
+                    modified: Vec::new(),
+                })
+            }
+            Message::InsertProp {
+                target,
+                key,
+                value,
+                position,
+            } => {
+                let target = resolve(transaction, name, &target)?;
+                transaction.splice_property(target, key.as_deref(), value, position)?;
+                Ok(Applied {
+                    modified: vec![target],
+                    ..Applied::default()
+                })
+            }
+            message => Err(Exception::Runtime {
+                name: "UnexpectedMessage".to_string(),
+                location: None,
+                description: format!("`<{}>` can't be applied to a document", message.name()),
+            }),
+        }
+    }
+
+    /// Copy the message so that it doesn't borrow from the frame it was parsed from.
+    pub fn into_owned(self, interner: &mut Interner) -> Message<'static> {
+        let owned = |s: Cow<'a, str>| Cow::Owned(s.into_owned());
+
+        match self {
+            Message::Get { doc } => Message::Get {
+                doc: doc.map(owned),
+            },
+            Message::Redirect {
+                connection,
+                url,
+                cosmetic,
+            } => Message::Redirect {
+                connection: owned(connection),
+                url: owned(url),
+                cosmetic,
+            },
+            Message::Insert {
+                target,
+                position,
+                contents,
+            } => Message::Insert {
+                target: owned(target),
+                position,
+                contents: contents
+                    .into_iter()
+                    .map(|f| f.into_owned(interner))
+                    .collect(),
+            },
+            Message::InsertProp {
+                target,
+                key,
+                value,
+                position,
+            } => Message::InsertProp {
+                target: owned(target),
+                key: key.map(owned),
+                value: value.map(owned),
+                position,
+            },
+        }
+    }
+}
+
+// Resolves the target of a message to an element.
+fn resolve(document: &Document, name: &str, target: &str) -> Result<NodeId, Exception> {
+    let url = Url::parse(target)?;
+    match EvalContext::new(document).with_name(name).resolve(0, &url) {
+        Some(Target::Element(id)) => Ok(id),
+        _ => Err(Exception::Runtime {
+            name: "NotFound".to_string(),
+            location: None,
+            description: format!("`{target}` doesn't locate an element"),
+        }),
+    }
+}
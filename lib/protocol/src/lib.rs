@@ -0,0 +1,40 @@
+/*!
+TRAX message directives, which clients and servers exchange to load documents and keep them in
+sync.
+
+Clients apply the messages they receive with [`Message::apply`], while servers can leave
+tracking connections and their documents to a [`Server`] by implementing [`Application`].
+
+## Example
+
+```rust
+use trax_protocol::Message;
+
+let frame = r#"<insert target="todo.trax#0/Frame/Body" start>
+    <Todo title="Do Laundry" done />
+</insert>
+<insertProp target="todo.trax#0/Frame/Body/Todo#1" key="done" end />"#;
+
+let messages = Message::parse(frame).unwrap();
+assert_eq!(messages.len(), 2);
+
+println!("{}", Message::frame(&messages));
+```
+
+## Safety
+
+- The library must not panic. Any panic is considered a critical bug
+  and should be reported.
+- The library forbids unsafe code.
+*/
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+mod apply;
+mod message;
+mod session;
+
+pub use apply::Applied;
+pub use message::{Message, MessageParseError};
+pub use session::{Application, Connection, ConnectionId, Server};
@@ -0,0 +1,388 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::{self, Display, Write},
+};
+
+use thiserror::Error;
+use trax_document::{Attribute, DocumentParseError, Exception, Fragment, Name, PlacePosition};
+
+/// An error encountered when parsing messages.
+#[derive(Debug, PartialEq, Error)]
+pub enum MessageParseError {
+    /// The frame isn't valid markup.
+    #[error(transparent)]
+    Syntax(#[from] DocumentParseError),
+
+    /// An element in the frame isn't one of the message directives.
+    #[error("`<{0}>` isn't a message")]
+    UnknownMessage(String),
+
+    /// A message doesn't have a property it requires.
+    #[error("`<{message}>` requires the `{property}` property")]
+    MissingProperty {
+        /// The name of the message.
+        message: &'static str,
+        /// The name of the property.
+        property: &'static str,
+    },
+
+    /// A property of a message doesn't have a valid value.
+    #[error("`{property}` of `<{message}>` should be {expected}, found `{found}`")]
+    InvalidProperty {
+        /// The name of the message.
+        message: &'static str,
+        /// The name of the property.
+        property: &'static str,
+        /// What the property should be.
+        expected: &'static str,
+        /// The property's value, or an empty string if it's a modifier.
+        found: String,
+    },
+
+    /// There's text between messages.
+    #[error("expected a message, found text `{0}`")]
+    UnexpectedText(String),
+}
+
+impl From<MessageParseError> for Exception {
+    fn from(error: MessageParseError) -> Self {
+        match error {
+            MessageParseError::Syntax(error) => error.into(),
+            error => Exception::Runtime {
+                name: "InvalidMessage".to_string(),
+                location: None,
+                description: error.to_string(),
+            },
+        }
+    }
+}
+
+/// A message directive from the spec, sent between a client and a server.
+///
+/// A frame may hold any number of messages, see [`Message::parse`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message<'a> {
+    /// `<get doc>`: request to load a document, or the initial document if `doc` isn't given.
+    Get {
+        /// The path of the document to load.
+        doc: Option<Cow<'a, str>>,
+    },
+
+    /// `<redirect connection url cosmetic>`: change the URL.
+    Redirect {
+        /// The current connection, like `trax.tcp:example.com#0`.
+        connection: Cow<'a, str>,
+        /// The new URL.
+        url: Cow<'a, str>,
+        /// Only update the URL visually, without loading anything.
+        cosmetic: bool,
+    },
+
+    /// `<insert target start|end index>`: insert the contents into the target, or replace the
+    /// target if there's no position. Without contents, the target is deleted.
+    Insert {
+        /// The URL of the element to target, like `todo.trax#0/Frame/Body`.
+        target: Cow<'a, str>,
+        /// Where the contents are placed among the target's children, from the `start`, `end`
+        /// and `index` properties. See [`PlacePosition::from_properties`].
+        position: Option<PlacePosition>,
+        /// The elements, text and comments to insert.
+        contents: Vec<Fragment<'a>>,
+    },
+
+    /// `<insertProp target key value start|end index>`: insert, replace or delete a property or
+    /// modifier of the target.
+    InsertProp {
+        /// The URL of the element to target.
+        target: Cow<'a, str>,
+        /// The name of the property.
+        key: Option<Cow<'a, str>>,
+        /// The value of the property, or `None` for a modifier.
+        value: Option<Cow<'a, str>>,
+        /// Where the property is placed among the target's properties, from the `start`, `end`
+        /// and `index` properties. See [`PlacePosition::from_properties`].
+        position: Option<PlacePosition>,
+    },
+}
+
+impl<'a> Message<'a> {
+    /// Parse a frame of any number of messages. Comments and whitespace between them are
+    /// ignored.
+    ///
+    /// ```rust
+    /// use trax_protocol::Message;
+    ///
+    /// let messages = Message::parse(r#"<get doc="todo.trax" /><insert target="todo.trax#0/Frame/Body/Todo#1" />"#).unwrap();
+    ///
+    /// assert_eq!(messages.len(), 2);
+    /// assert_eq!(messages[1].name(), "insert");
+    /// ```
+    pub fn parse(source: &'a str) -> Result<Vec<Self>, MessageParseError> {
+        Fragment::parse(source)?
+            .into_iter()
+            .filter(|f| !matches!(f, Fragment::Comment(_)))
+            .map(Message::from_fragment)
+            .collect()
+    }
+
+    /// Read a message from the element holding it.
+    pub fn from_fragment(fragment: Fragment<'a>) -> Result<Self, MessageParseError> {
+        let (prefix, local, attributes, children) = match fragment {
+            Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            } => (prefix, local, attributes, children),
+            Fragment::Text(text) => {
+                return Err(MessageParseError::UnexpectedText(text.into_owned()))
+            }
+            Fragment::Comment(comment) => {
+                return Err(MessageParseError::UnexpectedText(format!("/*{comment}*/")))
+            }
+        };
+
+        let message = match (&*prefix, &*local) {
+            ("", "get") => "get",
+            ("", "redirect") => "redirect",
+            ("", "insert") => "insert",
+            ("", "insertProp") => "insertProp",
+            _ => {
+                let name = match &*prefix {
+                    "" => local.to_string(),
+                    prefix => format!("{prefix}:{local}"),
+                };
+                return Err(MessageParseError::UnknownMessage(name));
+            }
+        };
+        let mut properties = Properties {
+            message,
+            attributes: attributes.into_iter().collect(),
+        };
+
+        Ok(match message {
+            "get" => Message::Get {
+                doc: properties.value("doc")?,
+            },
+            "redirect" => Message::Redirect {
+                connection: properties.required("connection")?,
+                url: properties.required("url")?,
+                cosmetic: properties.modifier("cosmetic"),
+            },
+            "insert" => Message::Insert {
+                target: properties.target()?,
+                position: properties.position()?,
+                contents: children,
+            },
+            _ => Message::InsertProp {
+                target: properties.target()?,
+                key: properties.value("key")?,
+                value: properties.value("value")?,
+                position: properties.position()?,
+            },
+        })
+    }
+
+    /// The name of the message's element.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Message::Get { .. } => "get",
+            Message::Redirect { .. } => "redirect",
+            Message::Insert { .. } => "insert",
+            Message::InsertProp { .. } => "insertProp",
+        }
+    }
+
+    /// Convert the message to the element holding it, which is read back by
+    /// [`Message::from_fragment`]. Its properties and contents are escaped when it's displayed.
+    ///
+    /// ```rust
+    /// use trax_protocol::Message;
+    ///
+    /// let message = Message::InsertProp {
+    ///     target: "todo.trax#0/Frame/Body/Todo".into(),
+    ///     key: Some("title".into()),
+    ///     value: Some(r#"say "it's" & <go>"#.into()),
+    ///     position: None,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     message.to_fragment().to_string(),
+    ///     r#"<insertProp target="todo.trax#0/Frame/Body/Todo" key="title" value="say &quot;it's&quot; &amp; &lt;go>" />"#
+    /// );
+    /// ```
+    pub fn to_fragment(&self) -> Fragment<'a> {
+        let mut attributes = VecDeque::new();
+        let mut push = |key: &'static str, value: Option<Cow<'a, str>>| {
+            attributes.push_back(Attribute::new("", key, value));
+        };
+
+        let mut children = Vec::new();
+        match self {
+            Message::Get { doc } => {
+                if let Some(doc) = doc {
+                    push("doc", Some(doc.clone()));
+                }
+            }
+            Message::Redirect {
+                connection,
+                url,
+                cosmetic,
+            } => {
+                push("connection", Some(connection.clone()));
+                push("url", Some(url.clone()));
+                if *cosmetic {
+                    push("cosmetic", None);
+                }
+            }
+            Message::Insert {
+                target,
+                position,
+                contents,
+            } => {
+                push("target", Some(target.clone()));
+                push_position(&mut push, position);
+                children.clone_from(contents);
+            }
+            Message::InsertProp {
+                target,
+                key,
+                value,
+                position,
+            } => {
+                push("target", Some(target.clone()));
+                if let Some(key) = key {
+                    push("key", Some(key.clone()));
+                }
+                if let Some(value) = value {
+                    push("value", Some(value.clone()));
+                }
+                push_position(&mut push, position);
+            }
+        }
+
+        Fragment::Element {
+            prefix: Name::default(),
+            local: Name::Borrowed(self.name()),
+            attributes,
+            children,
+        }
+    }
+
+    /// Write a frame holding every message, one per line.
+    pub fn frame<'m, I: IntoIterator<Item = &'m Message<'a>>>(messages: I) -> String
+    where
+        'a: 'm,
+    {
+        let mut frame = String::new();
+        for message in messages {
+            if !frame.is_empty() {
+                frame.push('\n');
+            }
+            let _ = write!(frame, "{message}");
+        }
+        frame
+    }
+}
+
+impl Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_fragment())
+    }
+}
+
+// Pushes the `start`, `end` and `index` properties of a position.
+fn push_position<'a>(
+    push: &mut impl FnMut(&'static str, Option<Cow<'a, str>>),
+    position: &Option<PlacePosition>,
+) {
+    let (modifier, index) = match position {
+        None => return,
+        Some(PlacePosition::Start) => (Some("start"), None),
+        Some(PlacePosition::End) => (Some("end"), None),
+        Some(PlacePosition::StartIndex(n)) => (Some("start"), Some(n)),
+        Some(PlacePosition::EndIndex(n)) => (Some("end"), Some(n)),
+        Some(PlacePosition::Replace(n)) => (None, Some(n)),
+    };
+
+    if let Some(modifier) = modifier {
+        push(modifier, None);
+    }
+    if let Some(index) = index {
+        push("index", Some(index.to_string().into()));
+    }
+}
+
+// The properties of a message's element, which are taken as they're read.
+struct Properties<'a> {
+    message: &'static str,
+    attributes: Vec<Attribute<'a>>,
+}
+
+impl<'a> Properties<'a> {
+    fn get(&self, name: &str) -> Option<&Attribute<'a>> {
+        self.attributes
+            .iter()
+            .find(|a| a.prefix().is_empty() && a.local() == name)
+    }
+
+    fn value(&mut self, name: &'static str) -> Result<Option<Cow<'a, str>>, MessageParseError> {
+        let Some(i) = self
+            .attributes
+            .iter()
+            .position(|a| a.prefix().is_empty() && a.local() == name)
+        else {
+            return Ok(None);
+        };
+
+        match self.attributes.remove(i).into_value() {
+            Some(value) => Ok(Some(value)),
+            None => Err(MessageParseError::InvalidProperty {
+                message: self.message,
+                property: name,
+                expected: "a value",
+                found: String::new(),
+            }),
+        }
+    }
+
+    fn required(&mut self, name: &'static str) -> Result<Cow<'a, str>, MessageParseError> {
+        self.value(name)?.ok_or(MessageParseError::MissingProperty {
+            message: self.message,
+            property: name,
+        })
+    }
+
+    fn modifier(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    // The examples in the spec also call the target `path`.
+    fn target(&mut self) -> Result<Cow<'a, str>, MessageParseError> {
+        match self.value("path")? {
+            Some(path) if self.get("target").is_none() => Ok(path),
+            _ => self.required("target"),
+        }
+    }
+
+    fn position(&mut self) -> Result<Option<PlacePosition>, MessageParseError> {
+        let index = match self.value("index")? {
+            Some(index) => Some(index.trim().parse::<usize>().map_err(|_| {
+                MessageParseError::InvalidProperty {
+                    message: self.message,
+                    property: "index",
+                    expected: "a positive int",
+                    found: index.to_string(),
+                }
+            })?),
+            None => None,
+        };
+
+        Ok(PlacePosition::from_properties(
+            self.modifier("start"),
+            self.modifier("end"),
+            index,
+        ))
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::BTreeMap;
+
+use trax_document::{Changeset, Document, EntityRef, Exception, Interner, OwnedDocument, Url};
+
+use crate::Message;
+
+/// The id of a connection, like `0` in `trax.tcp:example.com#0`. Each tab a client has open to a
+/// server is its own connection.
+pub type ConnectionId = usize;
+
+/// What a [`Server`] calls to load documents and react to clients.
+///
+/// Messages returned by the callbacks are applied to the server's copy of the connection's
+/// documents before they're sent, see [`Server::send`]. If any of them fails, none of them are
+/// applied.
+pub trait Application {
+    /// The address of the server, like `trax.tcp:example.com`.
+    fn address(&self) -> &str;
+
+    /// The path of the document clients are redirected to by `<get />`, like `todo.trax`.
+    fn initial_document(&self) -> &str;
+
+    /// Load the source of a document, or `None` if there isn't one at the path.
+    fn load(&mut self, connection: ConnectionId, doc: &str) -> Option<String>;
+
+    /// Called once a document has been loaded by a connection, to fill it in.
+    fn loaded(
+        &mut self,
+        _connection: ConnectionId,
+        _doc: &str,
+        _document: &OwnedDocument,
+    ) -> Vec<Message<'static>> {
+        Vec::new()
+    }
+
+    /// Called once an `<insert>` or `<insertProp>` from the client has been applied to the
+    /// document it targets.
+    fn changed(
+        &mut self,
+        _connection: ConnectionId,
+        _doc: &str,
+        _document: &OwnedDocument,
+        _message: &Message<'static>,
+    ) -> Vec<Message<'static>> {
+        Vec::new()
+    }
+
+    /// Called when the client changes its URL with `<redirect>`.
+    fn redirected(&mut self, _connection: ConnectionId, _url: &str, _cosmetic: bool) {}
+
+    /// Called once a connection has been closed.
+    fn disconnected(&mut self, _connection: ConnectionId) {}
+}
+
+/// A connection to a [`Server`], with its copy of every document it loaded.
+#[derive(Clone, Debug, Default)]
+pub struct Connection {
+    url: Option<String>,
+    documents: BTreeMap<String, OwnedDocument>,
+    // shares the names of the connection's documents and messages, and is dropped with them
+    interner: Interner,
+}
+
+impl Connection {
+    /// The path of the document the connection is at, from the last `<get>` or `<redirect>`.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// The connection's copy of a document it loaded.
+    pub fn document(&self, doc: &str) -> Option<&OwnedDocument> {
+        self.documents.get(doc)
+    }
+
+    /// The paths of the documents the connection loaded, in order.
+    pub fn documents(&self) -> impl Iterator<Item = &str> {
+        self.documents.keys().map(String::as_str)
+    }
+}
+
+/// Tracks the connections to a TRAX server and answers their messages, so that the server only
+/// has to implement [`Application`] and move frames in and out.
+///
+/// ```rust
+/// use trax_protocol::{Application, ConnectionId, Message, Server};
+///
+/// struct Todos;
+///
+/// impl Application for Todos {
+///     fn address(&self) -> &str {
+///         "trax.tcp:example.com"
+///     }
+///
+///     fn initial_document(&self) -> &str {
+///         "todo.trax"
+///     }
+///
+///     fn load(&mut self, _: ConnectionId, doc: &str) -> Option<String> {
+///         (doc == "todo.trax").then(|| "<document><Body /></document>".to_string())
+///     }
+/// }
+///
+/// let mut server = Server::new(Todos);
+/// let connection = server.connect();
+///
+/// let replies = server.receive(connection, "<get />").unwrap();
+/// assert_eq!(
+///     Message::frame(&replies),
+///     r#"<redirect connection="trax.tcp:example.com#0" url="todo.trax" />"#
+/// );
+///
+/// let replies = server.receive(connection, r#"<get doc="todo.trax" />"#).unwrap();
+/// assert_eq!(
+///     Message::frame(&replies),
+///     r#"<insert target="todo.trax#0"><document><Body /></document></insert>"#
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Server<A> {
+    application: A,
+    connections: BTreeMap<ConnectionId, Connection>,
+    // the changes made to documents since the outermost `Server::atomic` began, or `None` outside
+    // of it
+    journal: Option<Vec<Change>>,
+}
+
+// A change to one of a connection's documents, which can be reverted.
+#[derive(Debug)]
+enum Change {
+    // a message was applied to the document
+    Applied(String, Changeset<'static>),
+    // the document was loaded, replacing the one which had been loaded before, if any
+    Loaded(String, Option<OwnedDocument>),
+}
+
+impl<A: Application> Server<A> {
+    /// Create a server without any connections.
+    pub fn new(application: A) -> Self {
+        Self {
+            application,
+            connections: BTreeMap::new(),
+            journal: None,
+        }
+    }
+
+    /// The server's application.
+    pub fn application(&self) -> &A {
+        &self.application
+    }
+
+    /// The server's application, mutably.
+    pub fn application_mut(&mut self) -> &mut A {
+        &mut self.application
+    }
+
+    /// Open a connection, which gets the lowest id that isn't in use.
+    pub fn connect(&mut self) -> ConnectionId {
+        let id = (0..)
+            .find(|id| !self.connections.contains_key(id))
+            .unwrap_or_default();
+        self.connections.insert(id, Connection::default());
+        id
+    }
+
+    /// Close a connection, returning it if it was open.
+    pub fn disconnect(&mut self, connection: ConnectionId) -> Option<Connection> {
+        let closed = self.connections.remove(&connection)?;
+        self.application.disconnected(connection);
+        Some(closed)
+    }
+
+    /// A connection which is open.
+    pub fn connection(&self, connection: ConnectionId) -> Option<&Connection> {
+        self.connections.get(&connection)
+    }
+
+    /// The ids of the open connections, in order.
+    pub fn connections(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+        self.connections.keys().copied()
+    }
+
+    /// Answer a frame of messages from a connection, returning the messages to send back.
+    ///
+    /// Messages are handled in order, stopping at the first one which fails. The connection is
+    /// then put back as it was before the frame, since none of the replies are sent. The
+    /// exception can be sent back with [`Exception::to_fragment`].
+    pub fn receive(
+        &mut self,
+        connection: ConnectionId,
+        frame: &str,
+    ) -> Result<Vec<Message<'static>>, Exception> {
+        let messages = Message::parse(frame)?;
+        self.atomic(connection, |server| {
+            let mut replies = Vec::new();
+            for message in messages {
+                replies.extend(server.handle(connection, message)?);
+            }
+            Ok(replies)
+        })
+    }
+
+    /// Answer a message from a connection, returning the messages to send back.
+    ///
+    /// - `<get />` redirects to the application's initial document.
+    /// - `<get doc>` loads the document and replaces the client's with it.
+    /// - `<redirect>` changes the connection's URL, and loads the document unless it's
+    ///   `cosmetic`.
+    /// - `<insert>` and `<insertProp>` are applied to the server's copy of the document they
+    ///   target, see [`Application::changed`].
+    ///
+    /// Messages from the application are applied all together or not at all.
+    pub fn handle(
+        &mut self,
+        connection: ConnectionId,
+        message: Message<'_>,
+    ) -> Result<Vec<Message<'static>>, Exception> {
+        let message = message.into_owned(&mut self.open(connection)?.interner);
+
+        match message {
+            Message::Get { doc: None } => {
+                let url = self.application.initial_document().to_string();
+                Ok(vec![Message::Redirect {
+                    connection: format!("{}#{connection}", self.application.address()).into(),
+                    url: url.into(),
+                    cosmetic: false,
+                }])
+            }
+            Message::Get { doc: Some(doc) } => self.load(connection, &doc),
+            Message::Redirect {
+                connection: url_connection,
+                url,
+                cosmetic,
+            } => {
+                if Url::parse(&url_connection)?.connection != Some(connection) {
+                    return Err(runtime(
+                        "InvalidConnection",
+                        format!("`{url_connection}` isn't connection {connection}"),
+                    ));
+                }
+
+                self.application.redirected(connection, &url, cosmetic);
+                if !cosmetic {
+                    return self.load(connection, &url);
+                }
+                let path = self.path(&url).to_string();
+                self.open(connection)?.url = Some(path);
+                Ok(Vec::new())
+            }
+            message @ (Message::Insert { .. } | Message::InsertProp { .. }) => {
+                let doc = self.apply(connection, message.clone())?;
+                let document = &self.connections[&connection].documents[&doc];
+                let messages = self
+                    .application
+                    .changed(connection, &doc, document, &message);
+                self.atomic(connection, |server| server.send_all(connection, messages))
+            }
+        }
+    }
+
+    /// Apply a message to the server's copy of the document it targets before it's sent to a
+    /// connection, so that the copy stays in sync with the client's. `<get>` and `<redirect>`
+    /// are returned as they are.
+    pub fn send(
+        &mut self,
+        connection: ConnectionId,
+        message: Message<'static>,
+    ) -> Result<Message<'static>, Exception> {
+        self.open(connection)?;
+        if let Message::Insert { .. } | Message::InsertProp { .. } = message {
+            self.apply(connection, message.clone())?;
+        }
+        Ok(message)
+    }
+
+    fn send_all(
+        &mut self,
+        connection: ConnectionId,
+        messages: Vec<Message<'static>>,
+    ) -> Result<Vec<Message<'static>>, Exception> {
+        messages
+            .into_iter()
+            .map(|message| self.send(connection, message))
+            .collect()
+    }
+
+    // Loads a document into a connection, replacing the client's document with it.
+    fn load(
+        &mut self,
+        connection: ConnectionId,
+        doc: &str,
+    ) -> Result<Vec<Message<'static>>, Exception> {
+        let doc = self.path(doc).to_string();
+        let source = self
+            .application
+            .load(connection, &doc)
+            .ok_or_else(|| runtime("NotFound", format!("`{doc}` couldn't be loaded")))?;
+        let document = Document::new(&source)?;
+
+        self.atomic(connection, |server| {
+            let open = server.open(connection)?;
+            let document = document.into_owned_with(&mut open.interner);
+            let root = document.fragment(&EntityRef::Element(0));
+            let root = root.map(|f| f.into_owned(&mut open.interner));
+            open.url = Some(doc.clone());
+            let replaced = open.documents.insert(doc.clone(), document);
+            server.record(Change::Loaded(doc.clone(), replaced));
+
+            let mut replies = vec![Message::Insert {
+                target: format!("{doc}#{connection}").into(),
+                position: None,
+                contents: root.into_iter().collect(),
+            }];
+            let messages = server.application.loaded(
+                connection,
+                &doc,
+                &server.connections[&connection].documents[&doc],
+            );
+            replies.extend(server.send_all(connection, messages)?);
+            Ok(replies)
+        })
+    }
+
+    // Runs `change`, putting the connection back as it was if it fails, so that the server's copy
+    // of its documents never gets ahead of what the client was sent. The documents it changed are
+    // reverted from the journal, so only the URL and the interner are copied.
+    fn atomic<T>(
+        &mut self,
+        connection: ConnectionId,
+        change: impl FnOnce(&mut Self) -> Result<T, Exception>,
+    ) -> Result<T, Exception> {
+        let open = self.open(connection)?;
+        let (url, interner) = (open.url.clone(), open.interner.clone());
+
+        let outer = self.journal.replace(Vec::new());
+        let result = change(self);
+        let changes = std::mem::replace(&mut self.journal, outer).unwrap_or_default();
+
+        if result.is_ok() {
+            if let Some(journal) = &mut self.journal {
+                journal.extend(changes);
+            }
+            return result;
+        }
+
+        let open = self.open(connection)?;
+        for change in changes.into_iter().rev() {
+            match change {
+                Change::Applied(doc, changeset) => {
+                    if let Some(document) = open.documents.get_mut(&doc) {
+                        // the changes were just made, so reverting them can't conflict
+                        let _ = changeset.revert(document);
+                    }
+                }
+                Change::Loaded(doc, Some(document)) => {
+                    open.documents.insert(doc, document);
+                }
+                Change::Loaded(doc, None) => {
+                    open.documents.remove(&doc);
+                }
+            }
+        }
+        open.url = url;
+        open.interner = interner;
+        result
+    }
+
+    // Adds a change to the journal, if a change is running.
+    fn record(&mut self, change: Change) {
+        if let Some(journal) = &mut self.journal {
+            journal.push(change);
+        }
+    }
+
+    // Applies an `<insert>` or `<insertProp>` to the document it targets, returning its path.
+    fn apply(
+        &mut self,
+        connection: ConnectionId,
+        message: Message<'static>,
+    ) -> Result<String, Exception> {
+        let (Message::Insert { target, .. } | Message::InsertProp { target, .. }) = &message else {
+            return Ok(String::new());
+        };
+        let url = Url::parse(target)?;
+        if url.connection.is_some_and(|c| c != connection) {
+            return Err(runtime(
+                "InvalidConnection",
+                format!("`{target}` isn't in connection {connection}"),
+            ));
+        }
+
+        let doc = self.path(url.document.unwrap_or_default()).to_string();
+        let document = self
+            .open(connection)?
+            .documents
+            .get_mut(&doc)
+            .ok_or_else(|| runtime("NotFound", format!("`{doc}` isn't loaded")))?;
+        let mut transaction = document.transaction();
+        message.apply_in(&mut transaction, &doc)?;
+        let changeset = transaction.commit();
+
+        self.record(Change::Applied(doc.clone(), changeset));
+        Ok(doc)
+    }
+
+    // The path of a document, without the server's address.
+    fn path<'u>(&self, url: &'u str) -> &'u str {
+        url.strip_prefix(self.application.address())
+            .and_then(|path| path.strip_prefix('/'))
+            .unwrap_or(url)
+    }
+
+    fn open(&mut self, connection: ConnectionId) -> Result<&mut Connection, Exception> {
+        self.connections.get_mut(&connection).ok_or_else(|| {
+            runtime(
+                "NotConnected",
+                format!("connection {connection} isn't open"),
+            )
+        })
+    }
+}
+
+fn runtime(name: &str, description: String) -> Exception {
+    Exception::Runtime {
+        name: name.to_string(),
+        location: None,
+        description,
+    }
+}
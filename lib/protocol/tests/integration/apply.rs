@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Document, EntityRef, Interner};
+    use trax_protocol::{Applied, Message};
+
+    use crate::render;
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+
+    fn apply(document: &mut Document<'static>, frame: &'static str) -> Vec<Applied> {
+        Message::parse(frame)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.apply(document, "todo.trax").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn spec_conversation() {
+        let mut doc = Document::new("<document><Frame><Body /></Frame></document>").unwrap();
+
+        let applied = apply(
+            &mut doc,
+            r#"<insert target="todo.trax#0/Frame/Body" start>
+                <Todo title="Do Laundry" done />
+                <Todo title="Work on TRAX" desc="gonna take a while" />
+            </insert>"#,
+        );
+        assert_eq!(
+            applied,
+            [Applied {
+                inserted: vec![EntityRef::Element(3), EntityRef::Element(4)],
+                ..Applied::default()
+            }]
+        );
+
+        let applied = apply(
+            &mut doc,
+            r#"<insertProp target="todo.trax#0/Frame/Body/Todo#1" key="done" end />"#,
+        );
+        assert_eq!(applied[0].modified, [4]);
+        assert_eq!(
+            render(&doc, 4),
+            r#"<Todo title="Work on TRAX" desc="gonna take a while" done />"#
+        );
+
+        let applied = apply(
+            &mut doc,
+            r#"<insert path="todo.trax#0/Frame/Body/Todo#1" />"#,
+        );
+        assert_eq!(applied[0].dropped, [EntityRef::Element(4)]);
+        assert_eq!(
+            render(&doc, 2),
+            r#"<Body><Todo title="Do Laundry" done /></Body>"#
+        );
+    }
+
+    #[test]
+    fn load_document() {
+        let mut doc = Document::new("<document><H1>Loading</H1></document>").unwrap();
+        let frame = format!(r#"<insert target="todo.trax#0">{TODO}</insert>"#);
+        let message = Message::parse(&frame).unwrap().remove(0);
+
+        let applied = message
+            .into_owned(&mut Interner::default())
+            .apply(&mut doc, "todo.trax");
+        let applied = [applied.unwrap()];
+        assert_eq!(applied[0].dropped, [EntityRef::Element(1)]);
+        assert_eq!(applied[0].inserted.len(), 2);
+        assert_eq!(doc.to_string(), Document::new(TODO).unwrap().to_string());
+    }
+
+    #[test]
+    fn properties() {
+        let mut doc =
+            Document::new(r#"<document><Todo title="Laundry" done /></document>"#).unwrap();
+
+        apply(
+            &mut doc,
+            r#"<insertProp target="todo.trax#0/Todo" value="Dishes" start />
+            <insertProp target="todo.trax#0/Todo" key="desc" value="soon" start index="1" />
+            <insertProp target="todo.trax#0/Todo" key="finished" index="2" />"#,
+        );
+        assert_eq!(
+            render(&doc, 1),
+            r#"<Todo title="Dishes" desc="soon" finished />"#
+        );
+
+        apply(
+            &mut doc,
+            r#"<insertProp target="todo.trax#0/Todo" end />
+            <insertProp target="todo.trax#0/Todo" />"#,
+        );
+        assert_eq!(render(&doc, 1), "<Todo />");
+    }
+
+    #[test]
+    fn exceptions() {
+        let mut doc = Document::new("<document><Body><Todo /></Body></document>").unwrap();
+        let mut error = |frame: &'static str| {
+            let message = Message::parse(frame).unwrap().remove(0);
+            message
+                .apply(&mut doc, "todo.trax")
+                .unwrap_err()
+                .name()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(r#"<insert target="other.trax#0/Body" />"#),
+            "NotFound"
+        );
+        assert_eq!(
+            error(r#"<insert target="todo.trax#0/Body.title" />"#),
+            "NotFound"
+        );
+        assert_eq!(
+            error(r#"<insert target="todo.trax#0/Body?" />"#),
+            "InvalidUrl"
+        );
+        assert_eq!(
+            error(r#"<insert target="todo.trax#0/Body" start index="2"><Todo /></insert>"#),
+            "PositionOutOfRange"
+        );
+        assert_eq!(
+            error(r#"<insert target="todo.trax#0"><Body /></insert>"#),
+            "ReplaceFailed"
+        );
+        assert_eq!(
+            error(r#"<insertProp target="todo.trax#0/Body/Todo" index="0" />"#),
+            "IndexOutOfRange"
+        );
+        assert_eq!(
+            error(r#"<insertProp target="todo.trax#0/Body" value="1" />"#),
+            "MissingPosition"
+        );
+        assert_eq!(error(r#"<get doc="todo.trax" />"#), "UnexpectedMessage");
+    }
+
+    #[test]
+    fn into_owned() {
+        let frame = String::from(
+            r#"<insert target="todo.trax#0/Body" end><Todo title="Laundry" /></insert>"#,
+        );
+        let message = Message::parse(&frame).unwrap().remove(0);
+        let owned = message.clone().into_owned(&mut Interner::default());
+        drop(frame);
+
+        assert_eq!(
+            owned.to_string(),
+            r#"<insert target="todo.trax#0/Body" end><Todo title="Laundry" /></insert>"#
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use trax_document::{Document, EntityRef, NodeId};
+
+mod apply;
+mod message;
+mod session;
+
+// The markup of an element.
+fn render(document: &Document, id: NodeId) -> String {
+    document
+        .fragment(&EntityRef::Element(id))
+        .unwrap()
+        .to_string()
+}
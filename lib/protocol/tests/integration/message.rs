@@ -0,0 +1,226 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Exception, Fragment, PlacePosition};
+    use trax_protocol::{Message, MessageParseError};
+
+    #[test]
+    fn get() {
+        assert_eq!(
+            Message::parse(r#"<get /><get doc="todo.trax" />"#),
+            Ok(vec![
+                Message::Get { doc: None },
+                Message::Get {
+                    doc: Some("todo.trax".into())
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn redirect() {
+        let src = r#"<redirect connection="trax.tcp:qoogle.com#0" url="trax.tcp:qoogle.com/search.trax" cosmetic />"#;
+        let messages = Message::parse(src).unwrap();
+
+        assert_eq!(
+            messages,
+            [Message::Redirect {
+                connection: "trax.tcp:qoogle.com#0".into(),
+                url: "trax.tcp:qoogle.com/search.trax".into(),
+                cosmetic: true,
+            }]
+        );
+        assert_eq!(messages[0].to_string(), src);
+
+        assert_eq!(
+            Message::parse(r#"<redirect url="todo.trax" />"#),
+            Err(MessageParseError::MissingProperty {
+                message: "redirect",
+                property: "connection"
+            })
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let src = r#"<insert target="todo.trax#0/Frame/Body" start>
+    <Todo title="Do Laundry" done />
+    <Todo title="Work on TRAX" desc="gonna take a while" />
+</insert>"#;
+        let messages = Message::parse(src).unwrap();
+        let [Message::Insert {
+            target,
+            position,
+            contents,
+        }] = &messages[..]
+        else {
+            panic!()
+        };
+
+        assert_eq!(target, "todo.trax#0/Frame/Body");
+        assert_eq!(position, &Some(PlacePosition::Start));
+        assert_eq!(contents.len(), 2);
+        assert_eq!(
+            messages[0].to_string(),
+            r#"<insert target="todo.trax#0/Frame/Body" start><Todo title="Do Laundry" done /><Todo title="Work on TRAX" desc="gonna take a while" /></insert>"#
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let cases = [
+            ("", None),
+            (" start", Some(PlacePosition::Start)),
+            (" end", Some(PlacePosition::End)),
+            (r#" start index="1""#, Some(PlacePosition::StartIndex(1))),
+            (r#" end index="2""#, Some(PlacePosition::EndIndex(2))),
+            (r#" index="3""#, Some(PlacePosition::Replace(3))),
+        ];
+
+        for (properties, expected) in cases {
+            let src = format!(r#"<insert target="document.trax#0"{properties} />"#);
+            let messages = Message::parse(&src).unwrap();
+
+            assert_eq!(
+                messages,
+                [Message::Insert {
+                    target: "document.trax#0".into(),
+                    position: expected,
+                    contents: Vec::new(),
+                }]
+            );
+            assert_eq!(messages[0].to_string(), src);
+        }
+
+        assert_eq!(
+            Message::parse(r#"<insert target="document.trax#0" index="-1" />"#),
+            Err(MessageParseError::InvalidProperty {
+                message: "insert",
+                property: "index",
+                expected: "a positive int",
+                found: "-1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn insert_prop() {
+        let src = r#"<insertProp target="todo.trax#0/Frame/Body/Todo#1" key="done" end />"#;
+        let messages = Message::parse(src).unwrap();
+
+        assert_eq!(
+            messages,
+            [Message::InsertProp {
+                target: "todo.trax#0/Frame/Body/Todo#1".into(),
+                key: Some("done".into()),
+                value: None,
+                position: Some(PlacePosition::End),
+            }]
+        );
+        assert_eq!(messages[0].to_string(), src);
+
+        assert_eq!(
+            Message::parse(r#"<insertProp key="done" />"#),
+            Err(MessageParseError::MissingProperty {
+                message: "insertProp",
+                property: "target"
+            })
+        );
+        assert_eq!(
+            Message::parse(r#"<insertProp target="todo.trax#0" key />"#),
+            Err(MessageParseError::InvalidProperty {
+                message: "insertProp",
+                property: "key",
+                expected: "a value",
+                found: String::new()
+            })
+        );
+    }
+
+    #[test]
+    fn spec_examples() {
+        let src = r#"<insert target='document.trax#0/Section?title="Giant Rat"' start index="1">
+	<Text style="italic">
+		Move over rats, I'm the new 2nd child haha jaja
+	</Text>
+</insert>
+
+/* No contents means the target element is deleted */
+<insert target="todo.trax#0/Frame/Body/Todo#1" />
+
+/* Loads a new document */
+<insert target="document.trax#0">
+	<document>
+		<H1> welcome to my website </H1>
+		<Link dest="trax.tcp:coolmathgames.com"> Leave this godforsaken place </Link>
+	</document>
+</insert>
+
+<insert path="todo.trax#0/Frame/Body/Todo#1" />"#;
+        let messages = Message::parse(src).unwrap();
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            Message::frame(&messages),
+            r#"<insert target='document.trax#0/Section?title="Giant Rat"' start index="1"><Text style="italic">Move over rats, I'm the new 2nd child haha jaja</Text></insert>
+<insert target="todo.trax#0/Frame/Body/Todo#1" />
+<insert target="document.trax#0"><document><H1>welcome to my website</H1><Link dest="trax.tcp:coolmathgames.com">Leave this godforsaken place</Link></document></insert>
+<insert target="todo.trax#0/Frame/Body/Todo#1" />"#
+        );
+
+        // a frame round trips
+        assert_eq!(Message::parse(&Message::frame(&messages)), Ok(messages));
+    }
+
+    #[test]
+    fn escaping() {
+        let src = r#"<insertProp target="todo.trax#0/Frame/Body/Todo" key="title" value="it's &quot;done&quot;" />
+<insert target="todo.trax#0/Frame/Body" end index="2"><Todo title="a &lt; b">1 &lt; 2 &amp; 3</Todo></insert>
+<redirect connection="trax.tcp:example.com#0" url='search.trax?q="a"' cosmetic />"#;
+        let messages = Message::parse(src).unwrap();
+
+        let Message::InsertProp { value, .. } = &messages[0] else {
+            panic!("expected insertProp");
+        };
+        assert_eq!(value.as_deref(), Some(r#"it's "done""#));
+
+        let frame = Message::frame(&messages);
+        assert_eq!(frame, src);
+        assert_eq!(Message::parse(&frame), Ok(messages));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Message::parse("<delete />"),
+            Err(MessageParseError::UnknownMessage("delete".to_string()))
+        );
+        assert_eq!(
+            Message::parse("<get /> hello"),
+            Err(MessageParseError::UnexpectedText("hello".to_string()))
+        );
+        assert!(matches!(
+            Message::parse("<get doc=todo.trax />"),
+            Err(MessageParseError::Syntax(_))
+        ));
+        assert_eq!(
+            Message::from_fragment(Fragment::Comment("comment".into())),
+            Err(MessageParseError::UnexpectedText("/*comment*/".to_string()))
+        );
+    }
+
+    #[test]
+    fn exceptions() {
+        for frame in ["<foo />", "<get doc=todo.trax />", "<get /> <b>"] {
+            let exception = Exception::from(Message::parse(frame).unwrap_err());
+            let markup = exception.to_fragment().to_string();
+            let parsed = Fragment::parse(&markup).unwrap();
+
+            assert_eq!(
+                Exception::from_fragment(&parsed[0]),
+                Some(exception),
+                "{markup}"
+            );
+        }
+    }
+}
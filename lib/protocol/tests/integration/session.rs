@@ -0,0 +1,431 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{EntityRef, Fragment, OwnedDocument, PlacePosition};
+    use trax_protocol::{Application, ConnectionId, Message, Server};
+
+    use crate::render;
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+
+    // Serves `todo.trax` without its todos and fills them in once it's loaded, then deletes the
+    // ones which are marked done.
+    #[derive(Default)]
+    struct Todos {
+        redirects: Vec<(ConnectionId, String, bool)>,
+        disconnected: Vec<ConnectionId>,
+    }
+
+    impl Application for Todos {
+        fn address(&self) -> &str {
+            "trax.tcp:example.com"
+        }
+
+        fn initial_document(&self) -> &str {
+            "todo.trax"
+        }
+
+        fn load(&mut self, _connection: ConnectionId, doc: &str) -> Option<String> {
+            match doc {
+                "todo.trax" => {
+                    let start = TODO.find("<Todo title")?;
+                    let end = TODO.find("</Body>")?;
+                    Some(format!("{}{}", &TODO[..start], &TODO[end..]))
+                }
+                "about.trax" => Some("<document><H1>About</H1></document>".to_string()),
+                _ => None,
+            }
+        }
+
+        fn loaded(
+            &mut self,
+            _connection: ConnectionId,
+            doc: &str,
+            _document: &OwnedDocument,
+        ) -> Vec<Message<'static>> {
+            match doc {
+                "todo.trax" => Message::parse(
+                    r#"<insert target="todo.trax#0/Frame/Body" start>
+                        <Todo title="Do Laundry" done />
+                        <Todo title="Work on TRAX" desc="gonna take a while" />
+                    </insert>"#,
+                )
+                .unwrap(),
+                _ => Vec::new(),
+            }
+        }
+
+        fn changed(
+            &mut self,
+            _connection: ConnectionId,
+            _doc: &str,
+            _document: &OwnedDocument,
+            message: &Message<'static>,
+        ) -> Vec<Message<'static>> {
+            match message {
+                Message::InsertProp {
+                    target,
+                    key: Some(key),
+                    ..
+                } if key == "done" => vec![Message::Insert {
+                    target: target.clone(),
+                    position: None,
+                    contents: Vec::new(),
+                }],
+                _ => Vec::new(),
+            }
+        }
+
+        fn redirected(&mut self, connection: ConnectionId, url: &str, cosmetic: bool) {
+            self.redirects.push((connection, url.to_string(), cosmetic));
+        }
+
+        fn disconnected(&mut self, connection: ConnectionId) {
+            self.disconnected.push(connection);
+        }
+    }
+
+    // Answers every change to `good.trax`, and every load of `bad.trax`, with one message which
+    // applies and one which doesn't.
+    struct Failing;
+
+    impl Application for Failing {
+        fn address(&self) -> &str {
+            "trax.tcp:example.com"
+        }
+
+        fn initial_document(&self) -> &str {
+            "good.trax"
+        }
+
+        fn load(&mut self, _connection: ConnectionId, _doc: &str) -> Option<String> {
+            Some("<document><Body /></document>".to_string())
+        }
+
+        fn loaded(
+            &mut self,
+            _connection: ConnectionId,
+            doc: &str,
+            _document: &OwnedDocument,
+        ) -> Vec<Message<'static>> {
+            match doc {
+                "bad.trax" => Failing::replies(doc),
+                _ => Vec::new(),
+            }
+        }
+
+        fn changed(
+            &mut self,
+            _connection: ConnectionId,
+            doc: &str,
+            _document: &OwnedDocument,
+            _message: &Message<'static>,
+        ) -> Vec<Message<'static>> {
+            Failing::replies(doc)
+        }
+    }
+
+    impl Failing {
+        fn replies(doc: &str) -> Vec<Message<'static>> {
+            vec![
+                Message::Insert {
+                    target: format!("{doc}#0/Body").into(),
+                    position: Some(PlacePosition::End),
+                    contents: Fragment::parse("<Reply />").unwrap(),
+                },
+                Message::Insert {
+                    target: format!("{doc}#0/Missing").into(),
+                    position: None,
+                    contents: Vec::new(),
+                },
+            ]
+        }
+    }
+
+    fn body(server: &Server<Todos>, connection: ConnectionId) -> String {
+        let document = server
+            .connection(connection)
+            .unwrap()
+            .document("todo.trax")
+            .unwrap();
+        let frame = document
+            .element(0)
+            .unwrap()
+            .children()
+            .find_map(|c| match c {
+                EntityRef::Element(id) if document.element(*id).unwrap().local() == "Frame" => {
+                    Some(*id)
+                }
+                _ => None,
+            });
+        let body = document
+            .element(frame.unwrap())
+            .unwrap()
+            .children()
+            .filter_map(|c| match c {
+                EntityRef::Element(id) => Some(*id),
+                _ => None,
+            })
+            .last()
+            .unwrap();
+        render(document, body)
+    }
+
+    #[test]
+    fn spec_conversation() {
+        let mut server = Server::new(Todos::default());
+        let connection = server.connect();
+
+        let replies = server.receive(connection, "<get />").unwrap();
+        assert_eq!(
+            Message::frame(&replies),
+            r#"<redirect connection="trax.tcp:example.com#0" url="todo.trax" />"#
+        );
+
+        let replies = server
+            .receive(connection, r#"<get doc="todo.trax" />"#)
+            .unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0].name(), "insert");
+        assert!(replies[0]
+            .to_string()
+            .starts_with(r#"<insert target="todo.trax#0"><document>"#));
+        assert_eq!(
+            replies[1].to_string(),
+            r#"<insert target="todo.trax#0/Frame/Body" start><Todo title="Do Laundry" done /><Todo title="Work on TRAX" desc="gonna take a while" /></insert>"#
+        );
+        assert_eq!(
+            server.connection(connection).unwrap().url(),
+            Some("todo.trax")
+        );
+        assert_eq!(
+            body(&server, connection),
+            r#"<Body><Todo title="Do Laundry" done /><Todo title="Work on TRAX" desc="gonna take a while" /></Body>"#
+        );
+
+        let replies = server
+            .receive(
+                connection,
+                r#"<insertProp target="todo.trax#0/Frame/Body/Todo#1" key="done" end />"#,
+            )
+            .unwrap();
+        assert_eq!(
+            Message::frame(&replies),
+            r#"<insert target="todo.trax#0/Frame/Body/Todo#1" />"#
+        );
+        assert_eq!(
+            body(&server, connection),
+            r#"<Body><Todo title="Do Laundry" done /></Body>"#
+        );
+    }
+
+    #[test]
+    fn connections() {
+        let mut server = Server::new(Todos::default());
+        assert_eq!(
+            [server.connect(), server.connect(), server.connect()],
+            [0, 1, 2]
+        );
+
+        assert!(server.disconnect(1).is_some());
+        assert!(server.disconnect(1).is_none());
+        assert_eq!(server.application().disconnected, [1]);
+        assert_eq!(server.connections().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(server.connect(), 1);
+
+        server.receive(2, r#"<get doc="about.trax" />"#).unwrap();
+        assert_eq!(
+            server
+                .connection(2)
+                .unwrap()
+                .documents()
+                .collect::<Vec<_>>(),
+            ["about.trax"]
+        );
+        assert_eq!(server.connection(0).unwrap().documents().count(), 0);
+    }
+
+    #[test]
+    fn redirect() {
+        let mut server = Server::new(Todos::default());
+        let connection = server.connect();
+
+        let replies = server
+            .receive(
+                connection,
+                r#"<redirect connection="trax.tcp:example.com#0" url="trax.tcp:example.com/about.trax" />"#,
+            )
+            .unwrap();
+        assert_eq!(
+            Message::frame(&replies),
+            r#"<insert target="about.trax#0"><document><H1>About</H1></document></insert>"#
+        );
+
+        let replies = server
+            .receive(
+                connection,
+                r#"<redirect connection="trax.tcp:example.com#0" url="todo.trax" cosmetic />"#,
+            )
+            .unwrap();
+        assert!(replies.is_empty());
+        assert_eq!(
+            server.connection(connection).unwrap().url(),
+            Some("todo.trax")
+        );
+        assert_eq!(
+            server.application().redirects,
+            [
+                (0, "trax.tcp:example.com/about.trax".to_string(), false),
+                (0, "todo.trax".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn send() {
+        let mut server = Server::new(Todos::default());
+        let connection = server.connect();
+        server
+            .receive(connection, r#"<get doc="todo.trax" />"#)
+            .unwrap();
+
+        let message =
+            Message::parse(r#"<insertProp target="todo.trax#0/Frame/Body/Todo" key="done" />"#)
+                .unwrap()
+                .remove(0);
+        assert_eq!(server.send(connection, message.clone()), Ok(message));
+        assert_eq!(
+            body(&server, connection),
+            r#"<Body><Todo title="Do Laundry" done /><Todo title="Work on TRAX" desc="gonna take a while" /></Body>"#
+        );
+
+        let message = Message::parse(
+            r#"<insert target="todo.trax#0/Frame/Body" end><Todo title="Dishes" /></insert>"#,
+        )
+        .unwrap()
+        .remove(0);
+        server.send(connection, message).unwrap();
+        assert!(body(&server, connection).ends_with(r#"<Todo title="Dishes" /></Body>"#));
+    }
+
+    #[test]
+    fn atomic() {
+        let mut server = Server::new(Failing);
+        let connection = server.connect();
+        let document = |server: &Server<Failing>, doc: &str| {
+            let document = server.connection(connection).unwrap().document(doc)?;
+            Some(render(document, 0))
+        };
+
+        // the document isn't loaded when filling it in fails
+        let error = server.receive(connection, r#"<get doc="bad.trax" />"#);
+        assert_eq!(error.unwrap_err().name(), "NotFound");
+        assert_eq!(server.connection(connection).unwrap().url(), None);
+        assert_eq!(document(&server, "bad.trax"), None);
+
+        // neither the change nor the replies are kept when a reply fails
+        server
+            .receive(connection, r#"<get doc="good.trax" />"#)
+            .unwrap();
+        let error = server.receive(
+            connection,
+            r#"<insertProp target="good.trax#0/Body" key="x" value="1" />"#,
+        );
+        assert_eq!(error.unwrap_err().name(), "NotFound");
+        assert_eq!(
+            document(&server, "good.trax").as_deref(),
+            Some("<document><Body /></document>")
+        );
+
+        // the same goes for earlier messages in the frame
+        let error = server.receive(
+            connection,
+            r#"<get doc="other.trax" /><get doc="bad.trax" />"#,
+        );
+        assert_eq!(error.unwrap_err().name(), "NotFound");
+        assert_eq!(
+            server.connection(connection).unwrap().url(),
+            Some("good.trax")
+        );
+        assert_eq!(document(&server, "other.trax"), None);
+
+        // a document which is loaded again is put back as it was
+        let sent = Message::Insert {
+            target: "good.trax#0/Body".into(),
+            position: Some(PlacePosition::End),
+            contents: Fragment::parse("<Sent />").unwrap(),
+        };
+        server.send(connection, sent).unwrap();
+        let error = server.receive(
+            connection,
+            r#"<get doc="good.trax" /><get doc="bad.trax" />"#,
+        );
+        assert_eq!(error.unwrap_err().name(), "NotFound");
+        assert_eq!(
+            document(&server, "good.trax").as_deref(),
+            Some("<document><Body><Sent /></Body></document>")
+        );
+    }
+
+    #[test]
+    fn interning() {
+        let mut server = Server::new(Todos::default());
+        let [first, second] = [server.connect(), server.connect()];
+        for connection in [first, second] {
+            server
+                .receive(connection, r#"<get doc="about.trax" />"#)
+                .unwrap();
+        }
+        server
+            .receive(
+                first,
+                r#"<insert target="about.trax#0/H1" end><H1 /></insert>"#,
+            )
+            .unwrap();
+
+        // names are shared within a connection, but not with other connections
+        let name = |connection: ConnectionId, id: usize| {
+            let document = server.connection(connection)?.document("about.trax")?;
+            Some(document.element(id)?.local().as_ptr())
+        };
+        assert_eq!(name(first, 1), name(first, 2));
+        assert_ne!(name(first, 1), name(second, 1));
+    }
+
+    #[test]
+    fn exceptions() {
+        let mut server = Server::new(Todos::default());
+        let connection = server.connect();
+        server
+            .receive(connection, r#"<get doc="todo.trax" />"#)
+            .unwrap();
+        let mut error = |frame: &str| {
+            server
+                .receive(connection, frame)
+                .unwrap_err()
+                .name()
+                .to_string()
+        };
+
+        assert_eq!(error(r#"<get doc="missing.trax" />"#), "NotFound");
+        assert_eq!(error(r#"<insert target="about.trax#0/H1" />"#), "NotFound");
+        assert_eq!(
+            error(r#"<insert target="todo.trax#0/Frame/Nav" />"#),
+            "NotFound"
+        );
+        assert_eq!(
+            error(r#"<insert target="todo.trax#1/Frame" />"#),
+            "InvalidConnection"
+        );
+        assert_eq!(
+            error(r#"<redirect connection="trax.tcp:example.com#3" url="todo.trax" />"#),
+            "InvalidConnection"
+        );
+        assert_eq!(error("<ping />"), "InvalidMessage");
+        assert_eq!(error("<get doc=todo.trax />"), "DocumentParseException");
+        assert_eq!(
+            server.receive(7, "<get />").unwrap_err().name(),
+            "NotConnected"
+        );
+    }
+}
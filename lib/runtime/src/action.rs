@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+
+use thiserror::Error;
+use trax_document::{
+    DropEntityError, EntityRef, EvalContext, EvalError, Fragment, InsertElementError,
+    ModifyEntityError, NodeId, PlacePosition, Target, Type, Url, Value,
+};
+
+use crate::Runtime;
+
+/// An error encountered when running an `action:` element.
+#[derive(Debug, PartialEq, Error)]
+pub enum ActionError {
+    /// The element doesn't have the `action` prefix.
+    #[error("element {0} isn't an action")]
+    NotAnAction(NodeId),
+
+    /// The action isn't one of the spec's directives.
+    #[error("`action:{0}` isn't an action")]
+    UnknownAction(String),
+
+    /// The action doesn't have a property it requires.
+    #[error("`action:{action}` requires the `{property}` property")]
+    MissingProperty {
+        /// The name of the action, without the `action` prefix.
+        action: String,
+        /// The name of the property.
+        property: String,
+    },
+
+    /// The URL doesn't locate an element.
+    #[error("`{0}` doesn't locate an element")]
+    TargetNotFound(String),
+
+    /// The `index` property is negative.
+    #[error("index {0} is negative")]
+    NegativeIndex(i64),
+
+    /// A property or an `eval` element in the contents of the action couldn't be evaluated.
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+
+    /// The contents couldn't be inserted.
+    #[error(transparent)]
+    Insert(#[from] InsertElementError),
+
+    /// The target couldn't be deleted.
+    #[error(transparent)]
+    Drop(#[from] DropEntityError),
+
+    /// The target's properties couldn't be changed.
+    #[error(transparent)]
+    Modify(#[from] ModifyEntityError),
+}
+
+impl<'a> Runtime<'a> {
+    /// Fire an event at an element, running the actions located by its `onEvent:<event>`
+    /// property, which holds whitespace-separated URLs relative to the element.
+    ///
+    /// Actions are run in order, stopping at the first one which fails.
+    pub fn fire(&mut self, element_id: NodeId, event: &str) -> Result<(), ActionError> {
+        let urls: Vec<String> = self
+            .document
+            .element(element_id)
+            .and_then(|e| e.attribute(&format!("onEvent:{event}")))
+            .and_then(|a| a.value())
+            .map(|v| v.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        for url in urls {
+            let context = EvalContext::new(&self.document);
+            let actions: Vec<NodeId> = Url::parse(&url)
+                .map(|u| context.resolve_all(element_id, &u))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|target| match target {
+                    Target::Element(id) => Some(id),
+                    Target::Property(..) => None,
+                })
+                .collect();
+
+            if actions.is_empty() {
+                return Err(ActionError::TargetNotFound(url));
+            }
+            for action in actions {
+                self.run_action(action)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run an `action:` element, which performs the spec's message directive of the same name
+    /// on the document:
+    ///
+    /// - `<action:insert target start|end index>` inserts its contents, with `eval` elements
+    ///   replaced by their value. See
+    ///   [`Document::splice`](trax_document::Document::splice).
+    /// - `<action:insertProp target key value start|end index>` inserts, replaces or deletes a
+    ///   property of the target. See
+    ///   [`Document::splice_property`](trax_document::Document::splice_property).
+    ///
+    /// Actions aren't expanded until they're run, and the runtime is updated afterwards (see
+    /// [`Runtime::update`]).
+    pub fn run_action(&mut self, action_id: NodeId) -> Result<(), ActionError> {
+        let action = self
+            .document
+            .element(action_id)
+            .filter(|e| e.prefix() == "action")
+            .ok_or(ActionError::NotAnAction(action_id))?
+            .local()
+            .to_string();
+
+        let result = match action.as_str() {
+            "insert" => self.action_insert(action_id),
+            "insertProp" => self.action_insert_prop(action_id),
+            _ => return Err(ActionError::UnknownAction(action)),
+        };
+
+        self.update();
+        result
+    }
+
+    fn action_insert(&mut self, id: NodeId) -> Result<(), ActionError> {
+        let target = self.action_target(id, "insert")?;
+        let position = self.position(id)?;
+
+        let children: Vec<EntityRef> = self
+            .document
+            .element(id)
+            .map(|e| e.children().cloned().collect())
+            .unwrap_or_default();
+        let mut contents = Vec::new();
+        for child in &children {
+            contents.extend(self.contents(child)?);
+        }
+
+        self.document.splice(target, position, contents)?;
+        Ok(())
+    }
+
+    fn action_insert_prop(&mut self, id: NodeId) -> Result<(), ActionError> {
+        let target = self.action_target(id, "insertProp")?;
+        let position = self.position(id)?;
+        let context = EvalContext::new(&self.document);
+
+        let key = match context.property(id, "key", Type::Str)? {
+            Value::Str(key) => Some(key.into_owned()),
+            _ => None,
+        };
+        let value = match context.property(id, "value", Type::Any)? {
+            Value::Undefined => None,
+            value => Some(Cow::Owned(value.to_string())),
+        };
+
+        self.document
+            .splice_property(target, key.as_deref(), value, position)?;
+        Ok(())
+    }
+
+    // Reads the position of an action from its `start`, `end` and `index` properties.
+    fn position(&self, id: NodeId) -> Result<Option<PlacePosition>, ActionError> {
+        let context = EvalContext::new(&self.document);
+        let start = context.property(id, "start", Type::Bool)? == Value::Bool(true);
+        let end = context.property(id, "end", Type::Bool)? == Value::Bool(true);
+        let index = match context.property(id, "index", Type::Int)? {
+            Value::Int(i) => Some(usize::try_from(i).map_err(|_| ActionError::NegativeIndex(i))?),
+            _ => None,
+        };
+
+        Ok(PlacePosition::from_properties(start, end, index))
+    }
+
+    // Resolves the `target` property of an action to an element.
+    fn action_target(&self, id: NodeId, action: &str) -> Result<NodeId, ActionError> {
+        let url = self
+            .document
+            .element(id)
+            .and_then(|e| e.attribute("target"))
+            .and_then(|a| a.value())
+            .ok_or_else(|| ActionError::MissingProperty {
+                action: action.to_string(),
+                property: "target".to_string(),
+            })?;
+
+        let context = EvalContext::new(&self.document);
+        match Url::parse(url).map(|u| context.resolve(id, &u)) {
+            Ok(Some(Target::Element(target))) => Ok(target),
+            _ => Err(ActionError::TargetNotFound(url.to_string())),
+        }
+    }
+
+    // Copies an entity in the contents of an action, replacing `eval` elements with their value.
+    fn contents(&self, entity_ref: &EntityRef) -> Result<Option<Fragment<'a>>, EvalError> {
+        let EntityRef::Element(id) = entity_ref else {
+            return Ok(self.document.fragment(entity_ref));
+        };
+        let Some(element) = self.document.element(*id) else {
+            return Ok(None);
+        };
+
+        if element.prefix().is_empty() && element.local() == "eval" {
+            return Ok(match self.value_of(*id)? {
+                Value::Undefined | Value::Void => None,
+                value => Some(Fragment::Text(Cow::Owned(value.to_string()))),
+            });
+        }
+
+        let mut children = Vec::new();
+        for child in element.children() {
+            children.extend(self.contents(child)?);
+        }
+
+        Ok(match self.document.fragment(entity_ref) {
+            Some(Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                ..
+            }) => Some(Fragment::Element {
+                prefix,
+                local,
+                attributes,
+                children,
+            }),
+            fragment => fragment,
+        })
+    }
+}
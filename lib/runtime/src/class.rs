@@ -0,0 +1,373 @@
+use std::{collections::HashMap, ops::Range};
+
+use trax_document::{
+    Diagnostic, DiagnosticCode, EntityRef, EvalContext, Fragment, NodeId, PlacePosition, Schema,
+    Severity, Type, Value,
+};
+
+use crate::Runtime;
+
+/// A field of a [`Class`], declared with `<let:name Type />`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// The name of the field, which instances set as a property.
+    pub name: String,
+    /// The type of the field's value.
+    pub ty: Type,
+}
+
+impl Field {
+    /// Create a new field.
+    pub fn new<S: Into<String>>(name: S, ty: Type) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// A template defined with `<class:Name>`, which is instantiated wherever `<Name />` is used.
+///
+/// The children of the definition are its fields, followed by the body which is copied into each
+/// instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Class<'a> {
+    /// The name of the class, without the `class` prefix.
+    pub name: String,
+    /// The id of the `class:` element defining the class.
+    pub element: NodeId,
+    /// The fields of the class, in order.
+    pub fields: Vec<Field>,
+    /// The children of the definition which aren't fields.
+    pub body: Vec<Fragment<'a>>,
+}
+
+impl Class<'_> {
+    /// Returns a field by name.
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+/// A use of a [`Class`] in the document, which holds the values of its fields.
+///
+/// The element using the class is kept, and a copy of the class's body is appended to its
+/// children.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub(crate) class: String,
+    pub(crate) element: NodeId,
+    pub(crate) state: HashMap<String, Value<'static>>,
+    pub(crate) body: Vec<EntityRef>,
+}
+
+impl Instance {
+    /// The name of the instantiated class.
+    pub fn class(&self) -> &str {
+        &self.class
+    }
+
+    /// The id of the element using the class.
+    pub fn element(&self) -> NodeId {
+        self.element
+    }
+
+    /// Returns the value of a field, or `None` if the class doesn't have it.
+    pub fn get(&self, field: &str) -> Option<&Value<'static>> {
+        self.state.get(field)
+    }
+
+    /// The entities copied from the class's body.
+    pub fn body(&self) -> &[EntityRef] {
+        &self.body
+    }
+
+    // Returns `true` if the entity was copied into the instance from its class's body.
+    pub(crate) fn owns(&self, entity_ref: &EntityRef) -> bool {
+        self.body.contains(entity_ref)
+    }
+}
+
+// The spec's types, as written in `let:` fields, e.g. `String` or `Bool`.
+fn parse_type(name: &str) -> Option<Type> {
+    match name {
+        "String" => Some(Type::Str),
+        name => name.to_lowercase().parse().ok(),
+    }
+}
+
+impl<'a> Runtime<'a> {
+    /// Returns a class by name.
+    pub fn class(&self, name: &str) -> Option<&Class<'a>> {
+        self.classes.get(name)
+    }
+
+    /// The classes defined in the document, in no particular order.
+    pub fn classes(&self) -> impl Iterator<Item = &Class<'a>> {
+        self.classes.values()
+    }
+
+    /// Returns the instance using an element, if it uses a class.
+    pub fn instance(&self, element_id: NodeId) -> Option<&Instance> {
+        self.instances.get(&element_id)
+    }
+
+    /// The instances in the document, ordered by the id of their element.
+    pub fn instances(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.values()
+    }
+
+    /// Check the document for elements which aren't known: elements with an unprefixed name
+    /// starting with an uppercase letter which are neither classes nor described by the schema.
+    /// Invalid `visible:` modifiers and `pin:` properties are also reported, see
+    /// [`Runtime::computed`].
+    ///
+    /// Only the source of each element is checked, not the copies of class bodies in instances.
+    pub fn validate(&self, schema: &Schema) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(id) = stack.pop() {
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+
+            let local = element.local();
+            if element.prefix().is_empty()
+                && local.starts_with(char::is_uppercase)
+                && !self.classes.contains_key(local)
+                && schema.get("", local).is_none()
+            {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticCode::UnknownClass,
+                    self.start_tag(id),
+                    format!("`{local}` isn't a class or a known element"),
+                ));
+            }
+            self.validate_interactions(id, &mut diagnostics);
+
+            let instance = self.instances.get(&id);
+            stack.extend(
+                self.child_elements(id)
+                    .into_iter()
+                    .rev()
+                    .filter(|c| instance.is_none_or(|i| !i.owns(&EntityRef::Element(*c)))),
+            );
+        }
+
+        diagnostics
+    }
+
+    pub(crate) fn start_tag(&self, id: NodeId) -> Option<Range<usize>> {
+        self.document.element_span(id).map(|s| s.start_tag.clone())
+    }
+
+    pub(crate) fn attribute_span(
+        &self,
+        id: NodeId,
+        prefix: &str,
+        local: &str,
+    ) -> Option<Range<usize>> {
+        self.document
+            .element_span(id)
+            .and_then(|s| s.attribute(prefix, local))
+            .map(|a| a.full.clone())
+            .or_else(|| self.start_tag(id))
+    }
+
+    // Collects every `class:` definition in the document, checking their fields.
+    pub(crate) fn collect_classes(&mut self) {
+        let mut stack = vec![0];
+
+        while let Some(id) = stack.pop() {
+            stack.extend(self.child_elements(id).into_iter().rev());
+
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+            if element.prefix() != "class" {
+                continue;
+            }
+
+            let name = element.local().to_string();
+            if self.classes.contains_key(&name) {
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::DuplicateClass,
+                    self.start_tag(id),
+                    format!("class `{name}` is defined more than once"),
+                ));
+                continue;
+            }
+
+            let class = self.collect_class(id, name);
+            self.classes.insert(class.name.clone(), class);
+        }
+    }
+
+    fn collect_class(&mut self, id: NodeId, name: String) -> Class<'a> {
+        let mut class = Class {
+            name,
+            element: id,
+            fields: Vec::new(),
+            body: Vec::new(),
+        };
+
+        let children: Vec<EntityRef> = self
+            .document
+            .element(id)
+            .map(|e| e.children().cloned().collect())
+            .unwrap_or_default();
+
+        for child in children {
+            let field = match &child {
+                EntityRef::Element(i) => self
+                    .document
+                    .element(*i)
+                    .filter(|e| e.prefix() == "let")
+                    .map(|e| (*i, e.local().to_string())),
+                _ => None,
+            };
+
+            let Some((field_id, field)) = field else {
+                class.body.extend(self.document.fragment(&child));
+                continue;
+            };
+
+            let types: Vec<_> = self
+                .document
+                .element(field_id)
+                .into_iter()
+                .flat_map(|e| e.attributes())
+                .filter(|a| a.prefix().is_empty() && a.is_modifier())
+                .map(|a| a.local().to_string())
+                .collect();
+
+            let problem = match &types[..] {
+                _ if class.field(&field).is_some() => {
+                    Some(format!("`{field}` is declared more than once"))
+                }
+                [] => Some(format!(
+                    "`{field}` needs a type, like `<let:{field} String />`"
+                )),
+                [ty] => match parse_type(ty) {
+                    Some(ty) => {
+                        class.fields.push(Field { name: field, ty });
+                        None
+                    }
+                    None => Some(format!("`{ty}` isn't a type")),
+                },
+                _ => Some(format!("`{field}` has more than one type")),
+            };
+
+            if let Some(problem) = problem {
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::InvalidField,
+                    self.start_tag(field_id),
+                    problem,
+                ));
+            }
+        }
+
+        class
+    }
+
+    // Sets up the state of an instance from the properties of its element, then appends a copy
+    // of the class's body.
+    pub(crate) fn instantiate(&mut self, id: NodeId, class_name: &str) {
+        let Some(class) = self.classes.get(class_name).cloned() else {
+            return;
+        };
+
+        let mut ancestor = id;
+        while ancestor != 0 {
+            ancestor = self.document.element(ancestor).map_or(0, |e| e.parent());
+            if self
+                .instances
+                .get(&ancestor)
+                .is_some_and(|i| i.class == class.name)
+            {
+                self.recursive.insert(id);
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::RecursiveClass,
+                    self.start_tag(id),
+                    format!("`{}` is used inside one of its own instances", class.name),
+                ));
+                return;
+            }
+        }
+
+        let mut state = HashMap::new();
+        let mut diagnostics = Vec::new();
+        let context = EvalContext::new(&self.document);
+
+        for field in &class.fields {
+            let value = match context.property(id, &field.name, field.ty) {
+                Ok(Value::Undefined) => {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        DiagnosticCode::MissingProperty,
+                        self.start_tag(id),
+                        format!("`{}` doesn't set the `{}` field", class.name, field.name),
+                    ));
+                    Value::Undefined
+                }
+                Ok(value) => value.into_owned(),
+                Err(error) => {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticCode::TypeMismatch,
+                        error.location().cloned().or_else(|| self.start_tag(id)),
+                        error.to_string(),
+                    ));
+                    Value::Undefined
+                }
+            };
+            state.insert(field.name.clone(), value);
+        }
+
+        if let Some(element) = self.document.element(id) {
+            for attribute in element.attributes() {
+                let is_property = matches!(attribute.prefix(), "" | "asRef" | "asEval");
+                if is_property && class.field(attribute.local()).is_none() {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        DiagnosticCode::UnknownProperty,
+                        self.attribute_span(id, attribute.prefix(), attribute.local()),
+                        format!(
+                            "`{}` doesn't have a `{}` field",
+                            class.name,
+                            attribute.local()
+                        ),
+                    ));
+                }
+            }
+        }
+        self.diagnostics.extend(diagnostics);
+
+        let mut body = Vec::new();
+        for fragment in class.body {
+            match self
+                .document
+                .insert_fragment(id, PlacePosition::End, fragment)
+            {
+                Ok(entity_ref) => body.push(entity_ref),
+                Err(_) => break,
+            }
+        }
+
+        self.instances.insert(
+            id,
+            Instance {
+                class: class.name,
+                element: id,
+                state,
+                body,
+            },
+        );
+        self.render_fields(id, None);
+    }
+}
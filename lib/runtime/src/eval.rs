@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+
+use trax_document::{
+    Diagnostic, DiagnosticCode, EntityRef, EvalContext, EvalError, NodeId, PlacePosition, Severity,
+    Target, Url, Value,
+};
+
+use crate::{Iterable, Runtime};
+
+impl<'a> Runtime<'a> {
+    /// Evaluate a URL, starting from the element `from` for relative paths. See
+    /// [`EvalContext::evaluate`].
+    ///
+    /// Unlike the document, the runtime knows what `eval` and `format` elements evaluate to (see
+    /// [`Runtime::value_of`]), and the variable of a `for` loop over elements evaluates to the
+    /// element of the current item.
+    pub fn evaluate(&self, from: NodeId, url: &str) -> Result<Value<'static>, EvalError> {
+        let context = EvalContext::new(&self.document);
+        let target = Url::parse(url)
+            .ok()
+            .and_then(|url| context.resolve(from, &url));
+
+        match target {
+            Some(Target::Element(id)) => self.value_of(id),
+            Some(Target::Property(id, attribute)) => {
+                let is_element_var = self.loops.get(&id).is_some_and(|l| {
+                    attribute.prefix().is_empty()
+                        && attribute.local() == l.var()
+                        && matches!(
+                            l.iterable(),
+                            Iterable::Path(path) if Url::parse(path).is_ok_and(|u| u.property.is_none())
+                        )
+                });
+
+                match attribute.value() {
+                    Some(url) if is_element_var => {
+                        let item = Url::parse(url)
+                            .ok()
+                            .and_then(|url| context.resolve(id, &url));
+                        match item {
+                            Some(Target::Element(item)) => self.value_of(item),
+                            _ => Ok(Value::Undefined),
+                        }
+                    }
+                    Some(value) => Ok(Value::infer(value).into_owned()),
+                    None => Ok(Value::Bool(true)),
+                }
+            }
+            None => Ok(Value::Undefined),
+        }
+    }
+
+    /// The value of an element.
+    ///
+    /// - `<eval target="...">` evaluates its target (see [`Runtime::evaluate`]). Once it's been
+    ///   expanded, the value is kept even if the target changes.
+    /// - `<format>` gives its text, including the text of the elements inside it, as a `str`.
+    /// - Arithmetic elements are evaluated, see
+    ///   [`Document::evaluate`](trax_document::Document::evaluate).
+    /// - Other elements give the literal in their text (see [`Value::infer`]), or `void` if they
+    ///   don't have any.
+    pub fn value_of(&self, element_id: NodeId) -> Result<Value<'static>, EvalError> {
+        let Some(element) = self.document.element(element_id) else {
+            return Ok(Value::Undefined);
+        };
+        if let Some(value) = self.evals.get(&element_id) {
+            return Ok(value.clone());
+        }
+
+        match (element.prefix(), element.local()) {
+            ("", "eval") => match element.attribute("target").and_then(|a| a.value()) {
+                Some(url) => self.evaluate(element_id, url),
+                None => Ok(Value::Undefined),
+            },
+            ("", "format") => Ok(Value::Str(Cow::Owned(self.text_of(element_id)))),
+            _ => match self.document.evaluate(&EntityRef::Element(element_id))? {
+                Value::Void => {
+                    let text = self.text_of(element_id);
+                    if text.is_empty() {
+                        Ok(Value::Void)
+                    } else {
+                        Ok(Value::infer(&text).into_owned())
+                    }
+                }
+                value => Ok(value.into_owned()),
+            },
+        }
+    }
+
+    // Evaluates an `eval` element, showing its value as its text.
+    pub(crate) fn create_eval(&mut self, id: NodeId) {
+        let target = self
+            .document
+            .element(id)
+            .and_then(|e| e.attribute("target"))
+            .map(|a| a.value());
+
+        let value = match target {
+            Some(Some(url)) => match self.evaluate(id, url) {
+                Ok(value) => value,
+                Err(error) => {
+                    self.diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticCode::TypeMismatch,
+                        error.location().cloned().or_else(|| self.start_tag(id)),
+                        error.to_string(),
+                    ));
+                    Value::Undefined
+                }
+            },
+            _ => {
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::MissingProperty,
+                    self.start_tag(id),
+                    "`eval` requires the `target` property",
+                ));
+                Value::Undefined
+            }
+        };
+
+        if !matches!(value, Value::Undefined | Value::Void) {
+            let _ = self
+                .document
+                .insert_text(id, PlacePosition::End, value.to_string());
+        }
+        self.evals.insert(id, value);
+    }
+
+    // The text in the subtree of an element, without separators. Actions are skipped, since their
+    // contents aren't shown.
+    fn text_of(&self, id: NodeId) -> String {
+        let mut text = String::new();
+        let mut stack = vec![EntityRef::Element(id)];
+
+        while let Some(entity_ref) = stack.pop() {
+            match entity_ref {
+                EntityRef::Element(i) => match self.document.element(i) {
+                    Some(element) if element.prefix() != "action" => {
+                        let children: Vec<EntityRef> = element.children().cloned().collect();
+                        stack.extend(children.into_iter().rev());
+                    }
+                    _ => (),
+                },
+                EntityRef::Text(i) => {
+                    text.extend(self.document.text(i).map(|t| t.content()));
+                }
+                EntityRef::Comment(_) => (),
+            }
+        }
+
+        text
+    }
+}
@@ -0,0 +1,108 @@
+use trax_document::{EntityRef, NodeId};
+
+use crate::{ActionError, Runtime};
+
+/// An event sent by an element, to be handled by the other end of the connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The name of the event, like `click` or `submit`.
+    pub name: String,
+    /// The id of the element which sent the event.
+    pub target: NodeId,
+}
+
+impl Event {
+    /// Create a new event.
+    pub fn new<S: Into<String>>(name: S, target: NodeId) -> Self {
+        Self {
+            name: name.into(),
+            target,
+        }
+    }
+}
+
+/// What happened when an event was dispatched. See [`Runtime::dispatch`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dispatch {
+    /// The elements whose `onEvent:` actions were run, in order.
+    pub handled: Vec<NodeId>,
+    /// The events sent by `send` properties, which are also in [`Runtime::take_events`].
+    pub sent: Vec<Event>,
+    /// The elements dropped by `clear:self` properties.
+    pub cleared: Vec<NodeId>,
+}
+
+impl Runtime<'_> {
+    /// Dispatch an event, like a click, to its target. The event bubbles up from the target
+    /// through each of its ancestors, and at each element:
+    ///
+    /// 1. The actions of an `onEvent:<name>` property are run, see [`Runtime::fire`].
+    /// 2. A `send` property sends the event it names if the event is a `click`. Events sent by
+    ///    `bind:` elements are sent on input instead, see [`Runtime::input`].
+    /// 3. A `clear:self` property listing the event's name drops the instance the element is in,
+    ///    or the element itself if it isn't in one.
+    ///
+    /// Elements which are dropped while the event bubbles aren't visited.
+    pub fn dispatch(&mut self, event: Event) -> Result<Dispatch, ActionError> {
+        let mut path = Vec::new();
+        let mut current = event.target;
+        while let Some(element) = self.document.element(current) {
+            path.push(current);
+            if current == 0 {
+                break;
+            }
+            current = element.parent();
+        }
+
+        let mut dispatch = Dispatch::default();
+        for id in path {
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+            let handles = element
+                .attribute(&format!("onEvent:{}", event.name))
+                .is_some();
+            let send = element
+                .attribute("send")
+                .and_then(|a| a.value())
+                .filter(|_| event.name == "click")
+                .map(str::to_string);
+            let clears = element
+                .attribute("clear:self")
+                .and_then(|a| a.value())
+                .is_some_and(|v| v.split_whitespace().any(|name| name == event.name));
+
+            if handles {
+                self.fire(id, &event.name)?;
+                dispatch.handled.push(id);
+            }
+
+            if let Some(name) = send {
+                let sent = Event::new(name, id);
+                self.send(sent.clone());
+                dispatch.sent.push(sent);
+            }
+
+            if clears {
+                let cleared = self
+                    .instance_of(&EntityRef::Element(id))
+                    .map_or(id, |i| i.element());
+                if self.document.drop(EntityRef::Element(cleared)).is_ok() {
+                    dispatch.cleared.push(cleared);
+                }
+                self.update();
+            }
+        }
+
+        Ok(dispatch)
+    }
+
+    /// Take the events sent since they were last taken, in the order they were sent.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    pub(crate) fn send(&mut self, event: Event) {
+        self.outbox.push(event);
+    }
+}
@@ -0,0 +1,336 @@
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+use thiserror::Error;
+use trax_document::{Diagnostic, DiagnosticCode, NodeId, Severity};
+
+use crate::Runtime;
+
+/// A state an element is in because of the user, which `visible:<state>` modifiers depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Interaction {
+    /// The mouse is over the element.
+    Hover,
+    /// The element receives keyboard input.
+    Focus,
+    /// The element is being pressed.
+    Active,
+}
+
+impl Display for Interaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Interaction::Hover => "hover",
+            Interaction::Focus => "focus",
+            Interaction::Active => "active",
+        })
+    }
+}
+
+impl FromStr for Interaction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "hover" => Interaction::Hover,
+            "focus" => Interaction::Focus,
+            "active" => Interaction::Active,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// An edge or axis of an element or of the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    /// The top edge.
+    Top,
+    /// The bottom edge.
+    Bottom,
+    /// The left edge.
+    Left,
+    /// The right edge.
+    Right,
+    /// The center.
+    Center,
+    /// The horizontal position, like `mouse:x`.
+    X,
+    /// The vertical position, like `mouse:y`.
+    Y,
+}
+
+impl Display for Anchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Anchor::Top => "top",
+            Anchor::Bottom => "bottom",
+            Anchor::Left => "left",
+            Anchor::Right => "right",
+            Anchor::Center => "center",
+            Anchor::X => "x",
+            Anchor::Y => "y",
+        })
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "top" => Anchor::Top,
+            "bottom" => Anchor::Bottom,
+            "left" => Anchor::Left,
+            "right" => Anchor::Right,
+            "center" => Anchor::Center,
+            "x" => Anchor::X,
+            "y" => Anchor::Y,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// What a [`Pin`] anchors an element to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PinTarget {
+    /// The element's parent.
+    Parent,
+    /// The mouse.
+    Mouse,
+}
+
+impl Display for PinTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PinTarget::Parent => "parent",
+            PinTarget::Mouse => "mouse",
+        })
+    }
+}
+
+impl FromStr for PinTarget {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "parent" => PinTarget::Parent,
+            "mouse" => PinTarget::Mouse,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// An error encountered when parsing a [`Pin`].
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum PinError {
+    /// The value isn't written as `target:anchor`.
+    #[error("expected `target:anchor`, like `parent:top`, found `{0}`")]
+    InvalidFormat(String),
+
+    /// The edge or anchor isn't an [`Anchor`].
+    #[error("`{0}` isn't an edge, expected top, bottom, left, right, center, x or y")]
+    UnknownAnchor(String),
+
+    /// The target isn't a [`PinTarget`].
+    #[error("`{0}` can't be pinned to, expected parent or mouse")]
+    UnknownTarget(String),
+}
+
+/// A constraint from a `pin:<edge>="<target>:<anchor>"` property, which places an edge of an
+/// element at an anchor of its target. For example, `pin:bottom="parent:top"` places the bottom
+/// of the element at the top of its parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pin {
+    /// The edge of the element which is placed.
+    pub edge: Anchor,
+    /// What the element is placed against.
+    pub target: PinTarget,
+    /// Where on the target the edge is placed.
+    pub anchor: Anchor,
+}
+
+impl Pin {
+    /// Parse a pin from the local name and value of a `pin:` property.
+    ///
+    /// ```rust
+    /// use trax_runtime::{Anchor, Pin, PinTarget};
+    ///
+    /// assert_eq!(
+    ///     Pin::parse("center", "mouse:x"),
+    ///     Ok(Pin { edge: Anchor::Center, target: PinTarget::Mouse, anchor: Anchor::X })
+    /// );
+    /// assert!(Pin::parse("center", "mouse").is_err());
+    /// ```
+    pub fn parse(edge: &str, value: &str) -> Result<Self, PinError> {
+        let anchor = |s: &str| {
+            s.parse::<Anchor>()
+                .map_err(|_| PinError::UnknownAnchor(s.to_string()))
+        };
+
+        let (target, to) = value
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| PinError::InvalidFormat(value.to_string()))?;
+
+        Ok(Pin {
+            edge: anchor(edge)?,
+            target: target
+                .parse()
+                .map_err(|_| PinError::UnknownTarget(target.to_string()))?,
+            anchor: anchor(to)?,
+        })
+    }
+}
+
+impl Display for Pin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pin:{}=\"{}:{}\"", self.edge, self.target, self.anchor)
+    }
+}
+
+/// The properties of an element which depend on the state of the runtime, for renderers. See
+/// [`Runtime::computed`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Computed {
+    /// Whether the element is shown. Elements aren't shown if they or one of their ancestors
+    /// has the `invisible` modifier, or `visible:<state>` modifiers none of which are met.
+    pub visible: bool,
+    /// Whether the element has the `detach` modifier, i.e. it's laid out on top of the
+    /// document rather than inside its parent.
+    pub detached: bool,
+    /// The element's valid `pin:` properties, in order.
+    pub pins: Vec<Pin>,
+}
+
+impl Runtime<'_> {
+    /// Set whether an element is in an interaction state, like when the mouse enters or leaves
+    /// it.
+    pub fn set_interaction(&mut self, element_id: NodeId, state: Interaction, on: bool) {
+        let states = self.interactions.entry(element_id).or_default();
+        if on {
+            states.insert(state);
+        } else {
+            states.remove(&state);
+            if states.is_empty() {
+                self.interactions.remove(&element_id);
+            }
+        }
+    }
+
+    /// Returns `true` if an element is in an interaction state, either because it was set on the
+    /// element or on one of its ancestors.
+    pub fn has_interaction(&self, element_id: NodeId, state: Interaction) -> bool {
+        let mut current = element_id;
+        loop {
+            if self
+                .interactions
+                .get(&current)
+                .is_some_and(|s| s.contains(&state))
+            {
+                return true;
+            }
+            match self.document.element(current) {
+                Some(element) if current != 0 => current = element.parent(),
+                _ => return false,
+            }
+        }
+    }
+
+    /// The properties of an element which depend on the state of the runtime, or `None` if the
+    /// element doesn't exist.
+    ///
+    /// A `visible:<state>` modifier is met if the element is in that [`Interaction`] state, see
+    /// [`Runtime::has_interaction`]. Invalid `pin:` properties are skipped, and reported by
+    /// [`Runtime::validate`].
+    pub fn computed(&self, element_id: NodeId) -> Option<Computed> {
+        let element = self.document.element(element_id)?;
+
+        let mut visible = true;
+        let mut current = element_id;
+        while visible {
+            let Some(element) = self.document.element(current) else {
+                break;
+            };
+            visible = self.is_shown(current);
+            if current == 0 {
+                break;
+            }
+            current = element.parent();
+        }
+
+        let pins = element
+            .attributes()
+            .filter(|a| a.prefix() == "pin")
+            .filter_map(|a| Pin::parse(a.local(), a.value()?).ok())
+            .collect();
+
+        Some(Computed {
+            visible,
+            detached: element.attribute("detach").is_some_and(|a| a.is_modifier()),
+            pins,
+        })
+    }
+
+    // Returns `false` if an element hides itself, regardless of its ancestors.
+    fn is_shown(&self, id: NodeId) -> bool {
+        let Some(element) = self.document.element(id) else {
+            return false;
+        };
+        if element
+            .attribute("invisible")
+            .is_some_and(|a| a.is_modifier())
+        {
+            return false;
+        }
+
+        let states: HashSet<Option<Interaction>> = element
+            .attributes()
+            .filter(|a| a.prefix() == "visible" && a.is_modifier())
+            .map(|a| a.local().parse().ok())
+            .collect();
+
+        states.is_empty()
+            || states
+                .into_iter()
+                .flatten()
+                .any(|state| self.has_interaction(id, state))
+    }
+
+    // Reports `visible:` modifiers with unknown states and invalid `pin:` properties of an
+    // element, see `Runtime::validate`.
+    pub(crate) fn validate_interactions(&self, id: NodeId, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(element) = self.document.element(id) else {
+            return;
+        };
+
+        for attribute in element.attributes() {
+            let problem = match (attribute.prefix(), attribute.value()) {
+                ("visible", _) if attribute.local().parse::<Interaction>().is_err() => (
+                    DiagnosticCode::UnknownProperty,
+                    format!(
+                        "`{}` isn't an interaction, expected hover, focus or active",
+                        attribute.local()
+                    ),
+                ),
+                ("pin", Some(value)) => match Pin::parse(attribute.local(), value) {
+                    Ok(_) => continue,
+                    Err(error) => (DiagnosticCode::TypeMismatch, error.to_string()),
+                },
+                ("pin", None) => (
+                    DiagnosticCode::TypeMismatch,
+                    format!(
+                        "`pin:{}` needs a value, like `parent:top`",
+                        attribute.local()
+                    ),
+                ),
+                _ => continue,
+            };
+
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                problem.0,
+                self.attribute_span(id, attribute.prefix(), attribute.local()),
+                problem.1,
+            ));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::ops::Range;
+
+use trax_document::{
+    Attribute, ChangeEvent, Diagnostic, DiagnosticCode, EntityRef, EvalContext, Fragment, NodeId,
+    PlacePosition, Severity, Target, Type, Url, Value,
+};
+
+use crate::Runtime;
+
+/// The most items a `for` loop may have. Larger ranges are reported and not expanded.
+pub const MAX_ITERATIONS: usize = 10_000;
+
+/// What a `for` loop iterates over, from its `in` property.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Iterable {
+    /// Each integer in a range, like `1..10`.
+    Range(Range<i64>),
+    /// Each element or property located by a URL, like `document/Blank`. See
+    /// [`EvalContext::resolve_all`].
+    Path(String),
+}
+
+/// A `<for var in="...">` element, which repeats its children for each item of an [`Iterable`].
+///
+/// While the children are copied for an item, the loop variable is set on the `for` element, so
+/// `for.var` and `../.var` resolve to the item. Elements are given by their URL, see
+/// [`Document::url_of`](trax_document::Document::url_of). Afterwards, the variable is a modifier
+/// again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loop<'a> {
+    element: NodeId,
+    var: String,
+    iterable: Iterable,
+    template: Vec<Fragment<'a>>,
+    // the element containing the items of a path, whose children are watched for changes
+    collection: Option<NodeId>,
+    // the elements of the items of a path, whose subtrees are watched for changes
+    items: Vec<NodeId>,
+}
+
+impl<'a> Loop<'a> {
+    /// The id of the `for` element.
+    pub fn element(&self) -> NodeId {
+        self.element
+    }
+
+    /// The name of the loop variable.
+    pub fn var(&self) -> &str {
+        &self.var
+    }
+
+    /// What the loop iterates over.
+    pub fn iterable(&self) -> &Iterable {
+        &self.iterable
+    }
+
+    /// The children of the `for` element in the source, which are copied for each item.
+    pub fn template(&self) -> &[Fragment<'a>] {
+        &self.template
+    }
+}
+
+impl<'a> Runtime<'a> {
+    /// Returns the loop of a `for` element.
+    pub fn for_loop(&self, element_id: NodeId) -> Option<&Loop<'a>> {
+        self.loops.get(&element_id)
+    }
+
+    /// The loops in the document, ordered by the id of their element.
+    pub fn loops(&self) -> impl Iterator<Item = &Loop<'a>> {
+        self.loops.values()
+    }
+
+    // Registers a `for` element as a loop, taking its children as the template, and expands it.
+    pub(crate) fn create_loop(&mut self, id: NodeId) {
+        let Some(element) = self.document.element(id) else {
+            return;
+        };
+
+        let var = element
+            .attributes()
+            .find(|a| a.prefix().is_empty() && a.is_modifier())
+            .map(|a| a.local().to_string());
+        let Some(var) = var else {
+            self.diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                DiagnosticCode::MissingProperty,
+                self.start_tag(id),
+                "`for` requires a loop variable, like `<for item in=\"1..10\">`",
+            ));
+            return;
+        };
+
+        let children: Vec<EntityRef> = element.children().cloned().collect();
+        let Some(iterable) = self.iterable(id) else {
+            return;
+        };
+
+        let template = children
+            .iter()
+            .filter_map(|c| self.document.fragment(c))
+            .collect();
+
+        self.loops.insert(
+            id,
+            Loop {
+                element: id,
+                var,
+                iterable,
+                template,
+                collection: None,
+                items: Vec::new(),
+            },
+        );
+        self.iterate(id);
+    }
+
+    // Parses the `in` property of a `for` element, reporting problems if it's invalid.
+    fn iterable(&mut self, id: NodeId) -> Option<Iterable> {
+        let element = self.document.element(id)?;
+        let span = self
+            .document
+            .element_span(id)
+            .and_then(|s| s.attribute("", "in"))
+            .and_then(|a| a.value.clone());
+
+        let problem = match element.attribute("in").map(|a| a.value()) {
+            Some(Some(text)) => match Value::infer(text) {
+                Value::Range(range)
+                    if range
+                        .end
+                        .checked_sub(range.start)
+                        .is_none_or(|n| n > MAX_ITERATIONS as i64) =>
+                {
+                    Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticCode::TooManyIterations,
+                        span.or_else(|| self.start_tag(id)),
+                        format!("`{text}` has more than {MAX_ITERATIONS} items"),
+                    )
+                }
+                Value::Range(range) => return Some(Iterable::Range(range)),
+                _ if Value::parse(Type::Url, text).is_ok() && Url::parse(text).is_ok() => {
+                    return Some(Iterable::Path(text.trim().to_string()))
+                }
+                _ => Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticCode::TypeMismatch,
+                    span.or_else(|| self.start_tag(id)),
+                    format!("`in` should be a range or a URL, found `{text}`"),
+                ),
+            },
+            Some(None) => Diagnostic::new(
+                Severity::Error,
+                DiagnosticCode::TypeMismatch,
+                self.start_tag(id),
+                "`in` should be a range or a URL, found a modifier",
+            ),
+            None => Diagnostic::new(
+                Severity::Error,
+                DiagnosticCode::MissingProperty,
+                self.start_tag(id),
+                "`for` requires the `in` property",
+            ),
+        };
+
+        self.diagnostics.push(problem);
+        None
+    }
+
+    // Replaces the children of a loop with a copy of its template for each item.
+    pub(crate) fn iterate(&mut self, id: NodeId) {
+        let Some(iterable) = self.iterable(id) else {
+            return;
+        };
+        let Some(l) = self.loops.get_mut(&id) else {
+            return;
+        };
+        l.iterable = iterable.clone();
+        let var = l.var.clone();
+        let template = l.template.clone();
+
+        let children: Vec<EntityRef> = self
+            .document
+            .element(id)
+            .map(|e| e.children().cloned().collect())
+            .unwrap_or_default();
+        for child in children {
+            let _ = self.document.drop(child);
+        }
+
+        let mut collection = None;
+        let mut items = Vec::new();
+        let values: Vec<String> = match &iterable {
+            Iterable::Range(range) => range.clone().map(|i| i.to_string()).collect(),
+            Iterable::Path(path) => {
+                let Ok(url) = Url::parse(path) else {
+                    return;
+                };
+                let context = EvalContext::new(&self.document);
+
+                let mut parent = url.clone();
+                parent.segments.pop();
+                parent.property = None;
+                if let Some(Target::Element(p)) = context.resolve(id, &parent) {
+                    collection = Some(p);
+                }
+
+                context
+                    .resolve_all(id, &url)
+                    .into_iter()
+                    .filter_map(|target| match target {
+                        Target::Element(i) => {
+                            items.push(i);
+                            Some(self.document.url_of(i)?.to_string())
+                        }
+                        Target::Property(i, attribute) => {
+                            items.push(i);
+                            Some(attribute.value().unwrap_or("true").to_string())
+                        }
+                    })
+                    .collect()
+            }
+        };
+
+        for value in values {
+            let _ = self
+                .document
+                .set_attribute(id, Attribute::new("", var.clone(), Some(value)));
+
+            for fragment in template.clone() {
+                if let Ok(EntityRef::Element(child)) =
+                    self.document
+                        .insert_fragment(id, PlacePosition::End, fragment)
+                {
+                    self.expand(child);
+                }
+            }
+        }
+        let _ = self
+            .document
+            .set_attribute(id, Attribute::new("", var, None::<String>));
+
+        if let Some(l) = self.loops.get_mut(&id) {
+            l.collection = collection;
+            l.items = items;
+        }
+    }
+
+    // Returns `true` if a change to the document changes what a loop iterates over.
+    pub(crate) fn affects(&self, l: &Loop, change: &ChangeEvent) -> bool {
+        let node = match change {
+            ChangeEvent::Inserted { parent, .. }
+            | ChangeEvent::Dropped { parent, .. }
+            | ChangeEvent::Moved { parent, .. } => *parent,
+            ChangeEvent::AttributeChanged { element, name } if *element == l.element => {
+                return name == "in"
+            }
+            ChangeEvent::AttributeChanged { element, .. } => *element,
+            ChangeEvent::TextChanged { text } => match self.document.text(*text) {
+                Some(text) => text.parent(),
+                None => return false,
+            },
+        };
+
+        if Some(node) == l.collection {
+            return true;
+        }
+
+        let mut ancestor = node;
+        loop {
+            if l.items.contains(&ancestor) {
+                return true;
+            }
+            match self.document.element(ancestor) {
+                Some(element) if ancestor != 0 => ancestor = element.parent(),
+                _ => return false,
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+/*!
+TRAX runtime, which brings a [`Document`] to life by expanding the constructs the spec gives a
+meaning to, like `class:` templates and their `let:` fields, which `read:` elements show and
+`bind:` elements edit, `for` loops, `eval` and `format` elements, and `action:` elements which
+run when an event is dispatched to an element with an `onEvent:` handler. Modifiers which depend
+on the user, like `visible:hover`, and `pin:` anchors are computed for renderers.
+
+## Example
+
+```rust
+use trax_runtime::Runtime;
+
+let src = r#"<document>
+    <class:Greeting>
+        <let:name String />
+        <Text>Hello!</Text>
+    </class:Greeting>
+    <Greeting name="World" />
+</document>"#;
+
+let runtime = Runtime::new(trax_document::Document::new(src).unwrap());
+let instance = runtime.instances().next().unwrap();
+
+assert_eq!(instance.class(), "Greeting");
+assert_eq!(instance.get("name").unwrap().as_str(), Some("World"));
+```
+
+## Safety
+
+- The library must not panic. Any panic is considered a critical bug
+  and should be reported.
+- The library forbids unsafe code.
+*/
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use trax_document::{ChangeEvent, Diagnostic, Document, EntityRef, NodeId, SubscriptionId, Value};
+
+mod action;
+mod class;
+mod eval;
+mod event;
+mod interaction;
+mod iterate;
+mod state;
+
+pub use action::ActionError;
+pub use class::{Class, Field, Instance};
+pub use event::{Dispatch, Event};
+pub use interaction::{Anchor, Computed, Interaction, Pin, PinError, PinTarget};
+pub use iterate::{Iterable, Loop, MAX_ITERATIONS};
+pub use state::StateError;
+
+/// A [`Document`] along with the state of the constructs in it.
+///
+/// Every change to the document made by the runtime goes through the document's own mutations,
+/// so renderers can follow along with [`Document::subscribe`].
+#[derive(Debug)]
+pub struct Runtime<'a> {
+    document: Document<'a>,
+    classes: HashMap<String, Class<'a>>,
+    instances: BTreeMap<NodeId, Instance>,
+    // uses of classes inside their own instances, which are reported once and never instantiated
+    recursive: HashSet<NodeId>,
+    loops: BTreeMap<NodeId, Loop<'a>>,
+    // the values of `eval` elements, from when they were expanded
+    evals: BTreeMap<NodeId, Value<'static>>,
+    interactions: HashMap<NodeId, HashSet<Interaction>>,
+    diagnostics: Vec<Diagnostic>,
+    outbox: Vec<Event>,
+    // changes to the document since they were last handled, see `Runtime::update`
+    changes: Arc<Mutex<Vec<ChangeEvent>>>,
+    subscription: SubscriptionId,
+}
+
+impl<'a> Runtime<'a> {
+    /// Start running a document, collecting its classes and instantiating them.
+    pub fn new(mut document: Document<'a>) -> Self {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        let subscription = document.subscribe(move |change| {
+            if let Ok(mut changes) = sink.lock() {
+                changes.push(change.clone());
+            }
+        });
+
+        let mut runtime = Self {
+            document,
+            classes: HashMap::new(),
+            instances: BTreeMap::new(),
+            recursive: HashSet::new(),
+            loops: BTreeMap::new(),
+            evals: BTreeMap::new(),
+            interactions: HashMap::new(),
+            diagnostics: Vec::new(),
+            outbox: Vec::new(),
+            changes,
+            subscription,
+        };
+
+        runtime.collect_classes();
+        runtime.expand(0);
+        runtime.take_changes();
+        runtime
+    }
+
+    /// The running document.
+    pub fn document(&self) -> &Document<'a> {
+        &self.document
+    }
+
+    /// The running document, to be changed from outside of the runtime, e.g. by messages from a
+    /// server. Call [`Runtime::update`] after changing it.
+    pub fn document_mut(&mut self) -> &mut Document<'a> {
+        &mut self.document
+    }
+
+    /// Catch up with changes to the document: instances and loops which were dropped are
+    /// forgotten, loops whose items changed are expanded again, and classes used by new elements
+    /// are instantiated.
+    ///
+    /// Changes made by the runtime while updating don't cause another update.
+    pub fn update(&mut self) {
+        let changes = self.take_changes();
+
+        self.forget_dropped();
+        let dirty: Vec<NodeId> = self
+            .loops
+            .values()
+            .filter(|l| changes.iter().any(|c| self.affects(l, c)))
+            .map(|l| l.element())
+            .collect();
+
+        for id in dirty {
+            // expanding an outer loop replaces the loops inside it
+            if self.document.element(id).is_some() {
+                self.iterate(id);
+            }
+        }
+
+        self.forget_dropped();
+        self.expand(0);
+        self.take_changes();
+    }
+
+    /// Call `subscriber` with every change made to the document, including those made by the
+    /// runtime. See [`Document::subscribe`].
+    pub fn subscribe<F: FnMut(&ChangeEvent) + Send + Sync + 'static>(
+        &mut self,
+        subscriber: F,
+    ) -> SubscriptionId {
+        self.document.subscribe(subscriber)
+    }
+
+    /// Stop calling a subscriber. Returns `false` if it wasn't subscribed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.document.unsubscribe(id)
+    }
+
+    /// Stop running the document, returning it as it currently is.
+    pub fn into_document(mut self) -> Document<'a> {
+        self.document.unsubscribe(self.subscription);
+        self.document
+    }
+
+    /// Problems found while running the document, in the order they were found.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    // The ids of an element's child elements, so the document can be changed while visiting them.
+    fn child_elements(&self, id: NodeId) -> Vec<NodeId> {
+        self.document
+            .element(id)
+            .map(|e| {
+                e.children()
+                    .filter_map(|c| match c {
+                        EntityRef::Element(i) => Some(*i),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn take_changes(&mut self) -> Vec<ChangeEvent> {
+        self.changes
+            .lock()
+            .map(|mut changes| std::mem::take(&mut *changes))
+            .unwrap_or_default()
+    }
+
+    fn forget_dropped(&mut self) {
+        let document = &self.document;
+        self.instances
+            .retain(|id, _| document.element(*id).is_some());
+        self.recursive.retain(|id| document.element(*id).is_some());
+        self.loops.retain(|id, _| document.element(*id).is_some());
+        self.evals.retain(|id, _| document.element(*id).is_some());
+        self.interactions
+            .retain(|id, _| document.element(*id).is_some());
+    }
+
+    // Instantiates every class used in the subtree of an element and expands its loops and
+    // `eval` elements. Class definitions aren't expanded, since they're templates, and neither
+    // are actions until they're run.
+    fn expand(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+
+            if matches!(element.prefix(), "class" | "action") {
+                continue;
+            }
+
+            if element.prefix().is_empty()
+                && element.local() == "eval"
+                && !self.evals.contains_key(&id)
+            {
+                self.create_eval(id);
+                continue;
+            }
+
+            // the children of a new loop are expanded with each item
+            if element.prefix().is_empty()
+                && element.local() == "for"
+                && !self.loops.contains_key(&id)
+            {
+                self.create_loop(id);
+                continue;
+            }
+
+            if element.prefix().is_empty()
+                && self.classes.contains_key(element.local())
+                && !self.instances.contains_key(&id)
+                && !self.recursive.contains(&id)
+            {
+                let class = element.local().to_string();
+                self.instantiate(id, &class);
+            }
+
+            stack.extend(self.child_elements(id).into_iter().rev());
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::borrow::Cow;
+
+use thiserror::Error;
+use trax_document::{
+    Attribute, Diagnostic, DiagnosticCode, EntityRef, NodeId, PlacePosition, Severity, Type, Value,
+    ValueError,
+};
+
+use crate::{Event, Instance, Runtime};
+
+/// An error encountered when changing the state of an [`Instance`].
+#[derive(Debug, PartialEq, Error)]
+pub enum StateError {
+    /// The element doesn't use a class.
+    #[error("element {0} isn't an instance of a class")]
+    NotAnInstance(NodeId),
+
+    /// The element isn't a `bind:` element inside an instance.
+    #[error("element {0} isn't bound to a field")]
+    NotABinding(NodeId),
+
+    /// The class doesn't have the field.
+    #[error("`{class}` doesn't have a `{field}` field")]
+    UnknownField {
+        /// The name of the class.
+        class: String,
+        /// The name of the field.
+        field: String,
+    },
+
+    /// The value doesn't have the type of the field.
+    #[error("`{field}` should be {expected}, found {found}")]
+    TypeMismatch {
+        /// The name of the field.
+        field: String,
+        /// The type of the field.
+        expected: Type,
+        /// The type of the value.
+        found: Type,
+    },
+
+    /// Input to a `bind:` element isn't a valid value of its field's type.
+    #[error(transparent)]
+    InvalidValue(#[from] ValueError),
+}
+
+impl<'a> Runtime<'a> {
+    /// Returns the instance whose class's body a node was copied from, i.e. the instance whose
+    /// fields `read:` and `bind:` elements refer to.
+    ///
+    /// Nodes which are children of an instance's element in the source belong to the instance
+    /// the element is in, rather than the instance itself.
+    pub fn instance_of(&self, node: &EntityRef) -> Option<&Instance> {
+        let mut child = node.clone();
+        let mut parent = self.parent_of(node)?;
+
+        loop {
+            if let Some(instance) = self.instances.get(&parent) {
+                if instance.owns(&child) {
+                    return Some(instance);
+                }
+            }
+            if parent == 0 {
+                return None;
+            }
+
+            child = EntityRef::Element(parent);
+            parent = self.document.element(parent)?.parent();
+        }
+    }
+
+    /// Set a field of an instance. `read:` and `bind:` elements showing the field are updated,
+    /// and the field is written to the instance's element as a property.
+    ///
+    /// Ints are converted to floats for `float` fields, and any field can be set to
+    /// `undefined`.
+    pub fn set(
+        &mut self,
+        element_id: NodeId,
+        field: &str,
+        value: Value<'static>,
+    ) -> Result<(), StateError> {
+        let instance = self
+            .instances
+            .get(&element_id)
+            .ok_or(StateError::NotAnInstance(element_id))?;
+        let class = &self.classes[&instance.class];
+        let ty = class
+            .field(field)
+            .ok_or_else(|| StateError::UnknownField {
+                class: class.name.clone(),
+                field: field.to_string(),
+            })?
+            .ty;
+
+        let value = match (ty, value) {
+            (Type::Float, Value::Int(i)) => Value::Float(i as f64),
+            (Type::Any, value) => value,
+            (_, Value::Undefined) => Value::Undefined,
+            (ty, value) if value.ty() == ty => value,
+            (ty, value) => {
+                return Err(StateError::TypeMismatch {
+                    field: field.to_string(),
+                    expected: ty,
+                    found: value.ty(),
+                })
+            }
+        };
+
+        self.write_property(element_id, field, &value);
+        if let Some(instance) = self.instances.get_mut(&element_id) {
+            instance.state.insert(field.to_string(), value);
+        }
+        self.render_fields(element_id, Some(field));
+        self.update();
+        Ok(())
+    }
+
+    /// Handle input to a `bind:` element, like a text box or a checkbox, by setting its field to
+    /// the input parsed as the field's type (`true` or `false` for `bool` fields).
+    ///
+    /// If the element has a `send` property, the event it names is sent. See
+    /// [`Runtime::take_events`].
+    pub fn input(&mut self, bind_id: NodeId, input: &str) -> Result<(), StateError> {
+        let element = self
+            .document
+            .element(bind_id)
+            .filter(|e| e.prefix() == "bind")
+            .ok_or(StateError::NotABinding(bind_id))?;
+        let field = element.local().to_string();
+        let send = element
+            .attribute("send")
+            .and_then(|a| a.value())
+            .map(str::to_string);
+
+        let instance = self
+            .instance_of(&EntityRef::Element(bind_id))
+            .ok_or(StateError::NotABinding(bind_id))?;
+        let class = &self.classes[&instance.class];
+        let ty = class
+            .field(&field)
+            .ok_or_else(|| StateError::UnknownField {
+                class: class.name.clone(),
+                field: field.clone(),
+            })?
+            .ty;
+
+        let instance = instance.element;
+
+        let value = Value::parse(ty, input)?.into_owned();
+        self.set(instance, &field, value)?;
+
+        if let Some(name) = send {
+            self.send(Event::new(name, bind_id));
+        }
+        Ok(())
+    }
+
+    // Updates the `read:` and `bind:` elements of an instance showing a field, or every field.
+    //
+    // `read:field` shows the value as its text, and `bind:field` as its `value` property.
+    pub(crate) fn render_fields(&mut self, element_id: NodeId, only: Option<&str>) {
+        let Some(instance) = self.instances.get(&element_id) else {
+            return;
+        };
+
+        let mut bound = Vec::new();
+        let mut stack: Vec<NodeId> = instance
+            .body
+            .iter()
+            .rev()
+            .filter_map(|c| match c {
+                EntityRef::Element(i) => Some(*i),
+                _ => None,
+            })
+            .collect();
+
+        while let Some(id) = stack.pop() {
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+
+            if matches!(element.prefix(), "read" | "bind")
+                && only.is_none_or(|field| field == element.local())
+            {
+                bound.push(id);
+            }
+
+            // the bodies of nested instances are theirs
+            let nested = self.instances.get(&id);
+            stack.extend(
+                self.child_elements(id)
+                    .into_iter()
+                    .rev()
+                    .filter(|c| nested.is_none_or(|n| !n.owns(&EntityRef::Element(*c)))),
+            );
+        }
+
+        for id in bound {
+            let Some(element) = self.document.element(id) else {
+                continue;
+            };
+            let is_read = element.prefix() == "read";
+            let field = element.local().to_string();
+
+            let instance = &self.instances[&element_id];
+            let Some(value) = instance.get(&field).cloned() else {
+                // reported once, when the instance is created
+                if only.is_none() {
+                    self.diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        DiagnosticCode::UnknownProperty,
+                        self.start_tag(id),
+                        format!("`{}` doesn't have a `{field}` field", instance.class),
+                    ));
+                }
+                continue;
+            };
+
+            if is_read {
+                self.render_read(id, &value);
+            } else {
+                self.render_bind(id, &value);
+            }
+        }
+    }
+
+    fn render_read(&mut self, id: NodeId, value: &Value) {
+        let texts: Vec<usize> = self
+            .document
+            .element(id)
+            .into_iter()
+            .flat_map(|e| e.children())
+            .filter_map(|c| match c {
+                EntityRef::Text(i) => Some(*i),
+                _ => None,
+            })
+            .collect();
+
+        let content = match value {
+            Value::Undefined | Value::Void => None,
+            value => Some(value.to_string()),
+        };
+
+        match (texts.first(), content) {
+            (Some(&text), Some(content)) => {
+                if self.document.text(text).map(|t| t.content()) != Some(content.as_str()) {
+                    let _ = self.document.set_text(text, content);
+                }
+            }
+            (None, Some(content)) => {
+                let _ = self.document.insert_text(id, PlacePosition::End, content);
+            }
+            (Some(&text), None) => {
+                let _ = self.document.drop(EntityRef::Text(text));
+            }
+            (None, None) => (),
+        }
+    }
+
+    fn render_bind(&mut self, id: NodeId, value: &Value) {
+        match value {
+            Value::Undefined | Value::Void => {
+                let _ = self.document.remove_attribute(id, "", "value");
+            }
+            value => {
+                let _ = self.document.set_attribute(
+                    id,
+                    Attribute::new("", "value", Some(Cow::Owned(value.to_string()))),
+                );
+            }
+        }
+    }
+
+    // Writes a field to the instance's element, replacing any `asRef` or `asEval` property
+    // which set it.
+    fn write_property(&mut self, element_id: NodeId, field: &str, value: &Value) {
+        for prefix in ["asRef", "asEval"] {
+            let _ = self.document.remove_attribute(element_id, prefix, field);
+        }
+
+        let attribute = match value {
+            Value::Bool(true) => Attribute::new("", field.to_string(), None::<&str>),
+            Value::Bool(false) | Value::Undefined | Value::Void => {
+                let _ = self.document.remove_attribute(element_id, "", field);
+                return;
+            }
+            // only the instance can hold structure
+            Value::Element(_) | Value::Attribute(_) | Value::Modifier(_) => return,
+            value => Attribute::new("", field.to_string(), Some(value.to_string())),
+        };
+
+        let _ = self.document.set_attribute(element_id, attribute);
+    }
+
+    pub(crate) fn parent_of(&self, node: &EntityRef) -> Option<NodeId> {
+        match node {
+            EntityRef::Element(id) => self.document.element(*id).map(|e| e.parent()),
+            EntityRef::Text(id) => self.document.text(*id).map(|t| t.parent()),
+            EntityRef::Comment(id) => self.document.comment(*id).map(|c| c.parent()),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Document, EntityRef, ModifyEntityError, Value};
+    use trax_runtime::{ActionError, Runtime};
+
+    use crate::{find_all, render};
+
+    const CALCULATOR: &str = include_str!("../../../../doc/calculator.trax");
+
+    #[test]
+    fn calculator() {
+        let mut runtime = Runtime::new(Document::new(CALCULATOR).unwrap());
+        assert!(runtime.diagnostics().is_empty());
+
+        let buttons = find_all(&runtime, 0, "Button");
+        let labels: Vec<_> = buttons
+            .iter()
+            .map(|b| runtime.evaluate(*b, "./eval").unwrap())
+            .collect();
+        assert_eq!(labels, (1..10).map(Value::Int).collect::<Vec<_>>());
+
+        let format = find_all(&runtime, 0, "format")[0];
+        assert_eq!(runtime.value_of(format), Ok(Value::Str("\u{a0}".into())));
+
+        runtime.fire(buttons[2], "click").unwrap();
+        runtime.fire(buttons[4], "click").unwrap();
+
+        let blank = find_all(&runtime, 0, "Blank")[0];
+        assert_eq!(render(&runtime, blank), "<Blank invisible>35</Blank>");
+        assert_eq!(runtime.value_of(blank), Ok(Value::Int(35)));
+        assert_eq!(
+            render(&runtime, format),
+            "<format><for var in=\"document/Blank\"><eval target=\"../.var\">35</eval>\u{a0}</for></format>"
+        );
+        assert_eq!(runtime.value_of(format), Ok(Value::Str("35\u{a0}".into())));
+
+        // the actions themselves are left as they were
+        assert_eq!(
+            render(&runtime, buttons[0]),
+            r#"<Button onEvent:click="./action:insert"><action:insert target="document/Blank#0" end><eval target="../../eval" /></action:insert><eval target="document/Card/Blank#0/Blank#0/for.var">1</eval></Button>"#
+        );
+    }
+
+    #[test]
+    fn eval() {
+        let src = r#"<document><Sum><add>1 2</add></Sum><eval target="document/Sum/add" /><eval target="document/Sum" /><eval target="document/Missing" /><eval /></document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            render(&runtime, 0),
+            r#"<document><Sum><add>1 2</add></Sum><eval target="document/Sum/add">3</eval><eval target="document/Sum">1 2</eval><eval target="document/Missing" /><eval /></document>"#
+        );
+        assert_eq!(runtime.value_of(3), Ok(Value::Int(3)));
+        assert_eq!(runtime.value_of(5), Ok(Value::Undefined));
+        assert_eq!(runtime.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn insert() {
+        let src = r#"<document><List><a /><b /></List><action:insert target="document/List" start index="1"><c /><d /></action:insert><action:insert target="document/List" index="0"><e /></action:insert><action:insert target="document/List/b" /><action:insert target="document/List"><List /></action:insert></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+        let actions = [4, 7, 9, 10];
+
+        runtime.run_action(actions[0]).unwrap();
+        assert_eq!(render(&runtime, 1), "<List><a /><c /><d /><b /></List>");
+
+        runtime.run_action(actions[1]).unwrap();
+        assert_eq!(render(&runtime, 1), "<List><e /><c /><d /><b /></List>");
+
+        runtime.run_action(actions[2]).unwrap();
+        assert_eq!(render(&runtime, 1), "<List><e /><c /><d /></List>");
+
+        runtime.run_action(actions[3]).unwrap();
+        assert!(runtime.document().element(1).is_none());
+        assert_eq!(
+            render(&runtime, 0).split("<action").next(),
+            Some("<document><List />")
+        );
+    }
+
+    #[test]
+    fn insert_prop() {
+        let src = r#"<document><Todo /><action:insertProp target="document/Todo" key="done" /><action:insertProp target="document/Todo" key="title" value="Laundry" start /><action:insertProp target="document/Todo" value="Dishes" start /><action:insertProp target="document/Todo" end /><action:insertProp target="document/Todo" /></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+
+        runtime.run_action(2).unwrap();
+        runtime.run_action(3).unwrap();
+        assert_eq!(render(&runtime, 1), r#"<Todo title="Laundry" done />"#);
+
+        runtime.run_action(4).unwrap();
+        runtime.run_action(5).unwrap();
+        assert_eq!(render(&runtime, 1), r#"<Todo title="Dishes" />"#);
+
+        runtime.run_action(6).unwrap();
+        assert_eq!(render(&runtime, 1), "<Todo />");
+        assert_eq!(
+            runtime.run_action(5),
+            Err(ActionError::Modify(ModifyEntityError::IndexOutOfRange(
+                EntityRef::Element(1),
+                0,
+                0
+            )))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let src = r#"<document><Button onEvent:click="./action:missing" onEvent:hover="./Label"><Label /></Button><action:remove target="document" /><action:insert target="document/Nothing" /></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            runtime.fire(1, "click"),
+            Err(ActionError::TargetNotFound("./action:missing".to_string()))
+        );
+        assert_eq!(runtime.fire(1, "hover"), Err(ActionError::NotAnAction(2)));
+        assert_eq!(runtime.fire(1, "press"), Ok(()));
+        assert_eq!(
+            runtime.run_action(3),
+            Err(ActionError::UnknownAction("remove".to_string()))
+        );
+        assert_eq!(
+            runtime.run_action(4),
+            Err(ActionError::TargetNotFound("document/Nothing".to_string()))
+        );
+    }
+}
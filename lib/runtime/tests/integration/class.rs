@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{DiagnosticCode, Document, Schema, Type, Value};
+    use trax_runtime::{Field, Runtime};
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+    const COMPONENTS: &str = include_str!("../../../../doc/components.trax");
+
+    fn codes(runtime: &Runtime) -> Vec<DiagnosticCode> {
+        runtime.diagnostics().iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn todo() {
+        let runtime = Runtime::new(Document::new(TODO).unwrap());
+
+        let class = runtime.class("Todo").unwrap();
+        assert_eq!(
+            class.fields,
+            [
+                Field::new("title", Type::Str),
+                Field::new("done", Type::Bool),
+                Field::new("created", Type::Str),
+                Field::new("desc", Type::Str),
+            ]
+        );
+        assert_eq!(class.body.len(), 1);
+
+        let instances: Vec<_> = runtime.instances().collect();
+        assert_eq!(instances.len(), 2);
+        assert_eq!(
+            instances[0].get("title"),
+            Some(&Value::Str("Do Laundry".into()))
+        );
+        assert_eq!(instances[0].get("done"), Some(&Value::Bool(true)));
+        assert_eq!(instances[0].get("desc"), Some(&Value::Undefined));
+        assert_eq!(instances[1].get("done"), Some(&Value::Bool(false)));
+        assert_eq!(
+            instances[1].get("desc"),
+            Some(&Value::Str("gonna take a while".into()))
+        );
+
+        // only the first todo is missing its description
+        assert_eq!(codes(&runtime), [DiagnosticCode::MissingProperty]);
+        let span = runtime.diagnostics()[0].span.clone().unwrap();
+        assert!(TODO[span].starts_with(r#"<Todo title="Do Laundry""#));
+    }
+
+    #[test]
+    fn instantiate() {
+        let src = r#"<document>
+    <class:Labelled>
+        <let:label String />
+        /* copied into each instance */
+        <Text>Label</Text>
+    </class:Labelled>
+    <Labelled label="a" />
+    <Labelled label="b"><Icon /></Labelled>
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            runtime.document().into_string(),
+            r#"<document>
+	<class:Labelled>
+		<let:label String />
+		/* copied into each instance */
+		<Text>
+			Label
+		</Text>
+	</class:Labelled>
+	<Labelled label="a">
+		/* copied into each instance */
+		<Text>
+			Label
+		</Text>
+	</Labelled>
+	<Labelled label="b">
+		<Icon />
+		/* copied into each instance */
+		<Text>
+			Label
+		</Text>
+	</Labelled>
+</document>
+"#
+        );
+        assert_eq!(runtime.instance(5).unwrap().body().len(), 2);
+        assert!(runtime.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn nested() {
+        let src = r#"<document>
+    <class:Inner><let:n Int /><Leaf /></class:Inner>
+    <class:Outer><Inner n="1" /><Inner asEval:n="../../add" /></class:Outer>
+    <Outer />
+    <add> 1 2 </add>
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        let values: Vec<_> = runtime
+            .instances()
+            .filter(|i| i.class() == "Inner")
+            .map(|i| i.get("n").cloned())
+            .collect();
+        assert_eq!(values, [Some(Value::Int(1)), Some(Value::Int(3))]);
+        assert!(runtime.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn invalid_fields() {
+        let src = r#"<document>
+    <class:A>
+        <let:untyped />
+        <let:unknown Number />
+        <let:ambiguous Int Float />
+        <let:ok Int />
+        <let:ok Float />
+    </class:A>
+    <class:A />
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            runtime.class("A").unwrap().fields,
+            [Field::new("ok", Type::Int)]
+        );
+        assert_eq!(
+            codes(&runtime),
+            [
+                DiagnosticCode::InvalidField,
+                DiagnosticCode::InvalidField,
+                DiagnosticCode::InvalidField,
+                DiagnosticCode::InvalidField,
+                DiagnosticCode::DuplicateClass,
+            ]
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let src = r#"<document>
+    <class:Counter><let:count Int /><let:shown Bool /></class:Counter>
+    <Counter count="many" extra="1" asRef:other="." pin:top="parent:top" />
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        let diagnostics = runtime.diagnostics();
+        assert_eq!(
+            codes(&runtime),
+            [
+                DiagnosticCode::TypeMismatch,
+                DiagnosticCode::UnknownProperty,
+                DiagnosticCode::UnknownProperty,
+            ]
+        );
+        assert_eq!(&src[diagnostics[0].span.clone().unwrap()], "many");
+        assert_eq!(&src[diagnostics[1].span.clone().unwrap()], r#"extra="1""#);
+        assert_eq!(
+            diagnostics[2].message,
+            "`Counter` doesn't have a `other` field"
+        );
+        assert_eq!(
+            runtime.instance(4).unwrap().get("count"),
+            Some(&Value::Undefined)
+        );
+    }
+
+    #[test]
+    fn recursive() {
+        let src = r#"<document>
+    <class:Tree><Tree /></class:Tree>
+    <Tree />
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(runtime.instances().count(), 1);
+        assert_eq!(codes(&runtime), [DiagnosticCode::RecursiveClass]);
+    }
+
+    #[test]
+    fn recursive_reported_once() {
+        let src = r#"<document><class:A><let:x Int /><A x="1" /></class:A><A x="2" /></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+
+        runtime.update();
+        runtime.update();
+        assert_eq!(codes(&runtime), [DiagnosticCode::RecursiveClass]);
+    }
+
+    #[test]
+    fn unknown_classes() {
+        let runtime = Runtime::new(Document::new(TODO).unwrap());
+        let schema = Schema::parse(COMPONENTS).unwrap();
+
+        let unknown: Vec<_> = runtime
+            .validate(&schema)
+            .into_iter()
+            .inspect(|d| assert_eq!(d.code, DiagnosticCode::UnknownClass))
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            unknown,
+            [
+                "`Head` isn't a class or a known element",
+                "`H1` isn't a class or a known element",
+                "`Body` isn't a class or a known element",
+            ]
+        );
+    }
+}
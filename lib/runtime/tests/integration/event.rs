@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::Document;
+    use trax_runtime::{ActionError, Dispatch, Event, Runtime};
+
+    use crate::{find_all, render};
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+
+    #[test]
+    fn todo() {
+        let mut runtime = Runtime::new(Document::new(TODO).unwrap());
+        let todos: Vec<_> = runtime.instances().map(|i| i.element()).collect();
+        let [update, delete] = find_all(&runtime, todos[1], "Button")[..] else {
+            panic!()
+        };
+
+        assert_eq!(
+            runtime.dispatch(Event::new("click", update)),
+            Ok(Dispatch {
+                sent: vec![Event::new("click", update)],
+                ..Default::default()
+            })
+        );
+        assert!(runtime.document().element(todos[1]).is_some());
+
+        assert_eq!(
+            runtime.dispatch(Event::new("click", delete)),
+            Ok(Dispatch {
+                sent: vec![Event::new("click", delete)],
+                cleared: vec![todos[1]],
+                ..Default::default()
+            })
+        );
+        assert!(runtime.document().element(todos[1]).is_none());
+        assert_eq!(runtime.instances().count(), 1);
+
+        assert_eq!(
+            runtime.take_events(),
+            [Event::new("click", update), Event::new("click", delete)]
+        );
+    }
+
+    #[test]
+    fn other_events() {
+        let mut runtime = Runtime::new(Document::new(TODO).unwrap());
+        let todo = runtime.instances().next().unwrap().element();
+        let delete = find_all(&runtime, todo, "Button")[1];
+
+        assert_eq!(
+            runtime.dispatch(Event::new("hover", delete)),
+            Ok(Dispatch::default())
+        );
+        assert!(runtime.document().element(todo).is_some());
+        assert!(runtime.take_events().is_empty());
+    }
+
+    #[test]
+    fn bubbling() {
+        let src = r#"<document><List onEvent:click="./action:insert"><action:insert target=".." end><Item /></action:insert><Row onEvent:click="./action:insertProp" clear:self="remove"><action:insertProp target=".." key="clicked" /><Label /></Row></List></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+        let label = 6;
+
+        assert_eq!(
+            runtime.dispatch(Event::new("click", label)),
+            Ok(Dispatch {
+                handled: vec![4, 1],
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            render(&runtime, 4),
+            r#"<Row onEvent:click="./action:insertProp" clear:self="remove" clicked><action:insertProp target=".." key="clicked" /><Label /></Row>"#
+        );
+        assert_eq!(find_all(&runtime, 0, "Item").len(), 2);
+
+        assert_eq!(
+            runtime.dispatch(Event::new("remove", label)),
+            Ok(Dispatch {
+                cleared: vec![4],
+                ..Default::default()
+            })
+        );
+        assert!(runtime.document().element(4).is_none());
+
+        // dispatching to an element which doesn't exist does nothing
+        assert_eq!(
+            runtime.dispatch(Event::new("click", label)),
+            Ok(Dispatch::default())
+        );
+    }
+
+    #[test]
+    fn failing_handler() {
+        let src = r#"<document><Button onEvent:click="./action:missing" /></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            runtime.dispatch(Event::new("click", 1)),
+            Err(ActionError::TargetNotFound("./action:missing".to_string()))
+        );
+    }
+}
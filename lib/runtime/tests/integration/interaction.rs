@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{DiagnosticCode, Document, Schema};
+    use trax_runtime::{Anchor, Computed, Interaction, Pin, PinError, PinTarget, Runtime};
+
+    use crate::find;
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+
+    #[test]
+    fn tooltip() {
+        let mut runtime = Runtime::new(Document::new(TODO).unwrap());
+        let todo = runtime.instances().next().unwrap().element();
+        let card = find(&runtime, todo, "Card");
+        let tooltip = find(&runtime, card, "Tooltip");
+
+        let hidden = Computed {
+            visible: false,
+            detached: true,
+            pins: vec![
+                Pin {
+                    edge: Anchor::Bottom,
+                    target: PinTarget::Parent,
+                    anchor: Anchor::Top,
+                },
+                Pin {
+                    edge: Anchor::Center,
+                    target: PinTarget::Mouse,
+                    anchor: Anchor::X,
+                },
+            ],
+        };
+        assert_eq!(runtime.computed(tooltip), Some(hidden.clone()));
+
+        runtime.set_interaction(card, Interaction::Hover, true);
+        assert!(runtime.has_interaction(tooltip, Interaction::Hover));
+        assert!(!runtime.has_interaction(todo, Interaction::Hover));
+        assert_eq!(
+            runtime.computed(tooltip),
+            Some(Computed {
+                visible: true,
+                ..hidden.clone()
+            })
+        );
+
+        runtime.set_interaction(card, Interaction::Focus, true);
+        runtime.set_interaction(card, Interaction::Hover, false);
+        assert_eq!(runtime.computed(tooltip), Some(hidden));
+
+        assert_eq!(
+            runtime.computed(card),
+            Some(Computed {
+                visible: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(runtime.computed(1000), None);
+    }
+
+    #[test]
+    fn invisible() {
+        let src = r#"<document><Blank invisible><Text visible:hover /></Blank><Text visible:focus visible:active /></document>"#;
+        let mut runtime = Runtime::new(Document::new(src).unwrap());
+
+        runtime.set_interaction(2, Interaction::Hover, true);
+        assert!(!runtime.computed(1).unwrap().visible);
+        assert!(!runtime.computed(2).unwrap().visible);
+
+        assert!(!runtime.computed(3).unwrap().visible);
+        runtime.set_interaction(0, Interaction::Active, true);
+        assert!(runtime.computed(3).unwrap().visible);
+    }
+
+    #[test]
+    fn pins() {
+        assert_eq!(
+            Pin::parse("bottom", "parent:top").unwrap().to_string(),
+            r#"pin:bottom="parent:top""#
+        );
+        assert_eq!(
+            Pin::parse("middle", "parent:top"),
+            Err(PinError::UnknownAnchor("middle".to_string()))
+        );
+        assert_eq!(
+            Pin::parse("top", "sibling:top"),
+            Err(PinError::UnknownTarget("sibling".to_string()))
+        );
+        assert_eq!(
+            Pin::parse("top", "parent"),
+            Err(PinError::InvalidFormat("parent".to_string()))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let src = r#"<document><Text visible:hover visible:pressed pin:top="parent:top" pin:left="mouse" pin:right /></document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        let diagnostics = runtime.validate(&Schema::default());
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            [
+                DiagnosticCode::UnknownClass,
+                DiagnosticCode::UnknownProperty,
+                DiagnosticCode::TypeMismatch,
+                DiagnosticCode::TypeMismatch,
+            ]
+        );
+        assert_eq!(
+            diagnostics[2].message,
+            "expected `target:anchor`, like `parent:top`, found `mouse`"
+        );
+        assert_eq!(diagnostics[1].span, Some(30..45));
+    }
+}
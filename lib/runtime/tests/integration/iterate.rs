@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use trax_document::{Attribute, DiagnosticCode, Document, PlacePosition};
+    use trax_runtime::{Iterable, Runtime, MAX_ITERATIONS};
+
+    use crate::render;
+
+    const NUM: &str = "<class:Num><let:n Int /><read:n /></class:Num>";
+
+    #[test]
+    fn range() {
+        let src = format!(
+            r#"<document>{NUM}<Row><for i in="1..4"><Num asEval:n="../.i" /></for></Row></document>"#
+        );
+        let runtime = Runtime::new(Document::new(&src).unwrap());
+
+        assert_eq!(
+            render(&runtime, 4),
+            r#"<Row><for i in="1..4"><Num asEval:n="../.i"><read:n>1</read:n></Num><Num asEval:n="../.i"><read:n>2</read:n></Num><Num asEval:n="../.i"><read:n>3</read:n></Num></for></Row>"#
+        );
+
+        let l = runtime.for_loop(5).unwrap();
+        assert_eq!(l.var(), "i");
+        assert_eq!(l.iterable(), &Iterable::Range(1..4));
+        assert_eq!(l.template().len(), 1);
+        assert!(runtime.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn absolute() {
+        let src = format!(
+            r#"<document>{NUM}<Grid><for var in="1..3"><Num asEval:n="document/Grid/for.var" /></for></Grid></document>"#
+        );
+        let runtime = Runtime::new(Document::new(&src).unwrap());
+
+        let values: Vec<_> = runtime
+            .instances()
+            .map(|i| i.get("n").unwrap().to_string())
+            .collect();
+        assert_eq!(values, ["1", "2"]);
+    }
+
+    #[test]
+    fn nested() {
+        let src = r#"<document><for row in="0..2"><for col in="0..2"><Cell asRef:row="../../.row" asRef:col="../.col" /></for></for></document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(runtime.loops().count(), 3);
+        assert_eq!(runtime.document().into_string().matches("<Cell").count(), 4);
+    }
+
+    #[test]
+    fn path() {
+        let src = format!(
+            r#"<document>{NUM}<Items><Item n="5" /><Other /><Item n="8" /></Items><List><for n in="document/Items/Item.n"><Num asEval:n="../.n" /></for></List></document>"#
+        );
+        let mut runtime = Runtime::new(Document::new(&src).unwrap());
+        let numbers = |runtime: &Runtime| -> Vec<String> {
+            runtime
+                .instances()
+                .map(|i| i.get("n").unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(numbers(&runtime), ["5", "8"]);
+
+        // adding an item expands the loop again
+        runtime
+            .document_mut()
+            .insert(
+                4,
+                PlacePosition::End,
+                "",
+                "Item",
+                [Attribute::new("", "n", Some("13"))],
+            )
+            .unwrap();
+        runtime.update();
+        assert_eq!(numbers(&runtime), ["5", "8", "13"]);
+
+        // so does changing one
+        runtime
+            .document_mut()
+            .set_attribute(5, Attribute::new("", "n", Some("3")))
+            .unwrap();
+        runtime.update();
+        assert_eq!(numbers(&runtime), ["3", "8", "13"]);
+
+        // but changes elsewhere don't
+        let before = runtime.instances().next().unwrap().element();
+        runtime
+            .document_mut()
+            .insert(0, PlacePosition::End, "", "Unrelated", [])
+            .unwrap();
+        runtime.update();
+        assert_eq!(runtime.instances().next().unwrap().element(), before);
+    }
+
+    #[test]
+    fn elements() {
+        let src = r#"<document><Items><Item /><Item /></Items><for item in="document/Items/Item"><Ref asRef:to="../.item" /></for></document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            render(&runtime, 4),
+            r#"<for item in="document/Items/Item"><Ref asRef:to="../.item" /><Ref asRef:to="../.item" /></for>"#
+        );
+        assert_eq!(
+            runtime.document().url_of(3).unwrap().to_string(),
+            "document/Items/Item#1"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let src = r#"<document>
+    <for in="1..3"><A /></for>
+    <for i><A /></for>
+    <for i in="not a url"><A /></for>
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        let codes: Vec<_> = runtime.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            [
+                DiagnosticCode::MissingProperty,
+                DiagnosticCode::MissingProperty,
+                DiagnosticCode::TypeMismatch,
+            ]
+        );
+        assert_eq!(
+            &src[runtime.diagnostics()[2].span.clone().unwrap()],
+            "not a url"
+        );
+        assert_eq!(runtime.loops().count(), 0);
+    }
+
+    #[test]
+    fn too_many_iterations() {
+        let src = r#"<document><for i in="0..1000000000"><A /></for></document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        let diagnostic = &runtime.diagnostics()[0];
+        assert_eq!(diagnostic.code, DiagnosticCode::TooManyIterations);
+        assert_eq!(&src[diagnostic.span.clone().unwrap()], "0..1000000000");
+        assert_eq!(runtime.loops().count(), 0);
+
+        let src = format!(r#"<document><for i in="0..{MAX_ITERATIONS}"><A /></for></document>"#);
+        let runtime = Runtime::new(Document::new(&src).unwrap());
+        assert!(runtime.diagnostics().is_empty());
+        assert_eq!(
+            runtime.document().element(1).unwrap().children().count(),
+            MAX_ITERATIONS
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use trax_document::{EntityRef, NodeId};
+use trax_runtime::Runtime;
+
+mod action;
+mod class;
+mod event;
+mod interaction;
+mod iterate;
+mod state;
+
+// The markup of an element, as the runtime left it.
+fn render(runtime: &Runtime, id: NodeId) -> String {
+    runtime
+        .document()
+        .fragment(&EntityRef::Element(id))
+        .unwrap()
+        .to_string()
+}
+
+// The elements in the subtree of `from` named `name`, like `Button` or `read:title`, in document
+// order. Class definitions below `from` are skipped, since they're templates.
+fn find_all(runtime: &Runtime, from: NodeId, name: &str) -> Vec<NodeId> {
+    let document = runtime.document();
+    let mut found = Vec::new();
+    let mut stack = vec![from];
+
+    while let Some(id) = stack.pop() {
+        let element = document.element(id).unwrap();
+        if element.prefix() == "class" && id != from {
+            continue;
+        }
+        let full = match element.prefix() {
+            "" => element.local().to_string(),
+            prefix => format!("{prefix}:{}", element.local()),
+        };
+        if full == name && id != from {
+            found.push(id);
+        }
+
+        let children: Vec<_> = element.children().cloned().collect();
+        stack.extend(children.into_iter().rev().filter_map(|c| match c {
+            EntityRef::Element(i) => Some(i),
+            _ => None,
+        }));
+    }
+    found
+}
+
+// The first element named `name` in the subtree of `from`, in document order.
+fn find(runtime: &Runtime, from: NodeId, name: &str) -> NodeId {
+    find_all(runtime, from, name)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| panic!("`{name}` not found"))
+}
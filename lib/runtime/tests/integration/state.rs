@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use pretty_assertions::assert_eq;
+    use trax_document::{ChangeEvent, Document, EntityRef, NodeId, Type, Value};
+    use trax_runtime::{Event, Runtime, StateError};
+
+    use crate::{find, render};
+
+    const TODO: &str = include_str!("../../../../doc/todo.trax");
+
+    fn todos(runtime: &Runtime) -> Vec<NodeId> {
+        runtime.instances().map(|i| i.element()).collect()
+    }
+
+    fn value_of(runtime: &Runtime, id: NodeId) -> Option<String> {
+        let element = runtime.document().element(id).unwrap();
+        element.get_str("value").unwrap().map(str::to_string)
+    }
+
+    #[test]
+    fn initial() {
+        let runtime = Runtime::new(Document::new(TODO).unwrap());
+        let [first, second] = todos(&runtime)[..] else {
+            panic!()
+        };
+
+        assert_eq!(
+            render(&runtime, find(&runtime, first, "read:created")),
+            "<read:created>2024-01-22 13:32:00</read:created>"
+        );
+        assert_eq!(
+            value_of(&runtime, find(&runtime, first, "bind:done")).as_deref(),
+            Some("true")
+        );
+        assert_eq!(value_of(&runtime, find(&runtime, first, "bind:desc")), None);
+        assert_eq!(
+            value_of(&runtime, find(&runtime, second, "bind:done")).as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            value_of(&runtime, find(&runtime, second, "bind:desc")).as_deref(),
+            Some("gonna take a while")
+        );
+
+        // the definition is a template, so it isn't rendered
+        let class = runtime.class("Todo").unwrap().element;
+        assert_eq!(
+            render(&runtime, find(&runtime, class, "read:created")),
+            "<read:created />"
+        );
+    }
+
+    #[test]
+    fn input() {
+        let mut runtime = Runtime::new(Document::new(TODO).unwrap());
+        let first = todos(&runtime)[0];
+        let desc = find(&runtime, first, "bind:desc");
+        let done = find(&runtime, first, "bind:done");
+
+        runtime.input(desc, "Fold the shirts too").unwrap();
+        runtime.input(done, "false").unwrap();
+
+        let instance = runtime.instance(first).unwrap();
+        assert_eq!(
+            instance.get("desc"),
+            Some(&Value::Str("Fold the shirts too".into()))
+        );
+        assert_eq!(instance.get("done"), Some(&Value::Bool(false)));
+        assert_eq!(
+            value_of(&runtime, desc).as_deref(),
+            Some("Fold the shirts too")
+        );
+        assert_eq!(value_of(&runtime, done).as_deref(), Some("false"));
+
+        // the fields are written back to the instance
+        let element = runtime.document().element(first).unwrap();
+        assert_eq!(element.get_str("desc"), Ok(Some("Fold the shirts too")));
+        assert_eq!(element.get_bool("done"), Ok(false));
+
+        assert_eq!(
+            runtime.take_events(),
+            [Event::new("submit", desc), Event::new("update", done)]
+        );
+        assert_eq!(runtime.take_events(), []);
+
+        assert_eq!(
+            runtime.input(done, "maybe"),
+            Err(StateError::InvalidValue(
+                trax_document::ValueError::InvalidValue {
+                    expected: Type::Bool,
+                    found: "maybe".to_string(),
+                    location: None,
+                }
+            ))
+        );
+        assert_eq!(
+            runtime.input(first, "x"),
+            Err(StateError::NotABinding(first))
+        );
+    }
+
+    #[test]
+    fn set() {
+        let mut runtime = Runtime::new(Document::new(TODO).unwrap());
+        let second = todos(&runtime)[1];
+        let read = find(&runtime, second, "read:created");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        runtime.subscribe(move |e| sink.lock().unwrap().push(e.clone()));
+
+        runtime
+            .set(second, "created", Value::Str("today".into()))
+            .unwrap();
+        assert_eq!(render(&runtime, read), "<read:created>today</read:created>");
+
+        let text = match runtime.document().element(read).unwrap().children().next() {
+            Some(EntityRef::Text(i)) => *i,
+            _ => panic!(),
+        };
+        assert_eq!(
+            events.lock().unwrap()[..],
+            [
+                ChangeEvent::AttributeChanged {
+                    element: second,
+                    name: "created".to_string()
+                },
+                ChangeEvent::TextChanged { text },
+            ]
+        );
+
+        runtime.set(second, "created", Value::Undefined).unwrap();
+        assert_eq!(render(&runtime, read), "<read:created />");
+
+        assert_eq!(
+            runtime.set(second, "done", Value::Int(1)),
+            Err(StateError::TypeMismatch {
+                field: "done".to_string(),
+                expected: Type::Bool,
+                found: Type::Int,
+            })
+        );
+        assert_eq!(
+            runtime.set(second, "missing", Value::Int(1)),
+            Err(StateError::UnknownField {
+                class: "Todo".to_string(),
+                field: "missing".to_string(),
+            })
+        );
+        assert_eq!(
+            runtime.set(read, "created", Value::Int(1)),
+            Err(StateError::NotAnInstance(read))
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        let src = r#"<document>
+    <class:Inner><let:label String /><read:label /><Slot /></class:Inner>
+    <class:Outer>
+        <let:label String />
+        <Inner label="inner"><read:label /></Inner>
+    </class:Outer>
+    <Outer label="outer" />
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+        let outer = todos(&runtime)[0];
+        let inner = find(&runtime, outer, "Inner");
+
+        assert_eq!(
+            render(&runtime, inner),
+            "<Inner label=\"inner\"><read:label>outer</read:label><read:label>inner</read:label><Slot /></Inner>"
+        );
+
+        let read = find(&runtime, inner, "read:label");
+        assert_eq!(
+            runtime
+                .instance_of(&EntityRef::Element(read))
+                .map(|i| i.class()),
+            Some("Outer")
+        );
+        assert_eq!(runtime.instance_of(&EntityRef::Element(outer)), None);
+    }
+
+    #[test]
+    fn unknown_field() {
+        let src = r#"<document>
+    <class:A><let:a Int /><read:b /></class:A>
+    <A a="1" />
+</document>"#;
+        let runtime = Runtime::new(Document::new(src).unwrap());
+
+        assert_eq!(
+            runtime.diagnostics()[0].message,
+            "`A` doesn't have a `b` field"
+        );
+    }
+}